@@ -1 +0,0 @@
-let M=0,K=null,O=`undefined`,S=`boolean`,T=`string`,L=1,V=`Object`,P=`utf-8`,X=4,R=`function`,_=359,I=Array,U=Array.isArray,Q=Error,W=JSON.stringify,Z=Object,Y=Reflect,N=Uint8Array,J=undefined;var z=((b,c,d)=>{a.wasm_bindgen__convert__closures__invoke1_mut__h4b9aff18f33ba13e(b,c,g(d))});var s=(a=>{const b=typeof a;if(b==`number`||b==S||a==K){return `${a}`};if(b==T){return `"${a}"`};if(b==`symbol`){const b=a.description;if(b==K){return `Symbol`}else{return `Symbol(${b})`}};if(b==R){const b=a.name;if(typeof b==T&&b.length>M){return `Function(${b})`}else{return `Function`}};if(U(a)){const b=a.length;let c=`[`;if(b>M){c+=s(a[M])};for(let d=L;d<b;d++){c+=`, `+ s(a[d])};c+=`]`;return c};const c=/\[object ([^\]]+)\]/.exec(toString.call(a));let d;if(c.length>L){d=c[L]}else{return toString.call(a)};if(d==V){try{return `Object(`+ W(a)+ `)`}catch(a){return V}};if(a instanceof Q){return `${a.name}: ${a.message}\n${a.stack}`};return d});var w=((b,c)=>{a.wasm_bindgen__convert__closures__invoke0_mut__h7098d61b459af520(b,c)});var E=((a,b)=>{});var g=(a=>{if(d===b.length)b.push(b.length+ L);const c=d;d=b[c];b[c]=a;return c});var f=(a=>{const b=c(a);e(a);return b});var H=(async(b)=>{if(a!==J)return a;if(typeof b===O){b=new URL(`krondor-org-53afb6ca2038ad60_bg.wasm`,import.meta.url)};const c=D();if(typeof b===T||typeof Request===R&&b instanceof Request||typeof URL===R&&b instanceof URL){b=fetch(b)};E(c);const {instance:d,module:e}=await C(await b,c);return F(d,e)});function B(b,c){try{return b.apply(this,c)}catch(b){a.__wbindgen_exn_store(g(b))}}var A=((a,b)=>{if(a===M){return c(b)}else{return r(a,b)}});var v=((b,c)=>{a.wasm_bindgen__convert__closures__invoke0_mut__h83b5d71f5a29569d(b,c)});var G=(b=>{if(a!==J)return a;const c=D();E(c);if(!(b instanceof WebAssembly.Module)){b=new WebAssembly.Module(b)};const d=new WebAssembly.Instance(b,c);return F(d,b)});var p=(()=>{if(o===K||o.byteLength===M){o=new Int32Array(a.memory.buffer)};return o});var n=(a=>a===J||a===K);var c=(a=>b[a]);var F=((b,c)=>{a=b.exports;H.__wbindgen_wasm_module=c;o=K;i=K;a.__wbindgen_start();return a});var D=(()=>{const b={};b.wbg={};b.wbg.__wbindgen_object_drop_ref=(a=>{f(a)});b.wbg.__wbindgen_object_clone_ref=(a=>{const b=c(a);return g(b)});b.wbg.__wbindgen_cb_drop=(a=>{const b=f(a).original;if(b.cnt--==L){b.a=M;return !0};const c=!1;return c});b.wbg.__wbindgen_string_get=((b,d)=>{const e=c(d);const f=typeof e===T?e:J;var g=n(f)?M:m(f,a.__wbindgen_malloc,a.__wbindgen_realloc);var i=h;p()[b/X+ L]=i;p()[b/X+ M]=g});b.wbg.__wbindgen_string_new=((a,b)=>{const c=r(a,b);return g(c)});b.wbg.__wbg_new_abda76e883ba8a5f=(()=>{const a=new Q();return g(a)});b.wbg.__wbg_stack_658279fe44541cf6=((b,d)=>{const e=c(d).stack;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_error_f851667af71bcfc6=((b,c)=>{var d=A(b,c);if(b!==M){a.__wbindgen_free(b,c)};console.error(d)});b.wbg.__wbindgen_jsval_eq=((a,b)=>{const d=c(a)===c(b);return d});b.wbg.__wbindgen_boolean_get=(a=>{const b=c(a);const d=typeof b===S?(b?L:M):2;return d});b.wbg.__wbindgen_is_string=(a=>{const b=typeof c(a)===T;return b});b.wbg.__wbindgen_is_undefined=(a=>{const b=c(a)===J;return b});b.wbg.__wbindgen_is_null=(a=>{const b=c(a)===K;return b});b.wbg.__wbindgen_is_falsy=(a=>{const b=!c(a);return b});b.wbg.__wbg_fetch_381efb5e862610fa=(a=>{const b=fetch(c(a));return g(b)});b.wbg.__wbg_instanceof_Window_9029196b662bc42a=(a=>{let b;try{b=c(a) instanceof Window}catch{b=!1}const d=b;return d});b.wbg.__wbg_document_f7ace2b956f30a4f=(a=>{const b=c(a).document;return n(b)?M:g(b)});b.wbg.__wbg_location_56243dba507f472d=(a=>{const b=c(a).location;return g(b)});b.wbg.__wbg_history_3c2280e6b2a9316e=function(){return B((a=>{const b=c(a).history;return g(b)}),arguments)};b.wbg.__wbg_origin_435635095a005ca4=((b,d)=>{const e=c(d).origin;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_scrollTo_3fa406312438ebdf=((a,b,d)=>{c(a).scrollTo(b,d)});b.wbg.__wbg_requestAnimationFrame_d082200514b6674d=function(){return B(((a,b)=>{const d=c(a).requestAnimationFrame(c(b));return d}),arguments)};b.wbg.__wbg_clearTimeout_220be2fa0577b342=((a,b)=>{c(a).clearTimeout(b)});b.wbg.__wbg_setTimeout_eb1a0d116c26d9f6=function(){return B(((a,b,d)=>{const e=c(a).setTimeout(c(b),d);return e}),arguments)};b.wbg.__wbg_body_674aec4c1c0910cd=(a=>{const b=c(a).body;return n(b)?M:g(b)});b.wbg.__wbg_createComment_6b5ea2660a7c961a=((a,b,d)=>{var e=A(b,d);const f=c(a).createComment(e);return g(f)});b.wbg.__wbg_createDocumentFragment_2570c0407199fba9=(a=>{const b=c(a).createDocumentFragment();return g(b)});b.wbg.__wbg_createElement_4891554b28d3388b=function(){return B(((a,b,d)=>{var e=A(b,d);const f=c(a).createElement(e);return g(f)}),arguments)};b.wbg.__wbg_createTextNode_2fd22cd7e543f938=((a,b,d)=>{var e=A(b,d);const f=c(a).createTextNode(e);return g(f)});b.wbg.__wbg_getElementById_cc0e0d931b0d9a28=((a,b,d)=>{var e=A(b,d);const f=c(a).getElementById(e);return n(f)?M:g(f)});b.wbg.__wbg_setinnerHTML_b089587252408b67=((a,b,d)=>{var e=A(b,d);c(a).innerHTML=e});b.wbg.__wbg_outerHTML_f7749ceff37b5832=((b,d)=>{const e=c(d).outerHTML;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_getAttribute_3d8fcc9eaea35a17=((b,d,e,f)=>{var g=A(e,f);const i=c(d).getAttribute(g);var j=n(i)?M:m(i,a.__wbindgen_malloc,a.__wbindgen_realloc);var k=h;p()[b/X+ L]=k;p()[b/X+ M]=j});b.wbg.__wbg_hasAttribute_a7d05690680041c9=((a,b,d)=>{var e=A(b,d);const f=c(a).hasAttribute(e);return f});b.wbg.__wbg_removeAttribute_d8404da431968808=function(){return B(((a,b,d)=>{var e=A(b,d);c(a).removeAttribute(e)}),arguments)};b.wbg.__wbg_scrollIntoView_53f9fa1bf8ecc14c=(a=>{c(a).scrollIntoView()});b.wbg.__wbg_setAttribute_e7e80b478b7b8b2f=function(){return B(((a,b,d,e,f)=>{var g=A(b,d);var h=A(e,f);c(a).setAttribute(g,h)}),arguments)};b.wbg.__wbg_before_208bff4b64d8f1f7=function(){return B(((a,b)=>{c(a).before(c(b))}),arguments)};b.wbg.__wbg_remove_48288e91662163dc=(a=>{c(a).remove()});b.wbg.__wbg_append_184b4e731e022bf0=function(){return B(((a,b)=>{c(a).append(c(b))}),arguments)};b.wbg.__wbg_target_f171e89c61e2bccf=(a=>{const b=c(a).target;return n(b)?M:g(b)});b.wbg.__wbg_defaultPrevented_1f845e37fc7c0e1d=(a=>{const b=c(a).defaultPrevented;return b});b.wbg.__wbg_cancelBubble_90d1c3aa2a76cbeb=(a=>{const b=c(a).cancelBubble;return b});b.wbg.__wbg_composedPath_cf1bb5b8bcff496f=(a=>{const b=c(a).composedPath();return g(b)});b.wbg.__wbg_preventDefault_24104f3f0a54546a=(a=>{c(a).preventDefault()});b.wbg.__wbg_ctrlKey_0a805df688b5bf42=(a=>{const b=c(a).ctrlKey;return b});b.wbg.__wbg_shiftKey_8a070ab6169b5fa4=(a=>{const b=c(a).shiftKey;return b});b.wbg.__wbg_altKey_6fc1761a6b7a406e=(a=>{const b=c(a).altKey;return b});b.wbg.__wbg_metaKey_d89287be4389a3c1=(a=>{const b=c(a).metaKey;return b});b.wbg.__wbg_button_7a095234b69de930=(a=>{const b=c(a).button;return b});b.wbg.__wbg_new_5bda5e7ff9fb1cbe=function(){return B((()=>{const a=new Range();return g(a)}),arguments)};b.wbg.__wbg_deleteContents_272c16a65410a5ec=function(){return B((a=>{c(a).deleteContents()}),arguments)};b.wbg.__wbg_setEndBefore_e56d93bff710d732=function(){return B(((a,b)=>{c(a).setEndBefore(c(b))}),arguments)};b.wbg.__wbg_setStartBefore_bc3ac4e3ed8bc3a9=function(){return B(((a,b)=>{c(a).setStartBefore(c(b))}),arguments)};b.wbg.__wbg_error_788ae33f81d3b84b=(a=>{console.error(c(a))});b.wbg.__wbg_warn_d60e832f9882c1b2=(a=>{console.warn(c(a))});b.wbg.__wbg_parentNode_9e53f8b17eb98c9d=(a=>{const b=c(a).parentNode;return n(b)?M:g(b)});b.wbg.__wbg_childNodes_64dab37cf9d252dd=(a=>{const b=c(a).childNodes;return g(b)});b.wbg.__wbg_previousSibling_7ddf39401682f643=(a=>{const b=c(a).previousSibling;return n(b)?M:g(b)});b.wbg.__wbg_nextSibling_304d9aac7c2774ae=(a=>{const b=c(a).nextSibling;return n(b)?M:g(b)});b.wbg.__wbg_settextContent_28d80502cf08bde7=((a,b,d)=>{var e=A(b,d);c(a).textContent=e});b.wbg.__wbg_appendChild_51339d4cde00ee22=function(){return B(((a,b)=>{const d=c(a).appendChild(c(b));return g(d)}),arguments)};b.wbg.__wbg_cloneNode_1f7cce4ea8b708e2=function(){return B((a=>{const b=c(a).cloneNode();return g(b)}),arguments)};b.wbg.__wbg_length_7aeee1534dbcb390=(a=>{const b=c(a).length;return b});b.wbg.__wbg_instanceof_HtmlAnchorElement_a293f072b6174b83=(a=>{let b;try{b=c(a) instanceof HTMLAnchorElement}catch{b=!1}const d=b;return d});b.wbg.__wbg_target_30dbd64e4d167ef8=((b,d)=>{const e=c(d).target;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_href_d4ca789f9d2389f6=((b,d)=>{const e=c(d).href;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_signal_4bd18fb489af2d4c=(a=>{const b=c(a).signal;return g(b)});b.wbg.__wbg_new_55c9955722952374=function(){return B((()=>{const a=new AbortController();return g(a)}),arguments)};b.wbg.__wbg_abort_654b796176d117aa=(a=>{c(a).abort()});b.wbg.__wbg_addEventListener_5651108fc3ffeb6e=function(){return B(((a,b,d,e)=>{var f=A(b,d);c(a).addEventListener(f,c(e))}),arguments)};b.wbg.__wbg_addEventListener_a5963e26cd7b176b=function(){return B(((a,b,d,e,f)=>{var g=A(b,d);c(a).addEventListener(g,c(e),c(f))}),arguments)};b.wbg.__wbg_removeEventListener_5de660c02ed784e4=function(){return B(((a,b,d,e)=>{var f=A(b,d);c(a).removeEventListener(f,c(e))}),arguments)};b.wbg.__wbg_removeEventListener_1fa0d9594cdb0b1d=function(){return B(((a,b,d,e,f)=>{var g=A(b,d);c(a).removeEventListener(g,c(e),c(f))}),arguments)};b.wbg.__wbg_setinnerText_1849424c2fdc16ec=((a,b,d)=>{var e=A(b,d);c(a).innerText=e});b.wbg.__wbg_fetch_8eaf01857a5bb21f=((a,b)=>{const d=c(a).fetch(c(b));return g(d)});b.wbg.__wbg_newwithstrandinit_cad5cd6038c7ff5d=function(){return B(((a,b,d)=>{var e=A(a,b);const f=new Request(e,c(d));return g(f)}),arguments)};b.wbg.__wbg_setdata_8eae47221c7bc167=((a,b,d)=>{var e=A(b,d);c(a).data=e});b.wbg.__wbg_instanceof_Response_fc4327dbfcdf5ced=(a=>{let b;try{b=c(a) instanceof Response}catch{b=!1}const d=b;return d});b.wbg.__wbg_url_8503de97f69da463=((b,d)=>{const e=c(d).url;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_status_ac85a3142a84caa2=(a=>{const b=c(a).status;return b});b.wbg.__wbg_headers_b70de86b8e989bc0=(a=>{const b=c(a).headers;return g(b)});b.wbg.__wbg_arrayBuffer_288fb3538806e85c=function(){return B((a=>{const b=c(a).arrayBuffer();return g(b)}),arguments)};b.wbg.__wbg_text_a667ac1770538491=function(){return B((a=>{const b=c(a).text();return g(b)}),arguments)};b.wbg.__wbg_currentTime_d0312e40ced1fba1=(a=>{const b=c(a).currentTime;return b});b.wbg.__wbg_setcurrentTime_4f41f1d4da0bab1c=((a,b)=>{c(a).currentTime=b});b.wbg.__wbg_duration_ad6dcd550253aa4a=(a=>{const b=c(a).duration;return b});b.wbg.__wbg_paused_665d4269f49290ab=(a=>{const b=c(a).paused;return b});b.wbg.__wbg_pause_6db8c1a3a63d9f85=function(){return B((a=>{c(a).pause()}),arguments)};b.wbg.__wbg_play_3557bf04f90cf7c5=function(){return B((a=>{const b=c(a).play();return g(b)}),arguments)};b.wbg.__wbg_new_1eead62f64ca15ce=function(){return B((()=>{const a=new Headers();return g(a)}),arguments)};b.wbg.__wbg_append_fda9e3432e3e88da=function(){return B(((a,b,d,e,f)=>{var g=A(b,d);var h=A(e,f);c(a).append(g,h)}),arguments)};b.wbg.__wbg_setmax_0f6be8507eeffa74=((a,b,d)=>{var e=A(b,d);c(a).max=e});b.wbg.__wbg_setmin_b18333b89bad86e5=((a,b,d)=>{var e=A(b,d);c(a).min=e});b.wbg.__wbg_setstep_ed5f2dd39f6b62c1=((a,b,d)=>{var e=A(b,d);c(a).step=e});b.wbg.__wbg_settype_ed9a0cf484870612=((a,b,d)=>{var e=A(b,d);c(a).type=e});b.wbg.__wbg_value_9423da9d988ee8cf=((b,d)=>{const e=c(d).value;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_setvalue_1f95e61cbc382f7f=((a,b,d)=>{var e=A(b,d);c(a).value=e});b.wbg.__wbg_append_5739c748cab384b5=function(){return B(((a,b)=>{c(a).append(c(b))}),arguments)};b.wbg.__wbg_pushState_1145414a47c0b629=function(){return B(((a,b,d,e,f,g)=>{var h=A(d,e);var i=A(f,g);c(a).pushState(c(b),h,i)}),arguments)};b.wbg.__wbg_replaceState_2e530b05e604adc4=function(){return B(((a,b,d,e,f,g)=>{var h=A(d,e);var i=A(f,g);c(a).replaceState(c(b),h,i)}),arguments)};b.wbg.__wbg_sethref_e5626365d7354fea=function(){return B(((a,b,d)=>{var e=A(b,d);c(a).href=e}),arguments)};b.wbg.__wbg_origin_50aa482fa6784a0a=function(){return B(((b,d)=>{const e=c(d).origin;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f}),arguments)};b.wbg.__wbg_pathname_c8fd5c498079312d=function(){return B(((b,d)=>{const e=c(d).pathname;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f}),arguments)};b.wbg.__wbg_search_6c3c472e076ee010=function(){return B(((b,d)=>{const e=c(d).search;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f}),arguments)};b.wbg.__wbg_hash_a1a795b89dda8e3d=function(){return B(((b,d)=>{const e=c(d).hash;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f}),arguments)};b.wbg.__wbg_instanceof_ShadowRoot_b64337370f59fe2d=(a=>{let b;try{b=c(a) instanceof ShadowRoot}catch{b=!1}const d=b;return d});b.wbg.__wbg_host_e1c47c33975060d3=(a=>{const b=c(a).host;return g(b)});b.wbg.__wbg_origin_077fb2b471e27edc=((b,d)=>{const e=c(d).origin;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_pathname_57290e07c6bc0683=((b,d)=>{const e=c(d).pathname;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_search_2ff3bb9114e0ca34=((b,d)=>{const e=c(d).search;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_searchParams_f64cb84025011613=(a=>{const b=c(a).searchParams;return g(b)});b.wbg.__wbg_hash_2b57e787945b2db0=((b,d)=>{const e=c(d).hash;const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbg_newwithbase_79b8cac27ce631ac=function(){return B(((a,b,c,d)=>{var e=A(a,b);var f=A(c,d);const h=new URL(e,f);return g(h)}),arguments)};b.wbg.__wbindgen_is_object=(a=>{const b=c(a);const d=typeof b===`object`&&b!==K;return d});b.wbg.__wbindgen_is_function=(a=>{const b=typeof c(a)===R;return b});b.wbg.__wbg_get_44be0491f933a435=((a,b)=>{const d=c(a)[b>>>M];return g(d)});b.wbg.__wbg_length_fff51ee6522a1a18=(a=>{const b=c(a).length;return b});b.wbg.__wbg_newnoargs_581967eacc0e2604=((a,b)=>{var c=A(a,b);const d=new Function(c);return g(d)});b.wbg.__wbg_next_526fc47e980da008=(a=>{const b=c(a).next;return g(b)});b.wbg.__wbg_next_ddb3312ca1c4e32a=function(){return B((a=>{const b=c(a).next();return g(b)}),arguments)};b.wbg.__wbg_done_5c1f01fb660d73b5=(a=>{const b=c(a).done;return b});b.wbg.__wbg_value_1695675138684bd5=(a=>{const b=c(a).value;return g(b)});b.wbg.__wbg_iterator_97f0c81209c6c35a=(()=>{const a=Symbol.iterator;return g(a)});b.wbg.__wbg_get_97b561fb56f034b5=function(){return B(((a,b)=>{const d=Y.get(c(a),c(b));return g(d)}),arguments)};b.wbg.__wbg_call_cb65541d95d71282=function(){return B(((a,b)=>{const d=c(a).call(c(b));return g(d)}),arguments)};b.wbg.__wbg_new_b51585de1b234aff=(()=>{const a=new Z();return g(a)});b.wbg.__wbg_self_1ff1d729e9aae938=function(){return B((()=>{const a=self.self;return g(a)}),arguments)};b.wbg.__wbg_window_5f4faef6c12b79ec=function(){return B((()=>{const a=window.window;return g(a)}),arguments)};b.wbg.__wbg_globalThis_1d39714405582d3c=function(){return B((()=>{const a=globalThis.globalThis;return g(a)}),arguments)};b.wbg.__wbg_global_651f05c6a0944d1c=function(){return B((()=>{const a=global.global;return g(a)}),arguments)};b.wbg.__wbg_decodeURI_66e5ecae283e4c53=function(){return B(((a,b)=>{var c=A(a,b);const d=decodeURI(c);return g(d)}),arguments)};b.wbg.__wbg_encodeURI_07ec6f7516a63776=((a,b)=>{var c=A(a,b);const d=encodeURI(c);return g(d)});b.wbg.__wbg_isArray_4c24b343cb13cfb1=(a=>{const b=U(c(a));return b});b.wbg.__wbg_call_01734de55d61e11d=function(){return B(((a,b,d)=>{const e=c(a).call(c(b),c(d));return g(e)}),arguments)};b.wbg.__wbg_now_9c5990bda04c7e53=(()=>{const a=Date.now();return a});b.wbg.__wbg_is_205d914af04a8faa=((a,b)=>{const d=Z.is(c(a),c(b));return d});b.wbg.__wbg_resolve_53698b95aaf7fcf8=(a=>{const b=Promise.resolve(c(a));return g(b)});b.wbg.__wbg_then_f7e06ee3c11698eb=((a,b)=>{const d=c(a).then(c(b));return g(d)});b.wbg.__wbg_then_b2267541e2a73865=((a,b,d)=>{const e=c(a).then(c(b),c(d));return g(e)});b.wbg.__wbg_buffer_085ec1f694018c4f=(a=>{const b=c(a).buffer;return g(b)});b.wbg.__wbg_newwithbyteoffsetandlength_6da8e527659b86aa=((a,b,d)=>{const e=new N(c(a),b>>>M,d>>>M);return g(e)});b.wbg.__wbg_new_8125e318e6245eed=(a=>{const b=new N(c(a));return g(b)});b.wbg.__wbg_set_5cf90238115182c3=((a,b,d)=>{c(a).set(c(b),d>>>M)});b.wbg.__wbg_length_72e2208bbc0efc61=(a=>{const b=c(a).length;return b});b.wbg.__wbg_has_c5fcd020291e56b8=function(){return B(((a,b)=>{const d=Y.has(c(a),c(b));return d}),arguments)};b.wbg.__wbg_set_092e06b0f9d71865=function(){return B(((a,b,d)=>{const e=Y.set(c(a),c(b),c(d));return e}),arguments)};b.wbg.__wbg_stringify_e25465938f3f611f=function(){return B((a=>{const b=W(c(a));return g(b)}),arguments)};b.wbg.__wbindgen_debug_string=((b,d)=>{const e=s(c(d));const f=m(e,a.__wbindgen_malloc,a.__wbindgen_realloc);const g=h;p()[b/X+ L]=g;p()[b/X+ M]=f});b.wbg.__wbindgen_throw=((a,b)=>{throw new Q(r(a,b))});b.wbg.__wbindgen_memory=(()=>{const b=a.memory;return g(b)});b.wbg.__wbindgen_closure_wrapper790=((a,b,c)=>{const d=t(a,b,_,u);return g(d)});b.wbg.__wbindgen_closure_wrapper792=((a,b,c)=>{const d=t(a,b,_,u);return g(d)});b.wbg.__wbindgen_closure_wrapper795=((a,b,c)=>{const d=t(a,b,_,v);return g(d)});b.wbg.__wbindgen_closure_wrapper1966=((a,b,c)=>{const d=t(a,b,822,w);return g(d)});b.wbg.__wbindgen_closure_wrapper2159=((a,b,c)=>{const d=t(a,b,915,x);return g(d)});b.wbg.__wbindgen_closure_wrapper2316=((a,b,c)=>{const d=t(a,b,953,y);return g(d)});b.wbg.__wbindgen_closure_wrapper2602=((a,b,c)=>{const d=t(a,b,1082,z);return g(d)});return b});var C=(async(a,b)=>{if(typeof Response===R&&a instanceof Response){if(typeof WebAssembly.instantiateStreaming===R){try{return await WebAssembly.instantiateStreaming(a,b)}catch(b){if(a.headers.get(`Content-Type`)!=`application/wasm`){console.warn(`\`WebAssembly.instantiateStreaming\` failed because your server does not serve wasm with \`application/wasm\` MIME type. Falling back to \`WebAssembly.instantiate\` which is slower. Original error:\\n`,b)}else{throw b}}};const c=await a.arrayBuffer();return await WebAssembly.instantiate(c,b)}else{const c=await WebAssembly.instantiate(a,b);if(c instanceof WebAssembly.Instance){return {instance:c,module:a}}else{return c}}});var y=((b,c)=>{a.wasm_bindgen__convert__closures__invoke0_mut__hdcaa221a3b0961dd(b,c)});var x=((b,c,d)=>{a.wasm_bindgen__convert__closures__invoke1_mut__hb6e1adff5389c9d3(b,c,g(d))});var e=(a=>{if(a<132)return;b[a]=d;d=a});var t=((b,c,d,e)=>{const f={a:b,b:c,cnt:L,dtor:d};const g=(...b)=>{f.cnt++;const c=f.a;f.a=M;try{return e(c,f.b,...b)}finally{if(--f.cnt===M){a.__wbindgen_export_2.get(f.dtor)(c,f.b)}else{f.a=c}}};g.original=f;return g});var m=((a,b,c)=>{if(c===J){const c=k.encode(a);const d=b(c.length,L)>>>M;j().subarray(d,d+ c.length).set(c);h=c.length;return d};let d=a.length;let e=b(d,L)>>>M;const f=j();let g=M;for(;g<d;g++){const b=a.charCodeAt(g);if(b>127)break;f[e+ g]=b};if(g!==d){if(g!==M){a=a.slice(g)};e=c(e,d,d=g+ a.length*3,L)>>>M;const b=j().subarray(e+ g,e+ d);const f=l(a,b);g+=f.written};h=g;return e});var j=(()=>{if(i===K||i.byteLength===M){i=new N(a.memory.buffer)};return i});var r=((a,b)=>{a=a>>>M;return q.decode(j().subarray(a,a+ b))});var u=((b,c,d)=>{a.wasm_bindgen__convert__closures__invoke1_mut__h26c9023044639f2d(b,c,g(d))});let a;const b=new I(128).fill(J);b.push(J,K,!0,!1);let d=b.length;let h=M;let i=K;const k=typeof TextEncoder!==O?new TextEncoder(P):{encode:()=>{throw Q(`TextEncoder not available`)}};const l=typeof k.encodeInto===R?((a,b)=>k.encodeInto(a,b)):((a,b)=>{const c=k.encode(a);b.set(c);return {read:a.length,written:c.length}});let o=K;const q=typeof TextDecoder!==O?new TextDecoder(P,{ignoreBOM:!0,fatal:!0}):{decode:()=>{throw Q(`TextDecoder not available`)}};if(typeof TextDecoder!==O){q.decode()};export default H;export{G as initSync}
\ No newline at end of file
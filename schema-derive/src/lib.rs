@@ -0,0 +1,319 @@
+//! Derive macros for the typed schemas in `krondor-org`.
+//! The generated code refers to `crate::types::schema`, so these are only
+//! meant to be used from within that crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, Fields, Lit, LitStr, Meta, Token,
+    Type,
+};
+
+/// Derive `Schema`, along with the `From`/`TryFrom` conversions to and from Json it requires.
+///
+/// - `#[schema(name = "...")]` sets the schema name (required)
+/// - `#[schema(extensions("md", ...))]` sets the valid extensions
+/// - `#[schema(directories)]` marks the schema as tagging directories
+///
+/// Each field's doc comment becomes its description in `fields()`.
+/// `String` fields are required and `Option<String>` fields are optional.
+/// Any other type is parsed from a string with `TryFrom<&str>` -- see `SchemaEnum`.
+/// Mark those `#[schema(default)]` to fall back to `Default` when they're missing.
+#[proc_macro_derive(Schema, attributes(schema))]
+pub fn derive_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    schema(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive `Display` and `TryFrom<&str>` for an enum of unit variants.
+///
+/// Variants are written in snake_case unless renamed with `#[schema(rename = "...")]`.
+/// Parsing is case insensitive, and treats underscores and spaces the same.
+#[proc_macro_derive(SchemaEnum, attributes(schema))]
+pub fn derive_schema_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    schema_enum(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// How a field is read from and written to Json
+enum FieldKind {
+    /// A required string
+    String,
+    /// An optional string
+    Option,
+    /// Parsed from a string with `TryFrom<&str>`
+    Parsed { ty: Box<Type>, default: bool },
+}
+
+fn schema(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(ident, "Schema requires named fields")),
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "Schema can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut name = None;
+    let mut extensions = Vec::new();
+    let mut directories = false;
+    for attr in schema_attrs(&input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("extensions") {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                extensions.extend(Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?);
+            } else if meta.path.is_ident("directories") {
+                directories = true;
+            } else {
+                return Err(meta.error("unsupported schema attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    let name = name.ok_or(Error::new_spanned(
+        ident,
+        "missing #[schema(name = \"...\")] attribute",
+    ))?;
+
+    let mut into_value = Vec::new();
+    let mut try_from_value = Vec::new();
+    let mut field_idents = Vec::new();
+    let mut descriptions = Vec::new();
+    for field in fields.iter() {
+        let field_ident = field.ident.as_ref().expect("named field");
+        let key = field_ident.to_string();
+        let kind = field_kind(field.ty.clone(), &field.attrs)?;
+
+        into_value.push(match &kind {
+            FieldKind::String => quote! {
+                map.insert(#key.to_string(), serde_json::Value::String(val.#field_ident));
+            },
+            FieldKind::Option => quote! {
+                if let Some(#field_ident) = val.#field_ident {
+                    map.insert(#key.to_string(), serde_json::Value::String(#field_ident));
+                }
+            },
+            FieldKind::Parsed { .. } => quote! {
+                map.insert(
+                    #key.to_string(),
+                    serde_json::Value::String(val.#field_ident.to_string()),
+                );
+            },
+        });
+
+        try_from_value.push(match &kind {
+            FieldKind::String => quote! {
+                let #field_ident = value[#key]
+                    .as_str()
+                    .ok_or(SchemaError::MissingField(#key.to_string()))?
+                    .to_string();
+            },
+            FieldKind::Option => quote! {
+                let #field_ident = value[#key].as_str().map(String::from);
+            },
+            FieldKind::Parsed { ty, default } => {
+                let missing = if *default {
+                    quote! { <#ty as Default>::default() }
+                } else {
+                    quote! { return Err(SchemaError::MissingField(#key.to_string())) }
+                };
+                quote! {
+                    let #field_ident = match value[#key].as_str() {
+                        Some(#field_ident) => <#ty>::try_from(#field_ident).map_err(|_| {
+                            SchemaError::InvalidField(#key.to_string(), #field_ident.to_string())
+                        })?,
+                        None => #missing,
+                    };
+                }
+            }
+        });
+
+        descriptions.push(doc_comment(&field.attrs));
+        field_idents.push(field_ident);
+    }
+    let keys = field_idents.iter().map(|ident| ident.to_string());
+
+    Ok(quote! {
+        impl From<#ident> for serde_json::Value {
+            fn from(val: #ident) -> Self {
+                let mut map = serde_json::Map::new();
+                #(#into_value)*
+                serde_json::Value::Object(map)
+            }
+        }
+
+        impl TryFrom<serde_json::Value> for #ident {
+            type Error = crate::types::schema::SchemaError;
+            fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+                use crate::types::schema::SchemaError;
+                #(#try_from_value)*
+                Ok(Self { #(#field_idents),* })
+            }
+        }
+
+        impl crate::types::schema::Schema for #ident {
+            const NAME: &'static str = #name;
+
+            fn valid_extensions() -> Vec<&'static str> {
+                vec![#(#extensions),*]
+            }
+
+            fn tags_directories() -> bool {
+                #directories
+            }
+
+            fn fields() -> Vec<(&'static str, &'static str)> {
+                vec![#((#keys, #descriptions)),*]
+            }
+        }
+    })
+}
+
+fn schema_enum(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "SchemaEnum can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut variant_idents = Vec::new();
+    let mut names = Vec::new();
+    for variant in variants.iter() {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "SchemaEnum variants can't have fields",
+            ));
+        }
+        let mut name = LitStr::new(&snake_case(&variant.ident.to_string()), Span::call_site());
+        for attr in schema_attrs(&variant.attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    name = meta.value()?.parse::<LitStr>()?;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported schema attribute"))
+                }
+            })?;
+        }
+        variant_idents.push(&variant.ident);
+        names.push(name);
+    }
+    let normalized = names.iter().map(|name| normalize(&name.value()));
+    let type_name = snake_case(&ident.to_string());
+
+    Ok(quote! {
+        impl std::fmt::Display for #ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "{}",
+                    match self {
+                        #(Self::#variant_idents => #names,)*
+                    }
+                )
+            }
+        }
+
+        impl TryFrom<&str> for #ident {
+            type Error = crate::types::schema::SchemaError;
+
+            fn try_from(val: &str) -> Result<Self, Self::Error> {
+                let variant = match val.trim().to_lowercase().replace('_', " ").as_str() {
+                    #(#normalized => Self::#variant_idents,)*
+                    _ => {
+                        return Err(crate::types::schema::SchemaError::InvalidField(
+                            #type_name.to_string(),
+                            val.to_string(),
+                        ))
+                    }
+                };
+                Ok(variant)
+            }
+        }
+    })
+}
+
+fn schema_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("schema"))
+}
+
+fn field_kind(ty: Type, attrs: &[Attribute]) -> syn::Result<FieldKind> {
+    let last_segment = match &ty {
+        Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    };
+    let mut default = false;
+    for attr in schema_attrs(attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("default") {
+                default = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported schema attribute"))
+            }
+        })?;
+    }
+    Ok(match last_segment.as_deref() {
+        Some("String") => FieldKind::String,
+        Some("Option") => FieldKind::Option,
+        _ => FieldKind::Parsed {
+            ty: Box::new(ty),
+            default,
+        },
+    })
+}
+
+/// Join the lines of an item's doc comment into a single line
+fn doc_comment(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) if meta.path.is_ident("doc") => match &meta.value {
+                Expr::Lit(expr) => match &expr.lit {
+                    Lit::Str(doc) => Some(doc.value().trim().to_string()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn snake_case(ident: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in ident.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// Variants are matched case insensitively, treating underscores as spaces
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase().replace('_', " ")
+}
@@ -1,49 +1,129 @@
-use std::fmt::{self, Display};
-
 pub use super::args::{Args, Command, Parser};
 use super::config::{Config, ConfigError};
+use super::error::ErrorReport;
 use super::ops::{
-    device_subcommand, health, init, pull, push, stage, tag, DeviceSubcommandError, HealthError,
-    InitError, PullError, PushError, StageError, TagError,
+    device_subcommand, export, health, init, keygen, migrate, preview, publish, pull, push, stage,
+    tag, tag_from_file, DeviceSubcommandError, ExportError, HealthError, InitError, MigrateError,
+    PreviewError, Privacy, PublishError, PullError, PushError, StageError, TagError,
 };
+use super::output::OutputFormat;
+use crate::types::feed::Site;
+use crate::types::Publication;
 
 pub struct App;
 
 impl App {
     pub async fn run() {
-        tracing_subscriber::fmt::init();
-        capture_error(Self::run_result().await);
+        // Logs go to stderr, so what a command reports can be piped
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .init();
+        let args = Args::parse();
+        let output = args.output;
+        capture_error(output, Self::run_result(args).await);
     }
 
-    async fn run_result() -> Result<(), AppError> {
-        let args = Args::parse();
+    async fn run_result(args: Args) -> Result<(), AppError> {
         let config = Config::parse_args(&args)?;
+        let output = args.output;
         match args.command {
             Command::Device { subcommand } => {
-                device_subcommand(&config, &subcommand)?;
+                output.print(&device_subcommand(&config, &subcommand)?)?;
             }
             Command::Health => {
-                health(&config).await?;
+                output.print(&health(&config).await?)?;
             }
             Command::Init => {
-                init(&config)?;
+                output.print(&init(&config)?)?;
+            }
+            Command::Migrate => {
+                output.print(&migrate(&config).await?)?;
             }
             Command::Pull => {
-                pull(&config).await?;
+                output.print(&pull(&config).await?)?;
             }
-            Command::Stage => {
-                stage(&config).await?;
+            Command::Stage {
+                strip_front_matter,
+                encrypt,
+                recipient,
+                decrypt,
+            } => {
+                let privacy = Privacy {
+                    encrypt,
+                    recipients: recipient,
+                    decrypt,
+                };
+                output.print(&stage(&config, strip_front_matter, &privacy).await?)?;
             }
             Command::Stat => {
                 let change_log = config.change_log()?;
-                let displayable_change_log = change_log.displayable();
-                println!("{}", displayable_change_log);
+                output.print(&change_log.displayable())?;
+            }
+            Command::Keygen => {
+                output.print(&keygen())?;
+            }
+            Command::Tag {
+                name,
+                path,
+                value,
+                interactive: _,
+                from_file,
+            } => {
+                let report = match (from_file, name, path) {
+                    (Some(file), _, _) => tag_from_file(&config, &file).await?,
+                    (None, Some(name), Some(path)) => {
+                        tag(&config, &name, &path, value.as_deref()).await?
+                    }
+                    // clap requires a name and path unless tagging from a file
+                    _ => unreachable!(),
+                };
+                output.print(&report)?;
+            }
+            Command::Publish { path, draft, at } => {
+                let publication = match (draft, at) {
+                    (true, _) => Publication::Draft,
+                    (false, Some(at)) => Publication::Scheduled { at },
+                    (false, None) => Publication::Published,
+                };
+                output.print(&publish(&config, &path, publication).await?)?;
             }
-            Command::Tag { name, path, value } => {
-                tag(&config, &name, &path, &value).await?;
+            Command::Push {
+                minimal,
+                force,
+                sharded,
+                flat,
+                site_url,
+                site_title,
+                site_author,
+                site_email,
+                site_image,
+                site_category,
+            } => {
+                let site = site_url.map(|url| Site {
+                    title: site_title,
+                    url,
+                    author: site_author,
+                    email: site_email,
+                    image: site_image,
+                    category: site_category,
+                });
+                // Unless asked to switch, the manifest keeps its current layout
+                let sharded = (sharded || flat).then_some(sharded);
+                output.print(&push(&config, minimal, force, sharded, site.as_ref()).await?)?;
             }
-            Command::Push { minimal, force } => {
-                push(&config, minimal, force).await?;
+            Command::Preview {
+                port,
+                static_dir,
+                site_title,
+            } => {
+                preview(&config, port, &static_dir, &site_title, output).await?;
+            }
+            Command::Export {
+                out,
+                static_dir,
+                site_title,
+            } => {
+                output.print(&export(&config, &out, &static_dir, &site_title).await?)?;
             }
         }
         Ok(())
@@ -52,45 +132,93 @@ impl App {
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
+    #[error("{0}")]
     Config(#[from] ConfigError),
+    #[error("{0}")]
     DeviceSubcommand(#[from] DeviceSubcommandError),
+    #[error("{0}")]
     Init(#[from] InitError),
+    #[error("{0}")]
+    Migrate(#[from] MigrateError),
+    #[error("{0}")]
     Health(#[from] HealthError),
+    #[error("{0}")]
     Stage(#[from] StageError),
+    #[error("{0}")]
+    Publish(#[from] PublishError),
+    #[error("{0}")]
     Push(#[from] PushError),
+    #[error("{0}")]
     Tag(#[from] TagError),
+    #[error("{0}")]
     Pull(#[from] PullError),
+    #[error("{0}")]
+    Export(#[from] ExportError),
+    #[error("{0}")]
+    Preview(#[from] PreviewError),
+    #[error("{0}")]
+    Output(#[from] serde_json::Error),
+}
+
+impl AppError {
+    /// A stable name for what failed, as `<command>.<reason>`, for scripts to match on
+    fn code(&self) -> &'static str {
+        match self {
+            Self::Config(e) => e.code(),
+            Self::DeviceSubcommand(e) => e.code(),
+            Self::Init(e) => e.code(),
+            Self::Migrate(e) => e.code(),
+            Self::Health(e) => e.code(),
+            Self::Stage(e) => e.code(),
+            Self::Publish(e) => e.code(),
+            Self::Push(e) => e.code(),
+            Self::Tag(e) => e.code(),
+            Self::Pull(e) => e.code(),
+            Self::Export(e) => e.code(),
+            Self::Preview(e) => e.code(),
+            Self::Output(_) => "output.serialize",
+        }
+    }
 }
 
-fn capture_error<T>(result: Result<T, AppError>) {
-    match result {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("{}", e);
+/// Report an error to whoever ran us, and exit with its category's code
+fn capture_error<T>(output: OutputFormat, result: Result<T, AppError>) {
+    let Err(e) = result else {
+        return;
+    };
+    let report = ErrorReport::new(e.code(), &e);
+    match output {
+        OutputFormat::Text => eprint!("{}", report),
+        OutputFormat::Json => {
+            // Reports are plain data, so serializing them never fails
+            if let Ok(json) = serde_json::to_string_pretty(&report) {
+                println!("{}", json);
+            }
         }
     }
+    std::process::exit(report.exit_code());
 }
 
-impl Display for AppError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let error_message = format!("{:?}", self);
-        let red = "\x1b[31;1m"; // Bright red
-        let reset = "\x1b[0m"; // Reset to default color
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
 
-        // ASCII art for visual impact (optional)
-        let skull = "
-        ☠️ ☠️ ☠️
-        ";
+    use super::*;
 
-        write!(
-            f,
-            "{}{}{}\n{}{}\n{}",
-            red,
-            skull,
-            reset, // Skull in red
-            red,
-            error_message, // Error message in red
-            reset          // Reset color
-        )
+    #[test]
+    fn errors_have_stable_codes() {
+        assert_eq!(
+            AppError::from(PushError::NoChanges).code(),
+            "push.no_changes"
+        );
+        let mismatch = PushError::MissmatchedRootCid(Cid::default(), Cid::default());
+        assert_eq!(AppError::from(mismatch).code(), "push.mismatched_root_cid");
+        // Failures pushing a single object are named for why they failed
+        let error = PushError::PushFailed("a.txt".into(), Box::new(PushError::NoChanges));
+        assert_eq!(AppError::from(error).code(), "push.no_changes");
+        assert_eq!(
+            AppError::from(ConfigError::NoSetDevice).code(),
+            "config.no_set_device"
+        );
     }
 }
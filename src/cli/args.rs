@@ -1,10 +1,15 @@
 use std::path::PathBuf;
 
-use clap::{command, Subcommand};
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
 use ethers::types::Address;
 
 use url::Url;
 
+use crate::types::encryption::PublicKey;
+
+use super::output::OutputFormat;
+
 pub use clap::Parser;
 
 #[derive(Parser, Debug)]
@@ -20,6 +25,9 @@ pub struct Args {
     /// Private Secp256k1 Admin Key (should be contract deployer)
     #[clap(long)]
     pub admin_key: Option<String>,
+    /// How to report what a command did
+    #[clap(long, global = true, value_enum, default_value_t)]
+    pub output: OutputFormat,
 }
 
 // TODO: balance this
@@ -36,21 +44,56 @@ pub enum Command {
     Health,
     /// Initialize a new space to pull and work on changes
     Init,
+    /// Rewrite the remote manifest in the current manifest format and push it
+    Migrate,
     /// Pull the remote to the local dot directory -- overwrites any changes
     Pull,
     /// Stage changes against the local ipfs instance -- may be run mutliple times in a row
-    Stage,
+    Stage {
+        // Hide the front matter of staged markdown files when they're rendered
+        #[clap(long, short, default_value = "false")]
+        strip_front_matter: bool,
+        // Encrypt these paths, so only this device and the recipients can read them
+        #[clap(long, short)]
+        encrypt: Vec<PathBuf>,
+        // Public keys to encrypt paths for, in addition to this device's own
+        #[clap(long, short, requires = "encrypt")]
+        recipient: Vec<PublicKey>,
+        // Stop encrypting these paths, publishing them in the clear
+        #[clap(long, conflicts_with_all = ["encrypt", "recipient"])]
+        decrypt: Vec<PathBuf>,
+    },
     /// Stat changes
     Stat,
+    /// Generate a key pair for a reader of private objects
+    /// Pass the public key to `stage --recipient`, and give the secret key to the reader
+    Keygen,
     /// Tag an object with one of our schemas. These effect the schema definitions in the dot directory
     /// Changes to schemas will be reflected in the next push
     Tag {
-        #[clap(long, short)]
-        name: String,
+        #[clap(long, short, required_unless_present = "from_file")]
+        name: Option<String>,
+        #[clap(long, short, required_unless_present = "from_file")]
+        path: Option<PathBuf>,
+        #[clap(long, short, required_unless_present_any = ["interactive", "from_file"])]
+        value: Option<String>,
+        // Prompt for each field of the schema instead of passing a value
+        #[clap(long, short, conflicts_with = "value", default_value = "false")]
+        interactive: bool,
+        // Tag many paths at once from a .json or .csv file, as a single change
+        #[clap(long, short, conflicts_with_all = ["name", "path", "value", "interactive"])]
+        from_file: Option<PathBuf>,
+    },
+    /// Set when an object is shown to readers. Without flags, it's published on the next push
+    Publish {
         #[clap(long, short)]
         path: PathBuf,
+        // Keep the object out of the published manifest
+        #[clap(long, short, default_value = "false", conflicts_with = "at")]
+        draft: bool,
+        // Push the object, but have the site hide it until this time (RFC 3339, e.g. 2024-06-01T09:00:00Z)
         #[clap(long, short)]
-        value: String,
+        at: Option<DateTime<Utc>>,
     },
     /// Squash and sync changes with the remote
     Push {
@@ -60,6 +103,55 @@ pub enum Command {
         // Try to push all objects, even if they haven't changed
         #[clap(long, short, default_value = "false")]
         force: bool,
+        // Write the manifest as a tree of per-directory shards from now on, so readers can
+        //  fetch paths lazily and only changed shards are uploaded. Without this or --flat,
+        //  the manifest keeps the layout it was last pushed in
+        #[clap(long, short, default_value = "false")]
+        sharded: bool,
+        // Write the manifest as a single block from now on
+        #[clap(long, default_value = "false", conflicts_with = "sharded")]
+        flat: bool,
+        // Where the site is hosted. Feeds of writing and audio are only published if this is set
+        #[clap(long, env = "SITE_URL")]
+        site_url: Option<Url>,
+        // The name feeds give the site
+        #[clap(long, env = "SITE_TITLE", default_value = "Krondor")]
+        site_title: String,
+        // Who feeds credit for the site's content
+        #[clap(long, env = "SITE_AUTHOR")]
+        site_author: Option<String>,
+        // How podcast directories can reach the author
+        #[clap(long, env = "SITE_EMAIL")]
+        site_email: Option<String>,
+        // Cover art for the podcast feed
+        #[clap(long, env = "SITE_IMAGE")]
+        site_image: Option<Url>,
+        // The podcast's Apple Podcasts category, like `Arts` or `Arts > Books`
+        #[clap(long, env = "SITE_CATEGORY")]
+        site_category: Option<String>,
+    },
+    /// Serve the site as it will look once staged changes are pushed, on localhost
+    Preview {
+        #[clap(long, short, default_value = "8000")]
+        port: u16,
+        // Where the site's static markdown and icons live
+        #[clap(long, default_value = "static")]
+        static_dir: PathBuf,
+        // The name pages give the site
+        #[clap(long, env = "SITE_TITLE", default_value = "Krondor")]
+        site_title: String,
+    },
+    /// Render the pushed manifest to a tree of static html, with relative links throughout
+    Export {
+        // The directory to write the site to. It must be empty or not exist yet
+        #[clap(long, short)]
+        out: PathBuf,
+        // Where the site's static markdown and icons live
+        #[clap(long, default_value = "static")]
+        static_dir: PathBuf,
+        // The name pages give the site
+        #[clap(long, env = "SITE_TITLE", default_value = "Krondor")]
+        site_title: String,
     },
 }
 
@@ -81,6 +173,9 @@ pub enum DeviceSubcommand {
         ipfs_url: Url,
         #[clap(long)]
         ipfs_gateway_url: Url,
+        // Gateways to fall back on, in order, when the first one fails
+        #[clap(long)]
+        ipfs_fallback_gateway_url: Vec<Url>,
     },
     /// Update a Device
     Update {
@@ -89,13 +184,16 @@ pub enum DeviceSubcommand {
         #[clap(long)]
         eth_rpc: Option<Url>,
         #[clap(long)]
-        eth_chain_id: Option<u16>,
+        eth_chain_id: Option<u32>,
         #[clap(long)]
         contract_address: Option<Address>,
         #[clap(long, short)]
         ipfs_url: Option<Url>,
         #[clap(long)]
         ipfs_gateway_url: Option<Url>,
+        // Replaces the gateways to fall back on, if given
+        #[clap(long)]
+        ipfs_fallback_gateway_url: Option<Vec<Url>>,
     },
     /// Set a configuration value
     Set { alias: String },
@@ -103,4 +201,6 @@ pub enum DeviceSubcommand {
     Ls,
     /// Show the current device
     Show,
+    /// Show the public key private objects are always encrypted for on this device
+    Key,
 }
@@ -3,17 +3,12 @@ use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 
 use cid::Cid;
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
 
 // TODO: this is an akward way to do this, i could probably
 // constructs diffs better
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
-pub enum StagedType {
-    Added,
-    Modified,
-    Removed,
-}
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum ChangeType {
     Base,
@@ -22,6 +17,18 @@ pub enum ChangeType {
     Removed,
 }
 
+impl ChangeType {
+    /// A plain name for the change, for machines to read
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Base => "base",
+            Self::Added => "added",
+            Self::Modified => "modified",
+            Self::Removed => "removed",
+        }
+    }
+}
+
 impl std::fmt::Display for ChangeType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -58,8 +65,36 @@ impl Log {
     }
 }
 
+/// The changes in a Log, leaving out files that are unchanged from the base
 pub struct DisplayableLog(pub Log);
 
+/// Serialized as `{ "changes": [{ "path": <path>, "cid": <cid>, "change": <change> }] }`,
+/// where a change is one of `added`, `modified`, or `removed`
+impl Serialize for DisplayableLog {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Change<'a> {
+            path: &'a PathBuf,
+            cid: String,
+            change: &'static str,
+        }
+
+        let changes = self
+            .0
+            .iter()
+            .filter(|(_path, (_cid, change_type))| change_type != &ChangeType::Base)
+            .map(|(path, (cid, change_type))| Change {
+                path,
+                cid: cid.to_string(),
+                change: change_type.as_str(),
+            })
+            .collect::<Vec<_>>();
+        let mut log = serializer.serialize_struct("DisplayableLog", 1)?;
+        log.serialize_field("changes", &changes)?;
+        log.end()
+    }
+}
+
 impl std::fmt::Display for DisplayableLog {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut s = String::new();
@@ -67,7 +102,7 @@ impl std::fmt::Display for DisplayableLog {
             if diff_type == &ChangeType::Base {
                 continue;
             }
-            s.push_str(&format!("{}: {}\n", path.to_str().unwrap(), diff_type));
+            s.push_str(&format!("{}: {}\n", path.display(), diff_type));
         }
         write!(f, "{}", s)
     }
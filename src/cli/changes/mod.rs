@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use cid::Cid;
 use serde::{Deserialize, Serialize};
 
@@ -30,20 +32,41 @@ impl ChangeLog {
         }
     }
 
+    /// Start over from a manifest we just pushed.
+    /// The log tracks the cids of files in the working dir, which aren't the cids
+    /// of their objects if they're encrypted, so those are carried over
     pub fn wipe(&mut self, manifest: &Manifest, root_cid: &Cid) {
         let mut log = Log::new();
         for (path, object) in manifest.objects().iter() {
-            log.insert(path.clone(), (*object.cid(), ChangeType::Base));
+            let cid = match self.log.get(path) {
+                Some((cid, _)) if object.encryption().is_some() => *cid,
+                _ => *object.cid(),
+            };
+            log.insert(path.clone(), (cid, ChangeType::Base));
         }
         self.log = log;
         self.versions = vec![(*root_cid, manifest.clone())];
     }
 
+    /// Record that a draft was published. Drafts aren't pushed, but wiping after a push
+    /// marks them as part of the base all the same, so they're marked as added again
+    /// for the next push to pick up
+    pub fn set_published(&mut self, path: &Path) {
+        if let Some((_cid, change @ ChangeType::Base)) = self.log.get_mut(path) {
+            *change = ChangeType::Added;
+        }
+    }
+
     pub fn update(&mut self, log: &Log, manifest: &Manifest, root_cid: &Cid) {
         self.log = log.clone();
         self.versions.push((*root_cid, manifest.clone()));
     }
 
+    /// Record the cid of a file we decrypted into the working dir
+    pub fn set_base_cid(&mut self, path: &Path, cid: Cid) {
+        self.log.insert(path.to_path_buf(), (cid, ChangeType::Base));
+    }
+
     pub fn manager_alias(&self) -> &String {
         &self.manager_alias
     }
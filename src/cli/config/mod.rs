@@ -2,20 +2,22 @@ use std::fs::create_dir_all;
 use std::{env, io::Write, path::PathBuf};
 
 use cid::Cid;
-use ethers::signers::LocalWallet;
+use ethers::signers::{LocalWallet, WalletError};
 use ethers::types::Address;
 use fs_tree::FsTree;
 
 use crate::cli::changes::ChangeLog;
 use crate::eth::EthRemote;
 use crate::ipfs::IpfsRemote;
+use crate::types::encryption::{EncryptionError, SecretKey};
+use crate::types::schema::SchemaDocument;
 use crate::types::Manifest;
 
 mod on_disk_default;
 pub mod on_disk_device;
 
 use on_disk_default::OnDiskDefault;
-use on_disk_device::OnDiskDevice;
+pub use on_disk_device::{OnDiskDevice, OnDiskDeviceError};
 
 use super::args::Args;
 use super::device::Device;
@@ -34,11 +36,15 @@ pub const DEVICE_CONFIG_NAME: &str = "device.json";
 pub const ROOT_CID_NAME: &str = "root";
 // name to lookup on disk base dor-store under a given device alias
 pub const BASE_DOR_STORE_NAME: &str = "base.json";
+// name to lookup the key private objects are encrypted for under a given device alias
+pub const ENCRYPTION_KEY_NAME: &str = "encryption.key";
 
 // path to folder containing local changes tracking in the given working dir
 pub const DEFAULT_LOCAL_DOT_DIR: &str = ".fs";
 // name to lookup change log within a dot dir
 pub const CHANGE_LOG_NAME: &str = "changes.json";
+// name to lookup the editable schema document within a dot dir
+pub const SCHEMAS_NAME: &str = "schemas.json";
 
 #[derive(Debug)]
 pub struct Config {
@@ -89,7 +95,10 @@ impl Config {
         let working_dir = self.working_dir();
         let dot_dir = PathBuf::from(DEFAULT_LOCAL_DOT_DIR);
         // Read Fs-tree at dir or pwd, stripping off the local dot directory
-        let next = match fs_tree::FsTree::read_at(working_dir.to_str().unwrap())? {
+        let working_dir_str = working_dir
+            .to_str()
+            .ok_or_else(|| ConfigError::NonUtf8Path(working_dir.clone()))?;
+        let next = match fs_tree::FsTree::read_at(working_dir_str)? {
             fs_tree::FsTree::Directory(mut d) => {
                 let _res = &d.remove_entry(&dot_dir);
                 fs_tree::FsTree::Directory(d)
@@ -121,6 +130,16 @@ impl Config {
         Ok(device)
     }
 
+    pub fn update_on_disk_device(
+        alias: String,
+        contract_address: Option<Address>,
+        ipfs_remote: Option<IpfsRemote>,
+        eth_remote: Option<EthRemote>,
+    ) -> Result<OnDiskDevice, ConfigError> {
+        let device = OnDiskDevice::update(alias, ipfs_remote, eth_remote, contract_address)?;
+        Ok(device)
+    }
+
     /* Members */
 
     pub fn init(&self) -> Result<(), ConfigError> {
@@ -141,6 +160,8 @@ impl Config {
         let change_log_str = serde_json::to_string_pretty(&change_log)?;
         let mut change_log_file = std::fs::File::create(change_log_path)?;
         change_log_file.write_all(change_log_str.as_bytes())?;
+
+        self.set_schemas(base.schemas())?;
         Ok(())
     }
 
@@ -175,6 +196,31 @@ impl Config {
         Ok(())
     }
 
+    /// Read the schema document editors maintain in the dot dir, if there is one
+    pub fn schemas(&self) -> Result<Option<SchemaDocument>, ConfigError> {
+        let dot_path = self.working_dir.join(DEFAULT_LOCAL_DOT_DIR);
+        let schemas_path = dot_path.join(SCHEMAS_NAME);
+
+        if !schemas_path.exists() {
+            return Ok(None);
+        }
+
+        let schemas_str = std::fs::read_to_string(schemas_path)?;
+        let schemas: SchemaDocument = serde_json::from_str(&schemas_str)?;
+        Ok(Some(schemas))
+    }
+
+    pub fn set_schemas(&self, schemas: &SchemaDocument) -> Result<(), ConfigError> {
+        let dot_path = self.working_dir.join(DEFAULT_LOCAL_DOT_DIR);
+        let schemas_path = dot_path.join(SCHEMAS_NAME);
+
+        let schemas_str = serde_json::to_string_pretty(schemas)?;
+        let mut schemas_file = std::fs::File::create(schemas_path)?;
+        schemas_file.write_all(schemas_str.as_bytes())?;
+
+        Ok(())
+    }
+
     pub fn root_cid(&self) -> Result<Cid, ConfigError> {
         let device_alias = self.device_alias.clone().ok_or(ConfigError::NoSetDevice)?;
         let cid = OnDiskDevice::root_cid(device_alias)?;
@@ -197,15 +243,22 @@ impl Config {
         OnDiskDevice::set_base(device_alias, base)
     }
 
+    /// The key this device decrypts private objects with, created on first use
+    pub fn encryption_key(&self) -> Result<SecretKey, ConfigError> {
+        let device_alias = self.device_alias.clone().ok_or(ConfigError::NoSetDevice)?;
+        OnDiskDevice::encryption_key(device_alias)
+    }
+
     pub fn device(&self) -> Result<Device, ConfigError> {
         let device_alias = self.device_alias.clone().ok_or(ConfigError::NoSetDevice)?;
         let device_config = OnDiskDevice::load(device_alias)?;
-        let device = Device::try_from(device_config).unwrap();
+        let device =
+            Device::try_from(device_config).map_err(|e| ConfigError::Device(Box::new(e)))?;
         match self.admin_key_string.clone() {
             Some(admin_key_string) => {
                 let admin_key = admin_key_string
                     .parse::<LocalWallet>()
-                    .expect("invalid key");
+                    .map_err(ConfigError::InvalidAdminKey)?;
                 let device = device.with_wallet(admin_key);
                 Ok(device)
             }
@@ -249,6 +302,37 @@ pub enum ConfigError {
     Cid(#[from] cid::Error),
     #[error("fs tree error: {0}")]
     FsTree(#[from] fs_tree::Error),
+    #[error("encryption key error: {0}")]
+    EncryptionKey(#[from] EncryptionError),
+    #[error("keystore error: {0}")]
+    Keystore(#[from] WalletError),
+    #[error("invalid admin key: {0}")]
+    InvalidAdminKey(#[source] WalletError),
+    #[error("failed to load device: {0}")]
+    Device(#[source] Box<OnDiskDeviceError>),
+    #[error("path is not valid utf-8: {}", .0.display())]
+    NonUtf8Path(PathBuf),
+}
+
+impl ConfigError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io(..) => "config.io",
+            Self::Parse(..) => "config.parse",
+            Self::NoSetDevice => "config.no_set_device",
+            Self::DotDirNotADirectory => "config.dot_dir_not_a_directory",
+            Self::DeviceNotFound(..) => "config.device_not_found",
+            Self::ChangeLogNotFound => "config.change_log_not_found",
+            Self::Cid(..) => "config.cid",
+            Self::FsTree(..) => "config.fs_tree",
+            Self::EncryptionKey(..) => "config.encryption_key",
+            Self::Keystore(..) => "config.keystore",
+            Self::InvalidAdminKey(..) => "config.invalid_admin_key",
+            Self::Device(..) => "config.device",
+            Self::NonUtf8Path(..) => "config.non_utf8_path",
+        }
+    }
 }
 
 /// Grab config path
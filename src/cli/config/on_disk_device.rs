@@ -2,7 +2,7 @@ use std::convert::TryFrom;
 use std::fmt::Display;
 use std::str::FromStr;
 use std::{
-    fs::{create_dir_all, File},
+    fs::{create_dir_all, File, OpenOptions},
     io::Write,
     path::PathBuf,
 };
@@ -16,11 +16,12 @@ use crate::cli::device::Device;
 use crate::eth::{EthClient, EthClientError, EthRemote};
 use crate::ipfs::{IpfsClient, IpfsError, IpfsGateway, IpfsRemote};
 
+use crate::types::encryption::SecretKey;
 use crate::types::Manifest;
 
 use super::{
     xdg_config_home, ConfigError, BASE_DOR_STORE_NAME, DEVICE_CONFIG_NAME, DEVICE_KEYSTORE_NAME,
-    ROOT_CID_NAME,
+    ENCRYPTION_KEY_NAME, ROOT_CID_NAME,
 };
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -58,8 +59,7 @@ impl OnDiskDevice {
 
         create_dir_all(&device_path)?;
 
-        let _wallet =
-            Wallet::new_keystore(&device_path, &mut rng, "", Some(DEVICE_KEYSTORE_NAME)).unwrap();
+        let _wallet = Wallet::new_keystore(&device_path, &mut rng, "", Some(DEVICE_KEYSTORE_NAME))?;
 
         let config = Self {
             alias: alias.clone(),
@@ -96,6 +96,16 @@ impl OnDiskDevice {
         self.alias.clone()
     }
 
+    /// Connection to the eth remote
+    pub fn eth_remote(&self) -> EthRemote {
+        self.eth_remote.clone()
+    }
+
+    /// Connection to the ipfs remote
+    pub fn ipfs_remote(&self) -> IpfsRemote {
+        self.ipfs_remote.clone()
+    }
+
     /// Return a list of all on disk devices
     pub fn list() -> Result<Vec<Self>, ConfigError> {
         let xdg_path = xdg_config_home()?;
@@ -106,15 +116,18 @@ impl OnDiskDevice {
             if !path.is_dir() {
                 continue;
             }
-            let alias = path.file_name().unwrap().to_str().unwrap().to_string();
-            let device = Self::load(alias.clone())?;
+            // Aliases come from the cli, so anything else here isn't one of our devices
+            let Some(alias) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let device = Self::load(alias.to_string())?;
             devices.push(device);
         }
         Ok(devices)
     }
 
     /// Update the on disk device configuration
-    pub fn _update(
+    pub fn update(
         alias: String,
         ipfs_remote: Option<IpfsRemote>,
         eth_remote: Option<EthRemote>,
@@ -140,10 +153,33 @@ impl OnDiskDevice {
     pub fn keystore(alias: String) -> Result<LocalWallet, ConfigError> {
         let device_path = device_path(alias.clone())?;
         let keystore_path = device_path.join(DEVICE_KEYSTORE_NAME);
-        let wallet = LocalWallet::decrypt_keystore(keystore_path, "").unwrap();
+        let wallet = LocalWallet::decrypt_keystore(keystore_path, "")?;
         Ok(wallet)
     }
 
+    /// Read the key private objects are encrypted for from disk for the device.
+    /// Devices created before objects could be encrypted get one generated
+    pub fn encryption_key(alias: String) -> Result<SecretKey, ConfigError> {
+        let device_path = device_path(alias.clone())?;
+        let key_path = device_path.join(ENCRYPTION_KEY_NAME);
+        if !key_path.exists() {
+            let key = SecretKey::generate();
+            let mut options = OpenOptions::new();
+            options.write(true).create_new(true);
+            // Only the owner should be able to read the key
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let mut key_file = options.open(key_path)?;
+            key_file.write_all(key.to_string().as_bytes())?;
+            return Ok(key);
+        }
+        let key_str = std::fs::read_to_string(key_path)?;
+        Ok(SecretKey::from_str(&key_str)?)
+    }
+
     /// Read the root cid from disk for the device
     pub fn root_cid(alias: String) -> Result<Cid, ConfigError> {
         let device_path = device_path(alias.clone())?;
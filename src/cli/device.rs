@@ -1,19 +1,18 @@
-use std::io::Cursor;
-use std::io::Read;
-use std::path::PathBuf;
+use std::io::{Cursor, Read, Write};
 
 use cid::Cid;
 use ethers::signers::LocalWallet;
-use ethers::types::Address;
-use futures_util::stream::TryStreamExt;
+use ethers::types::{Address, TxHash};
+use futures_util::stream::{Stream, TryStreamExt};
+use url::Url;
 
 use crate::eth::{EthClient, EthClientError, RootCid};
 use crate::ipfs::{
-    add_data_request, hash_data_request, IpfsApi, IpfsClient, IpfsClientError, IpfsError,
-    IpfsGateway,
+    add_data_request, dag_content, hash_data_request, put_block_request, IpfsApi, IpfsClient,
+    IpfsClientError, IpfsError, IpfsGateway, ProgressReporter,
 };
 
-use crate::types::Manifest;
+use crate::types::{BlockStore, Manifest, ManifestError, ManifestRoot};
 
 /// Union of IPFS and Ethereum clients for coordinating pushing and pulling
 /// dor-store updates to and from remote infrastructure.
@@ -64,13 +63,41 @@ impl Device {
     /* Dor Store Helpers */
 
     /// Read a Block by its Cid as a Manifest from Ipfs
+    /// Manifests in older formats are migrated to the current format, and
+    /// sharded manifests have all their shards loaded
     /// # Args
     /// - cid: The cid of the Manifest object
     /// - remote: whether to read against the remote of local IPFS client
     pub async fn read_manifest(&self, cid: &Cid, remote: bool) -> Result<Manifest, DeviceError> {
         let manifest_data = self.read_ipfs_data(cid, remote).await?;
-        let manifest = serde_json::from_slice(&manifest_data)?;
-        Ok(manifest)
+        self.parse_manifest(&manifest_data, remote).await
+    }
+
+    /// Parse the root block of a Manifest, loading any shards it links to
+    /// # Args
+    /// - data: the root block of the Manifest
+    /// - remote: whether to read shards against the remote of local IPFS client
+    pub async fn parse_manifest(&self, data: &[u8], remote: bool) -> Result<Manifest, DeviceError> {
+        match ManifestRoot::from_slice(data)? {
+            ManifestRoot::Flat(manifest) => Ok(manifest),
+            ManifestRoot::Sharded(sharded) => {
+                let blocks = DeviceBlocks {
+                    device: self,
+                    remote,
+                };
+                sharded.load(&blocks).await
+            }
+        }
+    }
+
+    /// Whether the Manifest a Cid names was written as a tree of shards
+    /// # Args
+    /// - cid: The cid of the root block of the Manifest
+    /// - remote: whether to read against the remote of local IPFS client
+    pub async fn is_sharded_manifest(&self, cid: &Cid, remote: bool) -> Result<bool, DeviceError> {
+        let data = self.read_ipfs_data(cid, remote).await?;
+        let root = ManifestRoot::from_slice(&data)?;
+        Ok(matches!(root, ManifestRoot::Sharded(_)))
     }
 
     /// Write a Manifest as a block on Ipfs
@@ -88,6 +115,35 @@ impl Device {
         Ok(cid)
     }
 
+    /// Write a Manifest as a tree of shards on Ipfs
+    /// Shards that are already present are not uploaded again
+    /// # Args
+    /// - remote: whether to write against the remote of local IPFS client
+    /// # Returns the Cid of the root block of the Manifest
+    pub async fn write_sharded_manifest(
+        &self,
+        manifest: &Manifest,
+        remote: bool,
+    ) -> Result<Cid, DeviceError> {
+        let (root, blocks) = manifest.shard()?;
+        let total = blocks.len();
+        let mut written = 0;
+        for (cid, data) in blocks {
+            if self.stat_ipfs_data(&cid, remote).await?.is_some() {
+                continue;
+            }
+            let put_cid = self.write_ipfs_block(data, remote).await?;
+            if put_cid != cid {
+                return Err(DeviceError::UnexpectedBlockCid(cid));
+            }
+            written += 1;
+        }
+        tracing::info!("wrote {} of {} manifest shards", written, total);
+        let root_data = serde_json::to_vec(&root)?;
+        let cid = self.write_ipfs_data(Cursor::new(root_data), remote).await?;
+        Ok(cid)
+    }
+
     /// Hash a Manifest object against Ipfs
     /// # Args
     /// - manifest: the Manifest instance to hash
@@ -122,22 +178,23 @@ impl Device {
     /// # Args
     /// - previous_root_cid: the previously known root cid of the remote
     /// - next_root_cid: the root cid to overwrite it with
+    /// # Returns the hash of the update's transaction, once it's been mined
     pub async fn update_root_cid(
         &self,
         previous_root_cid: Cid,
         next_root_cid: Cid,
-    ) -> Result<(), DeviceError> {
+    ) -> Result<Option<TxHash>, DeviceError> {
         let root_cid = RootCid::new(
             self.eth.clone(),
             self.contract_address,
             Some(self.wallet.clone()),
         )?;
 
-        let _maybe_txn_reciept = root_cid.update(previous_root_cid, next_root_cid).await?;
+        let maybe_txn_reciept = root_cid.update(previous_root_cid, next_root_cid).await?;
 
         // TODO: maybe should wait for emitted event and check for a valid update
 
-        Ok(())
+        Ok(maybe_txn_reciept.map(|reciept| reciept.transaction_hash))
     }
 
     /* Ipfs Helpers */
@@ -156,13 +213,32 @@ impl Device {
         Ok(id)
     }
 
-    // TODO: Check for links, keep pulling if any
-    // TODO: Add method for just returning the stream
-    /// Read a block by its cid against the configured IpfsClients
+    /// Read the content a Cid names against the configured IpfsClients.
+    /// UnixFS files spanning many blocks are reassembled by following their links
     /// # Args
     /// - cid: the cid to read
     /// - remote: whether to do so against a remote or local instance
     pub async fn read_ipfs_data(&self, cid: &Cid, remote: bool) -> Result<Vec<u8>, DeviceError> {
+        self.read_ipfs_data_stream(cid, remote).try_concat().await
+    }
+
+    /// Stream the content a Cid names against the configured IpfsClients, a block at a time,
+    /// so large files never have to be held in memory
+    /// # Args
+    /// - cid: the cid to read
+    /// - remote: whether to do so against a remote or local instance
+    pub fn read_ipfs_data_stream(
+        &self,
+        cid: &Cid,
+        remote: bool,
+    ) -> impl Stream<Item = Result<Vec<u8>, DeviceError>> + '_ {
+        dag_content(cid, move |cid| async move {
+            self.read_ipfs_block(&cid, remote).await
+        })
+    }
+
+    /// Read a single block by its cid against the configured IpfsClients
+    async fn read_ipfs_block(&self, cid: &Cid, remote: bool) -> Result<Vec<u8>, DeviceError> {
         let block_stream = if remote {
             self.ipfs_client.block_get(&cid.to_string())
         } else {
@@ -175,17 +251,33 @@ impl Device {
         Ok(block_data)
     }
 
-    /// Read a Cid from the configured Ipfs Gateway
+    /// The url the configured Ipfs Gateway serves a Cid at
+    pub fn ipfs_gateway_url(&self, cid: &Cid) -> Url {
+        self.ipfs_gateway.url(cid)
+    }
+
+    /// Read a Cid from the configured Ipfs Gateways, verifying what they serve against it
     /// # Args
     /// - cid: the cid to read
-    /// - path: Optional path parameter if the Cid points to a unix-fs directory
-    pub async fn read_ipfs_gateway_data(
+    pub async fn read_ipfs_gateway_data(&self, cid: &Cid) -> Result<Vec<u8>, DeviceError> {
+        let data = self.ipfs_gateway.get(cid).await?;
+        Ok(data)
+    }
+
+    /// Stream a Cid from the configured Ipfs Gateways into a writer, verifying what they
+    /// serve against it
+    /// # Args
+    /// - cid: the cid to read
+    /// - out: where to write the content
+    /// - progress: told about every byte written
+    pub async fn read_ipfs_gateway_data_to<W: Write>(
         &self,
         cid: &Cid,
-        path: Option<PathBuf>,
-    ) -> Result<Vec<u8>, DeviceError> {
-        let data = self.ipfs_gateway.get(cid, path).await?;
-        Ok(data)
+        out: &mut W,
+        progress: &dyn ProgressReporter,
+    ) -> Result<(), DeviceError> {
+        self.ipfs_gateway.get_to(cid, out, progress).await?;
+        Ok(())
     }
 
     /// Write data against the configured IpfsClients
@@ -209,6 +301,25 @@ impl Device {
         Ok(cid)
     }
 
+    /// Write a raw block against the configured IpfsClients
+    /// # Args
+    /// - data: the block to write
+    /// - remote: whether to do so against a remote or local instance
+    /// # Returns the cid of the written block
+    pub async fn write_ipfs_block(&self, data: Vec<u8>, remote: bool) -> Result<Cid, DeviceError> {
+        let data = Cursor::new(data);
+        let put_response = if remote {
+            self.ipfs_client
+                .block_put_with_options(data, put_block_request())
+        } else {
+            self.local_ipfs_client
+                .block_put_with_options(data, put_block_request())
+        }
+        .await?;
+        let cid = Cid::try_from(put_response.key)?;
+        Ok(cid)
+    }
+
     /// Hash data against the configured IpfsClients
     /// # Args
     /// - data: the data to write
@@ -235,7 +346,7 @@ impl Device {
     /// - cid: the cid to check
     /// - remote: whether to do so against a remote or local instance
     /// # Returns the size of the queried block
-    pub async fn _stat_ipfs_data(
+    pub async fn stat_ipfs_data(
         &self,
         cid: &Cid,
         remote: bool,
@@ -261,6 +372,20 @@ impl Device {
     }
 }
 
+/// Reads manifest shards through a Device
+struct DeviceBlocks<'a> {
+    device: &'a Device,
+    remote: bool,
+}
+
+impl BlockStore for DeviceBlocks<'_> {
+    type Error = DeviceError;
+
+    async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, DeviceError> {
+        self.device.read_ipfs_data(cid, self.remote).await
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DeviceError {
     #[error("cid error: {0}")]
@@ -275,4 +400,8 @@ pub enum DeviceError {
     RootCid(#[from] crate::eth::RootCidError),
     #[error("serde error: {0}")]
     Serde(#[from] serde_json::Error),
+    #[error("manifest error: {0}")]
+    Manifest(#[from] ManifestError),
+    #[error("remote returned an unexpected cid for block: {0}")]
+    UnexpectedBlockCid(Cid),
 }
@@ -0,0 +1,342 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use super::config::ConfigError;
+use super::ops::{
+    ExportError, HealthError, MigrateError, PreviewError, PublishError, PullError, PushError,
+    StageError, TagError,
+};
+use crate::eth::{EthClientError, RootCidError};
+use crate::ipfs::{IpfsClientError, IpfsError};
+use crate::types::encryption::EncryptionError;
+
+/// What kind of thing went wrong. Each exits with its own code, so scripts can tell
+/// failures apart without parsing messages
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Something we didn't expect to fail
+    Internal,
+    /// The command was given something it can't use
+    Usage,
+    /// No device is set up, or the working directory hasn't been initialized
+    Config,
+    /// The working directory and the remote disagree, or there's nothing to do
+    Conflict,
+    /// Ipfs couldn't be reached, or served something it shouldn't have
+    Ipfs,
+    /// The eth rpc or contract couldn't be reached
+    Eth,
+    /// Reading or writing local files failed
+    Io,
+}
+
+impl ErrorCategory {
+    /// Like clap, we exit with 2 on bad usage
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Internal => 1,
+            Self::Usage => 2,
+            Self::Config => 3,
+            Self::Conflict => 4,
+            Self::Ipfs => 5,
+            Self::Eth => 6,
+            Self::Io => 7,
+        }
+    }
+
+    /// The category of the first error in a chain we know how to categorize
+    pub fn of(error: &(dyn Error + 'static)) -> Self {
+        chain(error).find_map(categorize).unwrap_or(Self::Internal)
+    }
+}
+
+/// How a failed command is reported: `{ "error": { "code", "message", ... } }` as json
+#[derive(Serialize)]
+pub struct ErrorReport {
+    error: ErrorBody,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    /// What went wrong, as `<command>.<reason>`
+    code: &'static str,
+    message: String,
+    category: ErrorCategory,
+    exit_code: i32,
+    /// What to do about it, when we know
+    hint: Option<&'static str>,
+    /// Why it happened, outermost first
+    causes: Vec<String>,
+}
+
+impl ErrorReport {
+    pub fn new(code: &'static str, error: &(dyn Error + 'static)) -> Self {
+        let category = ErrorCategory::of(error);
+        Self {
+            error: ErrorBody {
+                code,
+                message: error.to_string(),
+                category,
+                exit_code: category.exit_code(),
+                hint: chain(error).find_map(hint),
+                causes: causes(error),
+            },
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.error.exit_code
+    }
+}
+
+impl Display for ErrorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "error: {}", self.error.message)?;
+        for cause in &self.error.causes {
+            writeln!(f, "  caused by: {}", cause)?;
+        }
+        if let Some(hint) = self.error.hint {
+            writeln!(f, "hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// An error, followed by each of its sources
+fn chain<'a>(error: &'a (dyn Error + 'static)) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+    std::iter::successors(Some(error), |&error| error.source())
+}
+
+/// The messages of an error's sources. Most errors already include their source's message
+/// in their own, so only the ones that would otherwise go unsaid are kept
+fn causes(error: &(dyn Error + 'static)) -> Vec<String> {
+    let mut causes = Vec::new();
+    let mut last = error.to_string();
+    for source in chain(error).skip(1) {
+        let message = source.to_string();
+        if !last.contains(&message) {
+            causes.push(message.clone());
+        }
+        last = message;
+    }
+    causes
+}
+
+/// Categorize a single error in a chain. Errors that only wrap another are left
+/// to their source
+fn categorize(error: &(dyn Error + 'static)) -> Option<ErrorCategory> {
+    use ErrorCategory::*;
+
+    if let Some(error) = error.downcast_ref::<ConfigError>() {
+        return match error {
+            ConfigError::Io(_) | ConfigError::Device(_) => None,
+            _ => Some(Config),
+        };
+    }
+    if let Some(error) = error.downcast_ref::<PushError>() {
+        return match error {
+            PushError::NoChanges
+            | PushError::MissmatchedRootCid(..)
+            | PushError::MissmatchedBase(..) => Some(Conflict),
+            PushError::CidMismatch(..) => Some(Ipfs),
+            _ => None,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<MigrateError>() {
+        return match error {
+            MigrateError::StagedChanges
+            | MigrateError::NoManifest
+            | MigrateError::MissmatchedRootCid(..) => Some(Conflict),
+            _ => None,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<PullError>() {
+        return match error {
+            PullError::PathIsDirectory(_) => Some(Conflict),
+            PullError::CidMismatch(..) => Some(Ipfs),
+            _ => None,
+        };
+    }
+    if let Some(StageError::CidMismatch(..)) = error.downcast_ref::<StageError>() {
+        return Some(Ipfs);
+    }
+    if let Some(PublishError::ObjectDoesNotExist(_)) = error.downcast_ref::<PublishError>() {
+        return Some(Usage);
+    }
+    if let Some(error) = error.downcast_ref::<TagError>() {
+        return match error {
+            TagError::Config(_)
+            | TagError::Serde(_)
+            | TagError::Device(_)
+            | TagError::Wizard(_)
+            | TagError::Io(_) => None,
+            _ => Some(Usage),
+        };
+    }
+    if let Some(ExportError::OutNotEmpty(_)) = error.downcast_ref::<ExportError>() {
+        return Some(Usage);
+    }
+    if let Some(error) = error.downcast_ref::<PreviewError>() {
+        return match error {
+            PreviewError::NothingStaged => Some(Config),
+            PreviewError::Server(_) => Some(Io),
+            _ => None,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<HealthError>() {
+        return match error {
+            HealthError::IpfsUnreachable(..) => Some(Ipfs),
+            HealthError::EthUnreachable(..) => Some(Eth),
+            _ => None,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<IpfsError>() {
+        return match error {
+            IpfsError::Io(_) => None,
+            _ => Some(Ipfs),
+        };
+    }
+    if error.is::<IpfsClientError>() {
+        return Some(Ipfs);
+    }
+    if let Some(error) = error.downcast_ref::<RootCidError>() {
+        return match error {
+            RootCidError::MissingSigner => Some(Usage),
+            _ => Some(Eth),
+        };
+    }
+    if error.is::<EthClientError>() {
+        return Some(Eth);
+    }
+    if error.is::<EncryptionError>() {
+        return Some(Usage);
+    }
+    if error.is::<std::io::Error>() {
+        return Some(Io);
+    }
+    None
+}
+
+/// What to do about a single error in a chain, if we know
+fn hint(error: &(dyn Error + 'static)) -> Option<&'static str> {
+    const PULL: &str = "the remote changed since you last pulled -- run `pull`, then stage again";
+    const IPFS: &str = "check your ipfs node is reachable with `health`";
+
+    if let Some(error) = error.downcast_ref::<ConfigError>() {
+        return match error {
+            ConfigError::NoSetDevice => {
+                Some("create a device with `device create`, or choose one with `device set`")
+            }
+            ConfigError::DeviceNotFound(_) => Some("list your devices with `device ls`"),
+            ConfigError::ChangeLogNotFound => {
+                Some("run `init` first, or `pull` to start from what's been pushed")
+            }
+            ConfigError::InvalidAdminKey(_) => Some("--admin-key takes a hex encoded private key"),
+            _ => None,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<PushError>() {
+        return match error {
+            PushError::NoChanges => Some("stage some changes first"),
+            PushError::MissmatchedRootCid(..) | PushError::MissmatchedBase(..) => Some(PULL),
+            _ => None,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<MigrateError>() {
+        return match error {
+            MigrateError::StagedChanges => Some("push your staged changes first"),
+            MigrateError::MissmatchedRootCid(..) => Some(PULL),
+            _ => None,
+        };
+    }
+    if let Some(PublishError::ObjectDoesNotExist(_)) = error.downcast_ref::<PublishError>() {
+        return Some("run `stage` first, so the object is tracked");
+    }
+    if let Some(error) = error.downcast_ref::<TagError>() {
+        return match error {
+            TagError::ObjectDoesNotExist(_) => Some("run `stage` first, so the object is tracked"),
+            TagError::SchemaDoesNotExist(_) => Some("schemas are defined in .fs/schemas.json"),
+            _ => None,
+        };
+    }
+    if let Some(ExportError::OutNotEmpty(_)) = error.downcast_ref::<ExportError>() {
+        return Some("export to a new or empty directory");
+    }
+    if let Some(PreviewError::NothingStaged) = error.downcast_ref::<PreviewError>() {
+        return Some("run `init` first");
+    }
+    if let Some(error) = error.downcast_ref::<HealthError>() {
+        return match error {
+            HealthError::IpfsUnreachable(..) | HealthError::EthUnreachable(..) => {
+                Some("check the urls your device uses with `device show`")
+            }
+            _ => None,
+        };
+    }
+    if let Some(error) = error.downcast_ref::<IpfsError>() {
+        return match error {
+            IpfsError::Timeout(_) | IpfsError::NoGateways => Some(
+                "check your gateways with `device show` -- fallbacks can be set with \
+                 `device update --ipfs-fallback-gateway-url`",
+            ),
+            IpfsError::Url(_) | IpfsError::Scheme(_) | IpfsError::MissingHost(_) => {
+                Some("fix your ipfs urls with `device update`")
+            }
+            IpfsError::Client(_) | IpfsError::Reqwest(_) => Some(IPFS),
+            _ => None,
+        };
+    }
+    if error.is::<IpfsClientError>() {
+        return Some(IPFS);
+    }
+    if let Some(RootCidError::MissingSigner) = error.downcast_ref::<RootCidError>() {
+        return Some("pass the key that deployed the contract with --admin-key");
+    }
+    if error.is::<EthClientError>() || error.is::<RootCidError>() {
+        return Some("check your eth rpc is reachable with `health`");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::device::DeviceError;
+
+    #[test]
+    fn errors_are_reported_with_their_causes_category_and_hint() {
+        // This error doesn't say what caused it, so its source is reported
+        let error = PushError::Config(ConfigError::NoSetDevice);
+        let report = ErrorReport::new("push", &error);
+        assert_eq!(report.error.message, "config error");
+        assert_eq!(report.error.causes, vec!["no set device"]);
+        assert_eq!(report.error.category, ErrorCategory::Config);
+        assert_eq!(report.exit_code(), 3);
+        assert!(report.error.hint.unwrap().contains("device create"));
+
+        // These all do, so nothing is repeated
+        let error = PushError::Device(DeviceError::Ipfs(IpfsError::NoGateways));
+        let report = ErrorReport::new("push", &error);
+        assert_eq!(
+            report.error.message,
+            "device error: ipfs error: no gateways configured"
+        );
+        assert!(report.error.causes.is_empty());
+        assert_eq!(report.error.category, ErrorCategory::Ipfs);
+
+        // The outermost error we can categorize decides
+        let error = PushError::NoChanges;
+        assert_eq!(ErrorCategory::of(&error), ErrorCategory::Conflict);
+        let error = PushError::Io(std::io::Error::other("disk full"));
+        assert_eq!(ErrorCategory::of(&error), ErrorCategory::Io);
+        let error = PushError::PushFailed("a.txt".into(), Box::new(error));
+        assert_eq!(ErrorCategory::of(&error), ErrorCategory::Io);
+        assert_eq!(
+            ErrorReport::new("push", &error).error.causes,
+            vec!["io error: disk full"]
+        );
+    }
+}
@@ -3,6 +3,11 @@ mod args;
 mod changes;
 mod config;
 mod device;
+mod error;
 mod ops;
+mod output;
+mod progress;
+mod render;
+mod wizard;
 
 pub use app::App;
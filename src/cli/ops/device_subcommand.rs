@@ -1,13 +1,19 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+use url::Url;
+
 use crate::cli::args::DeviceSubcommand;
-use crate::cli::config::{Config, ConfigError};
+use crate::cli::config::{Config, ConfigError, OnDiskDevice};
+use crate::cli::output::write_lines;
 use crate::eth::EthRemote;
 use crate::ipfs::IpfsRemote;
 
 pub fn device_subcommand(
     config: &Config,
     subcommand: &DeviceSubcommand,
-) -> Result<(), DeviceSubcommandError> {
-    match subcommand {
+) -> Result<DeviceReport, DeviceSubcommandError> {
+    let report = match subcommand {
         DeviceSubcommand::Create {
             alias,
             eth_rpc,
@@ -15,6 +21,7 @@ pub fn device_subcommand(
             contract_address,
             ipfs_url,
             ipfs_gateway_url,
+            ipfs_fallback_gateway_url,
         } => {
             let eth_remote = EthRemote {
                 rpc_url: eth_rpc.clone(),
@@ -23,35 +30,122 @@ pub fn device_subcommand(
             let ipfs_remote = IpfsRemote {
                 api_url: ipfs_url.clone(),
                 gateway_url: ipfs_gateway_url.clone(),
+                fallback_gateway_urls: ipfs_fallback_gateway_url.clone(),
             };
-            Config::create_on_disk_device(
+            let device = Config::create_on_disk_device(
                 alias.clone(),
                 *contract_address,
                 ipfs_remote,
                 eth_remote,
             )?;
+            DeviceReport::Created { created: device }
         }
         DeviceSubcommand::Update {
-            alias: _,
-            eth_rpc: _,
-            eth_chain_id: _,
-            contract_address: _,
-            ipfs_url: _,
-            ipfs_gateway_url: _,
+            alias,
+            eth_rpc,
+            eth_chain_id,
+            contract_address,
+            ipfs_url,
+            ipfs_gateway_url,
+            ipfs_fallback_gateway_url,
         } => {
-            todo!("updating devices is not supported yet")
+            let device = OnDiskDevice::load(alias.clone())?;
+            let eth_remote =
+                update_eth_remote(device.eth_remote(), eth_rpc.as_ref(), *eth_chain_id);
+            let ipfs_remote = update_ipfs_remote(
+                device.ipfs_remote(),
+                ipfs_url.as_ref(),
+                ipfs_gateway_url.as_ref(),
+                ipfs_fallback_gateway_url.as_deref(),
+            );
+            let device = Config::update_on_disk_device(
+                alias.clone(),
+                *contract_address,
+                ipfs_remote,
+                eth_remote,
+            )?;
+            DeviceReport::Updated { updated: device }
         }
         DeviceSubcommand::Set { alias } => {
             Config::set_device(alias.clone())?;
+            DeviceReport::Selected {
+                selected: alias.clone(),
+            }
         }
-        DeviceSubcommand::Ls => {
-            println!("{:?}", Config::list_on_disk_devices()?);
-        }
-        DeviceSubcommand::Show => {
-            println!("{}", config.on_disk_device()?);
+        DeviceSubcommand::Ls => DeviceReport::Listed {
+            devices: Config::list_on_disk_devices()?,
+        },
+        DeviceSubcommand::Show => DeviceReport::Shown {
+            device: config.on_disk_device()?,
+        },
+        DeviceSubcommand::Key => DeviceReport::Key {
+            public_key: config.encryption_key()?.public_key().to_string(),
+        },
+    };
+    Ok(report)
+}
+
+/// Update an eth remote with whatever was passed, keeping the rest as it was
+/// # Returns None if nothing was passed
+fn update_eth_remote(
+    remote: EthRemote,
+    rpc_url: Option<&Url>,
+    chain_id: Option<u32>,
+) -> Option<EthRemote> {
+    (rpc_url.is_some() || chain_id.is_some()).then(|| EthRemote {
+        rpc_url: rpc_url.cloned().unwrap_or(remote.rpc_url),
+        chain_id: chain_id.unwrap_or(remote.chain_id),
+    })
+}
+
+/// Update an ipfs remote with whatever was passed, keeping the rest -- including its
+/// fallback gateways, unless new ones were passed -- as it was
+/// # Returns None if nothing was passed
+fn update_ipfs_remote(
+    remote: IpfsRemote,
+    api_url: Option<&Url>,
+    gateway_url: Option<&Url>,
+    fallback_gateway_urls: Option<&[Url]>,
+) -> Option<IpfsRemote> {
+    let passed = api_url.is_some() || gateway_url.is_some() || fallback_gateway_urls.is_some();
+    passed.then(|| IpfsRemote {
+        api_url: api_url.cloned().unwrap_or(remote.api_url),
+        gateway_url: gateway_url.cloned().unwrap_or(remote.gateway_url),
+        fallback_gateway_urls: fallback_gateway_urls
+            .map(<[Url]>::to_vec)
+            .unwrap_or(remote.fallback_gateway_urls),
+    })
+}
+
+/// What a device subcommand did. Each is reported as an object with a single field
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum DeviceReport {
+    /// `{ "created": <device> }`
+    Created { created: OnDiskDevice },
+    /// `{ "updated": <device> }`
+    Updated { updated: OnDiskDevice },
+    /// `{ "selected": <alias> }`
+    Selected { selected: String },
+    /// `{ "devices": [<device>] }`
+    Listed { devices: Vec<OnDiskDevice> },
+    /// `{ "device": <device> }`
+    Shown { device: OnDiskDevice },
+    /// `{ "public_key": <key> }`
+    Key { public_key: String },
+}
+
+impl Display for DeviceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Created { created } => write!(f, "Created device {}", created.alias()),
+            Self::Updated { updated } => write!(f, "Updated device {}", updated.alias()),
+            Self::Selected { selected } => write!(f, "Using device {}", selected),
+            Self::Listed { devices } => write_lines(f, "", devices),
+            Self::Shown { device } => write!(f, "{}", device),
+            Self::Key { public_key } => write!(f, "{}", public_key),
         }
     }
-    Ok(())
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -59,3 +153,51 @@ pub enum DeviceSubcommandError {
     #[error("config error: {0}")]
     Config(#[from] ConfigError),
 }
+
+impl DeviceSubcommandError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "device.config",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn updates_keep_what_was_not_passed() {
+        let eth_remote = EthRemote {
+            rpc_url: url("http://localhost:8545"),
+            chain_id: 31337,
+        };
+        assert!(update_eth_remote(eth_remote.clone(), None, None).is_none());
+        let updated = update_eth_remote(eth_remote, None, Some(1)).unwrap();
+        assert_eq!(updated.rpc_url, url("http://localhost:8545"));
+        assert_eq!(updated.chain_id, 1);
+
+        let ipfs_remote = IpfsRemote {
+            api_url: url("http://localhost:5001"),
+            gateway_url: url("http://localhost:8080"),
+            fallback_gateway_urls: vec![url("https://ipfs.io")],
+        };
+        assert!(update_ipfs_remote(ipfs_remote.clone(), None, None, None).is_none());
+        let dweb = url("https://dweb.link");
+        let updated = update_ipfs_remote(ipfs_remote.clone(), None, Some(&dweb), None).unwrap();
+        assert_eq!(updated.api_url, url("http://localhost:5001"));
+        assert_eq!(updated.gateway_url, dweb);
+        assert_eq!(updated.fallback_gateway_urls, vec![url("https://ipfs.io")]);
+
+        // Fallbacks are replaced as a whole
+        let fallbacks = vec![dweb];
+        let updated = update_ipfs_remote(ipfs_remote, None, None, Some(&fallbacks)).unwrap();
+        assert_eq!(updated.gateway_url, url("http://localhost:8080"));
+        assert_eq!(updated.fallback_gateway_urls, fallbacks);
+    }
+}
@@ -0,0 +1,290 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::cli::config::{Config, ConfigError};
+use crate::cli::device::{Device, DeviceError};
+use crate::cli::output::write_lines;
+use crate::cli::render::{content_path, ContentSource, RenderError, SiteRenderer, STATIC_DIR};
+use crate::types::Manifest;
+
+/// Feeds get a new cid with every push, so exported sites also serve them at the
+/// paths feed readers and podcast apps look for, which can be subscribed to once
+const FEED_PATHS: [(&str, &str); 2] = [("writing.rss", "feed.xml"), ("podcast.rss", "podcast.xml")];
+
+/// Render the pushed manifest as a tree of plain html, which works without javascript,
+/// an RPC, or a gateway. Every link within the tree is relative, so it can be served from
+/// anywhere, including as a UnixFS directory on Ipfs
+/// # Args
+/// - out: the directory to write the tree to
+/// - static_dir: where the site's static markdown and icons live, if anywhere
+/// - title: what to call the site
+pub async fn export(
+    config: &Config,
+    out: &Path,
+    static_dir: &Path,
+    title: &str,
+) -> Result<ExportReport, ExportError> {
+    let working_dir = config.working_dir().clone();
+    let device = config.device()?;
+    let manifest = config.base()?;
+    export_manifest(&device, &working_dir, manifest, out, static_dir, title).await
+}
+
+/// Render a manifest into a directory, reading content from the working dir or the gateway
+async fn export_manifest(
+    device: &Device,
+    working_dir: &Path,
+    mut manifest: Manifest,
+    out: &Path,
+    static_dir: &Path,
+    title: &str,
+) -> Result<ExportReport, ExportError> {
+    // Only export what readers can see right now. Encrypted objects aren't for everyone
+    let now = Utc::now();
+    manifest.retain_objects(|object| {
+        object.publication().is_published_at(&now) && object.encryption().is_none()
+    });
+
+    if out.exists() && fs::read_dir(out)?.next().is_some() {
+        return Err(ExportError::OutNotEmpty(out.to_path_buf()));
+    }
+    fs::create_dir_all(out)?;
+
+    let renderer = SiteRenderer::new(
+        device,
+        working_dir,
+        &manifest,
+        static_dir,
+        title,
+        ContentSource::Published,
+    );
+    for (page, html) in renderer.pages().await? {
+        write_file(&out.join(page), html.as_bytes())?;
+    }
+
+    // Static markdown and icons the site ships with
+    if static_dir.is_dir() {
+        copy_dir(static_dir, &out.join(STATIC_DIR))?;
+    }
+
+    // A copy of every object's content, for its page to link to
+    let mut report = ExportReport {
+        out: out.to_path_buf(),
+        exported: Vec::new(),
+        feeds: Vec::new(),
+    };
+    for (path, object) in manifest.objects() {
+        let data = renderer.object_data(path, object).await?;
+        write_file(&out.join(content_path(path)), &data)?;
+        report.exported.push(path.clone());
+    }
+
+    // Every feed pushed with the manifest, under its own name and any fixed path it has
+    for (name, cid) in manifest.feeds() {
+        let data = device.read_ipfs_gateway_data(cid).await?;
+        let paths = FEED_PATHS
+            .iter()
+            .filter(|(feed, _)| feed == name)
+            .map(|(_, path)| *path);
+        for path in std::iter::once(name.as_str()).chain(paths) {
+            write_file(&out.join(path), &data)?;
+            report.feeds.push(PathBuf::from(path));
+        }
+    }
+
+    Ok(report)
+}
+
+/// Where a site was exported to, and which objects it holds
+#[derive(Serialize)]
+pub struct ExportReport {
+    out: PathBuf,
+    exported: Vec<PathBuf>,
+    /// Where feeds were written, relative to `out`
+    feeds: Vec<PathBuf>,
+}
+
+impl Display for ExportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_lines(
+            f,
+            "Exported ",
+            self.exported.iter().map(|path| path.display()),
+        )?;
+        write_lines(
+            f,
+            "Exported feed ",
+            self.feeds.iter().map(|path| path.display()),
+        )?;
+        writeln!(f, "Exported the site to {}", self.out.display())
+    }
+}
+
+fn write_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, data)
+}
+
+/// Copy a directory and everything within it
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("config error: {0}")]
+    Config(#[from] ConfigError),
+    #[error("device error: {0}")]
+    Device(#[from] DeviceError),
+    #[error("render error: {0}")]
+    Render(#[from] RenderError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0} is not empty -- export to a new directory")]
+    OutNotEmpty(PathBuf),
+}
+
+impl ExportError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "export.config",
+            Self::Device(..) => "export.device",
+            Self::Render(..) => "export.render",
+            Self::Io(..) => "export.io",
+            Self::OutNotEmpty(..) => "export.out_not_empty",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Multihash;
+    use cid::Cid;
+    use ethers::signers::LocalWallet;
+    use ethers::types::Address;
+    use serde_json::json;
+
+    use super::*;
+    use crate::eth::{EthClient, EthRemote};
+    use crate::ipfs::{IpfsClient, IpfsGateway};
+    use crate::types::Object;
+
+    /// The Cid of a raw block, hashed with blake3
+    fn raw_cid(data: &[u8]) -> Cid {
+        let hash = Multihash::wrap(0x1e, blake3::hash(data).as_bytes()).unwrap();
+        Cid::new_v1(0x55, hash)
+    }
+
+    /// A device whose gateway serves the given blocks as raw blocks, and nothing else
+    fn device(blocks: Vec<(Cid, Vec<u8>)>) -> Device {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let block = blocks.iter().find(|(cid, _)| {
+                    request
+                        .url()
+                        .starts_with(&format!("/ipfs/{}?format=raw", cid))
+                });
+                let _ = match block {
+                    Some((_, data)) => {
+                        request.respond(tiny_http::Response::from_data(data.clone()))
+                    }
+                    None => request.respond(tiny_http::Response::empty(404)),
+                };
+            }
+        });
+        let eth = EthClient::try_from(EthRemote {
+            rpc_url: "http://127.0.0.1:8545".parse().unwrap(),
+            chain_id: 31337,
+        })
+        .unwrap();
+        Device::new(
+            Address::zero(),
+            IpfsClient::default(),
+            IpfsClient::default(),
+            IpfsGateway::new(url.parse().unwrap()),
+            eth,
+            LocalWallet::new(&mut rand::thread_rng()),
+        )
+    }
+
+    #[tokio::test]
+    async fn manifests_are_exported_with_relative_links() {
+        let image = b"not really a png".to_vec();
+        let text = b"Look at <img src=\"./../visual/cat.png\">".to_vec();
+        let mut manifest = Manifest::default();
+        let objects = [
+            ("visual/cat.png", &image, "image/png", "visual", "A <cat>"),
+            (
+                "writing/hello.md",
+                &text,
+                "text/markdown",
+                "writing",
+                "Hello",
+            ),
+        ];
+        for (path, data, content_type, schema, title) in objects {
+            let mut object = Object::new(raw_cid(data));
+            object.set_content_type(Some(content_type.to_string()));
+            object.set_metdata(json!({ "type": schema, "title": title }));
+            manifest.insert_object(Path::new(path), &object);
+        }
+        let device = device(vec![
+            (raw_cid(&image), image.clone()),
+            (raw_cid(&text), text),
+        ]);
+
+        // Nothing is in the working dir, so content comes from the gateway
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("site");
+        let report = export_manifest(
+            &device,
+            dir.path(),
+            manifest.clone(),
+            &out,
+            &dir.path().join("static"),
+            "Krondor",
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.exported.len(), 2);
+
+        assert!(out.join("index.html").exists());
+        assert!(out.join("browse/visual/index.html").exists());
+        assert_eq!(fs::read(out.join("content/visual/cat.png")).unwrap(), image);
+        let page = fs::read_to_string(out.join("objects/visual/cat.png.html")).unwrap();
+        assert!(page.contains("<h1>A &lt;cat&gt;</h1>"));
+        assert!(page.contains(r#"src="../../content/visual/cat.png""#));
+        assert!(page.contains(r#"href="../../index.html""#));
+        let page = fs::read_to_string(out.join("objects/writing/hello.md.html")).unwrap();
+        assert!(page.contains(r#"<img src="../../content/visual/cat.png">"#));
+
+        // Exports never write over anything
+        let result = export_manifest(
+            &device,
+            dir.path(),
+            manifest,
+            &out,
+            &dir.path().join("static"),
+            "Krondor",
+        )
+        .await;
+        assert!(matches!(result, Err(ExportError::OutNotEmpty(_))));
+    }
+}
@@ -1,44 +1,50 @@
 use std::fmt::Display;
 
-use cid::Cid;
+use serde::Serialize;
 
 use crate::cli::config::{Config, ConfigError};
+use crate::cli::device::DeviceError;
 
-// TODO: check if all services are reachable, print out relevant config info in a pretty way
-pub async fn health(config: &Config) -> Result<(), HealthError> {
+/// Check this device can reach everything it's configured to use
+/// # Returns the report, or an error holding it if anything is unreachable
+pub async fn health(config: &Config) -> Result<HealthReport, HealthError> {
     let device = config.device()?;
 
     let alias = config.device_alias();
 
     let chain_id = device.chain_id();
 
-    let root_cid = match device.read_root_cid().await {
-        Ok(root_cid) => Some(root_cid),
-        Err(_) => None,
-    };
-    let eth_online = root_cid.is_some();
+    let root_cid = device.read_root_cid().await;
 
-    let local_ipfs_online = device.ipfs_id(false).await.is_ok();
+    let local_ipfs = device.ipfs_id(false).await;
 
-    let ipfs_online = device.ipfs_id(true).await.is_ok();
+    let ipfs = device.ipfs_id(true).await;
 
     let report = HealthReport {
         alias,
         chain_id,
-        root_cid,
-        local_ipfs_online,
-        ipfs_online,
-        eth_online,
+        root_cid: root_cid.as_ref().ok().map(|cid| cid.to_string()),
+        local_ipfs_online: local_ipfs.is_ok(),
+        ipfs_online: ipfs.is_ok(),
+        eth_online: root_cid.is_ok(),
     };
 
-    println!("{}", report);
-    Ok(())
+    if let Err(e) = local_ipfs.and(ipfs) {
+        return Err(HealthError::IpfsUnreachable(report, e));
+    }
+    if let Err(e) = root_cid {
+        return Err(HealthError::EthUnreachable(report, e));
+    }
+    Ok(report)
 }
 
-struct HealthReport {
+/// Whether this device can reach everything it's configured to use
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
     alias: Option<String>,
     chain_id: u32,
-    root_cid: Option<Cid>,
+    /// Unset if the root cid couldn't be read from the contract
+    root_cid: Option<String>,
     local_ipfs_online: bool,
     ipfs_online: bool,
     eth_online: bool,
@@ -54,7 +60,7 @@ impl Display for HealthReport {
         let chain_id = self.chain_id;
 
         let root_cid = match &self.root_cid {
-            Some(root_cid) => root_cid.to_string(),
+            Some(root_cid) => root_cid.clone(),
             None => "not configured".to_string(),
         };
         let local_ipfs_online = if self.local_ipfs_online {
@@ -83,4 +89,20 @@ pub enum HealthError {
     Config(#[from] ConfigError),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("ipfs is unreachable -- {0}")]
+    IpfsUnreachable(HealthReport, #[source] DeviceError),
+    #[error("eth is unreachable -- {0}")]
+    EthUnreachable(HealthReport, #[source] DeviceError),
+}
+
+impl HealthError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "health.config",
+            Self::Io(..) => "health.io",
+            Self::IpfsUnreachable(..) => "health.ipfs_unreachable",
+            Self::EthUnreachable(..) => "health.eth_unreachable",
+        }
+    }
 }
@@ -1,8 +1,27 @@
+use std::fmt::Display;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
 use crate::cli::config::{Config, ConfigError};
 
-pub fn init(config: &Config) -> Result<(), InitError> {
+pub fn init(config: &Config) -> Result<InitReport, InitError> {
     config.init()?;
-    Ok(())
+    Ok(InitReport {
+        working_dir: config.working_dir().clone(),
+    })
+}
+
+/// Where a space was initialized
+#[derive(Serialize)]
+pub struct InitReport {
+    working_dir: PathBuf,
+}
+
+impl Display for InitReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Initialized {}", self.working_dir.display())
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -10,3 +29,12 @@ pub enum InitError {
     #[error("config error: {0}")]
     Config(#[from] ConfigError),
 }
+
+impl InitError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "init.config",
+        }
+    }
+}
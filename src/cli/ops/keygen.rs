@@ -0,0 +1,28 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::types::encryption::SecretKey;
+
+/// Generate a key pair for a reader of private objects
+pub fn keygen() -> KeygenReport {
+    let key = SecretKey::generate();
+    KeygenReport {
+        secret_key: key.to_string(),
+        public_key: key.public_key().to_string(),
+    }
+}
+
+/// A generated key pair. The secret key goes to the reader, the public key to `stage --recipient`
+#[derive(Serialize)]
+pub struct KeygenReport {
+    secret_key: String,
+    public_key: String,
+}
+
+impl Display for KeygenReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "secret key: {}", self.secret_key)?;
+        writeln!(f, "public key: {}", self.public_key)
+    }
+}
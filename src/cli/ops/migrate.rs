@@ -0,0 +1,150 @@
+use std::fmt::Display;
+
+use cid::Cid;
+use serde::Serialize;
+
+use crate::cli::config::{Config, ConfigError};
+use crate::cli::device::{Device, DeviceError};
+use crate::cli::ops::with_local_drafts;
+use crate::types::{Manifest, ManifestError, MANIFEST_FORMAT};
+
+/// Rewrite the remote manifest in the current format and push it
+/// The local space must be up to date with the remote and have nothing staged
+pub async fn migrate(config: &Config) -> Result<MigrateReport, MigrateError> {
+    let device = config.device()?;
+    let disk_root_cid = config.root_cid()?;
+    let mut change_log = config.change_log()?;
+    let (root_cid, base) = change_log
+        .first_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?;
+    let (next_root_cid, _next_base) = change_log
+        .last_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?;
+
+    // Migrating rewrites the base, so don't clobber staged work
+    if root_cid != next_root_cid {
+        return Err(MigrateError::StagedChanges);
+    }
+
+    let remote_root_cid = device.read_root_cid().await?;
+    if remote_root_cid == Cid::default() {
+        return Err(MigrateError::NoManifest);
+    }
+    if remote_root_cid != disk_root_cid {
+        return Err(MigrateError::MissmatchedRootCid(
+            remote_root_cid,
+            disk_root_cid,
+        ));
+    }
+
+    let (format, mut manifest) = read_raw_manifest(&device, &remote_root_cid).await?;
+    if format == MANIFEST_FORMAT {
+        return Ok(MigrateReport {
+            from_format: format,
+            to_format: MANIFEST_FORMAT,
+            previous_root_cid: remote_root_cid.to_string(),
+            root_cid: None,
+            transaction: None,
+        });
+    }
+
+    tracing::info!(
+        "migrating manifest from format {} to {}",
+        format,
+        MANIFEST_FORMAT
+    );
+    manifest.set_previous_root(remote_root_cid);
+    manifest.set_version(Default::default());
+
+    let new_root_cid = device.write_manifest(&manifest, true).await?;
+    let transaction = device
+        .update_root_cid(remote_root_cid, new_root_cid)
+        .await?;
+
+    let manifest = with_local_drafts(manifest, base);
+    change_log.wipe(&manifest, &new_root_cid);
+    config.set_root_cid(&new_root_cid)?;
+    config.set_base(&manifest)?;
+    config.set_change_log(change_log)?;
+
+    Ok(MigrateReport {
+        from_format: format,
+        to_format: MANIFEST_FORMAT,
+        previous_root_cid: remote_root_cid.to_string(),
+        root_cid: Some(new_root_cid.to_string()),
+        transaction: transaction.map(|hash| format!("{:#x}", hash)),
+    })
+}
+
+/// What a migration rewrote
+#[derive(Serialize)]
+pub struct MigrateReport {
+    from_format: u32,
+    to_format: u32,
+    previous_root_cid: String,
+    /// Unset if the manifest was already in the current format
+    root_cid: Option<String>,
+    /// The hash of the transaction that updated the root cid, once it was mined
+    transaction: Option<String>,
+}
+
+impl Display for MigrateReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(root_cid) = &self.root_cid else {
+            return write!(f, "Manifest is already at format {}", self.to_format);
+        };
+        writeln!(
+            f,
+            "Migrated manifest from format {} to {}",
+            self.from_format, self.to_format
+        )?;
+        writeln!(
+            f,
+            "Updated root cid from {} to {}",
+            self.previous_root_cid, root_cid
+        )?;
+        if let Some(transaction) = &self.transaction {
+            writeln!(f, "Transaction: {}", transaction)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the manifest at the given cid, returning the format it was stored in
+/// alongside the migrated manifest
+async fn read_raw_manifest(device: &Device, cid: &Cid) -> Result<(u32, Manifest), MigrateError> {
+    let data = device.read_ipfs_data(cid, true).await?;
+    let format = Manifest::format_of(&data)?;
+    let manifest = Manifest::from_slice(&data)?;
+    Ok((format, manifest))
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrateError {
+    #[error("config error: {0}")]
+    Config(#[from] ConfigError),
+    #[error("device error: {0}")]
+    Device(#[from] DeviceError),
+    #[error("manifest error: {0}")]
+    Manifest(#[from] ManifestError),
+    #[error("there are staged changes -- push them before migrating")]
+    StagedChanges,
+    #[error("no manifest has been pushed yet")]
+    NoManifest,
+    #[error("missmatched root cid: {0} != {1} -- pull before migrating")]
+    MissmatchedRootCid(Cid, Cid),
+}
+
+impl MigrateError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "migrate.config",
+            Self::Device(..) => "migrate.device",
+            Self::Manifest(..) => "migrate.manifest",
+            Self::StagedChanges => "migrate.staged_changes",
+            Self::NoManifest => "migrate.no_manifest",
+            Self::MissmatchedRootCid(..) => "migrate.mismatched_root_cid",
+        }
+    }
+}
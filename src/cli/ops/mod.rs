@@ -1,16 +1,26 @@
 mod device_subcommand;
 mod diff;
+mod export;
 mod health;
 mod init;
+mod keygen;
+mod migrate;
+mod preview;
+mod publish;
 mod pull;
 mod push;
 mod stage;
 mod tag;
 
 pub use device_subcommand::{device_subcommand, DeviceSubcommandError};
+pub use export::{export, ExportError};
 pub use health::{health, HealthError};
 pub use init::{init, InitError};
-pub use pull::{pull, PullError};
+pub use keygen::keygen;
+pub use migrate::{migrate, MigrateError};
+pub use preview::{preview, PreviewError};
+pub use publish::{publish, PublishError};
+pub use pull::{pull, with_local_drafts, PullError};
 pub use push::{push, PushError};
-pub use stage::{stage, StageError};
-pub use tag::{tag, TagError};
+pub use stage::{stage, Privacy, StageError};
+pub use tag::{tag, tag_from_file, TagError};
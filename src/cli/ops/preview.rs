@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::path::{Component, Path, PathBuf};
+
+use cid::Cid;
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use tiny_http::{Header, Response, Server};
+
+use crate::cli::config::{Config, ConfigError};
+use crate::cli::device::{Device, DeviceError};
+use crate::cli::output::OutputFormat;
+use crate::cli::render::{ContentSource, RenderError, SiteRenderer, STATIC_DIR};
+use crate::types::content_type::OCTET_STREAM;
+use crate::types::Manifest;
+
+/// A rendered version of the change log, kept until something new is staged
+struct Rendered {
+    cid: Cid,
+    manifest: Manifest,
+    pages: BTreeMap<PathBuf, String>,
+}
+
+/// Where a preview is being served
+#[derive(Serialize)]
+struct PreviewReport {
+    url: String,
+}
+
+impl std::fmt::Display for PreviewReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Previewing staged changes at {}", self.url)
+    }
+}
+
+/// Serve the site as it will look once staged changes are pushed, on localhost.
+/// The manifest is the last version of the ChangeLog, and content is read from the
+/// local ipfs node, so neither the RootCid contract nor the gateway is needed.
+/// Drafts and scheduled objects are shown, with a notice saying they aren't out yet
+/// # Args
+/// - port: the port to listen on
+/// - static_dir: where the site's static markdown and icons live, if anywhere
+/// - title: what to call the site
+/// - output: how to report where the preview is served, once it's up
+pub async fn preview(
+    config: &Config,
+    port: u16,
+    static_dir: &Path,
+    title: &str,
+    output: OutputFormat,
+) -> Result<(), PreviewError> {
+    let working_dir = config.working_dir().clone();
+    let device = config.device()?;
+    let server = Server::http(("127.0.0.1", port)).map_err(PreviewError::Server)?;
+    output.print(&PreviewReport {
+        url: format!("http://127.0.0.1:{}", port),
+    })?;
+
+    let mut rendered: Option<Rendered> = None;
+    loop {
+        let request = tokio::task::block_in_place(|| server.recv())?;
+        let path = request_path(request.url());
+        let response = match respond(
+            config,
+            &device,
+            &working_dir,
+            static_dir,
+            title,
+            &mut rendered,
+            &path,
+        )
+        .await
+        {
+            Ok(Some(response)) => response,
+            Ok(None) => Response::from_string("Not found").with_status_code(404),
+            Err(e) => {
+                tracing::error!("error previewing {}: {}", path.display(), e);
+                Response::from_string(e.to_string()).with_status_code(500)
+            }
+        };
+        // The reader may have gone away, which isn't our problem
+        let _ = request.respond(response);
+    }
+}
+
+/// Find what's served at a path, rendering the site again if anything has been staged
+/// since it was last rendered
+async fn respond(
+    config: &Config,
+    device: &Device,
+    working_dir: &Path,
+    static_dir: &Path,
+    title: &str,
+    rendered: &mut Option<Rendered>,
+    path: &Path,
+) -> Result<Option<Response<Cursor<Vec<u8>>>>, PreviewError> {
+    let change_log = config.change_log()?;
+    let (cid, manifest) = change_log
+        .last_version()
+        .ok_or(PreviewError::NothingStaged)?;
+    if rendered.as_ref().map(|rendered| &rendered.cid) != Some(cid) {
+        // Encrypted objects stay sealed, even in previews
+        let mut manifest = manifest.clone();
+        manifest.retain_objects(|object| object.encryption().is_none());
+        let renderer = SiteRenderer::new(
+            device,
+            working_dir,
+            &manifest,
+            static_dir,
+            title,
+            ContentSource::Staged,
+        );
+        let pages = renderer.pages().await?;
+        tracing::info!("rendered {}", cid);
+        *rendered = Some(Rendered {
+            cid: *cid,
+            manifest,
+            pages,
+        });
+    }
+    let rendered = rendered.as_ref().unwrap();
+
+    if let Some(html) = rendered.pages.get(path) {
+        let response = Response::from_data(html.as_bytes().to_vec());
+        return Ok(Some(with_content_type(
+            response,
+            "text/html; charset=utf-8",
+        )));
+    }
+    if let Ok(file) = path.strip_prefix(STATIC_DIR) {
+        let data = match std::fs::read(static_dir.join(file)) {
+            Ok(data) => data,
+            Err(_) => return Ok(None),
+        };
+        let content_type = mime_guess::from_path(file).first_or_octet_stream();
+        let response = Response::from_data(data);
+        return Ok(Some(with_content_type(response, content_type.as_ref())));
+    }
+    let renderer = SiteRenderer::new(
+        device,
+        working_dir,
+        &rendered.manifest,
+        static_dir,
+        title,
+        ContentSource::Staged,
+    );
+    match renderer.content(path) {
+        Some((object_path, object)) => {
+            let data = renderer.object_data(object_path, object).await?;
+            let content_type = object.content_type().unwrap_or(OCTET_STREAM);
+            Ok(Some(with_content_type(
+                Response::from_data(data),
+                content_type,
+            )))
+        }
+        None => Ok(None),
+    }
+}
+
+/// The path a request url is for within the site. Directories are served by their index
+fn request_path(url: &str) -> PathBuf {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let decoded = percent_decode_str(url).decode_utf8_lossy();
+    // Never serve anything from outside the site
+    let mut path = Path::new(decoded.as_ref())
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .collect::<PathBuf>();
+    if decoded.ends_with('/') || path.as_os_str().is_empty() {
+        path.push("index.html");
+    }
+    path
+}
+
+fn with_content_type(
+    response: Response<Cursor<Vec<u8>>>,
+    content_type: &str,
+) -> Response<Cursor<Vec<u8>>> {
+    match Header::from_bytes("Content-Type", content_type) {
+        Ok(header) => response.with_header(header),
+        Err(_) => response,
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreviewError {
+    #[error("config error: {0}")]
+    Config(#[from] ConfigError),
+    #[error("device error: {0}")]
+    Device(#[from] DeviceError),
+    #[error("render error: {0}")]
+    Render(#[from] RenderError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("serde error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("could not start the preview server: {0}")]
+    Server(Box<dyn std::error::Error + Send + Sync>),
+    #[error("nothing to preview -- has this space been initialized?")]
+    NothingStaged,
+}
+
+impl PreviewError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "preview.config",
+            Self::Device(..) => "preview.device",
+            Self::Render(..) => "preview.render",
+            Self::Io(..) => "preview.io",
+            Self::Serde(..) => "preview.serde",
+            Self::Server(..) => "preview.server",
+            Self::NothingStaged => "preview.nothing_staged",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_never_leave_the_site() {
+        assert_eq!(request_path("/"), PathBuf::from("index.html"));
+        assert_eq!(request_path("/browse/"), PathBuf::from("browse/index.html"));
+        assert_eq!(
+            request_path("/objects/a%20b.md.html?x=1#top"),
+            PathBuf::from("objects/a b.md.html")
+        );
+        assert_eq!(
+            request_path("/../../etc/passwd"),
+            PathBuf::from("etc/passwd")
+        );
+        assert_eq!(
+            request_path("/content/%2e%2e/%2E%2E/secret"),
+            PathBuf::from("content/secret")
+        );
+        assert_eq!(request_path("/.."), PathBuf::from("index.html"));
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Display;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::cli::config::{Config, ConfigError};
+use crate::cli::device::DeviceError;
+use crate::types::Publication;
+
+/// Set when an object is shown to readers, recording it as a new version of the ChangeLog.
+/// Drafts are kept out of the published manifest, and scheduled objects are pushed
+/// but hidden by the site until their date
+pub async fn publish(
+    config: &Config,
+    path: &Path,
+    publication: Publication,
+) -> Result<PublishReport, PublishError> {
+    let device = config.device()?;
+    let mut change_log = config.change_log()?;
+    let (_cid, base_manifest) = change_log
+        .last_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?;
+    let mut manifest = base_manifest.clone();
+
+    // Paths are relative to the working directory
+    let path = path
+        .components()
+        .filter(|component| component != &Component::CurDir)
+        .collect::<PathBuf>();
+    let object = manifest
+        .get_object_mut(&path)
+        .ok_or(PublishError::ObjectDoesNotExist(path.clone()))?;
+    let mut report = PublishReport {
+        path: path.clone(),
+        publication: match publication {
+            Publication::Draft => "draft",
+            Publication::Published => "published",
+            Publication::Scheduled { .. } => "scheduled",
+        },
+        at: match publication {
+            Publication::Scheduled { at } => Some(at),
+            _ => None,
+        },
+        changed: false,
+    };
+    if object.publication() == &publication {
+        return Ok(report);
+    }
+    if object.publication().is_draft() {
+        change_log.set_published(&path);
+    }
+    object.set_publication(publication);
+    report.changed = true;
+
+    let cid = device.hash_manifest(&manifest, false).await?;
+    let log = change_log.log().clone();
+    change_log.update(&log, &manifest, &cid);
+    config.set_change_log(change_log)?;
+    Ok(report)
+}
+
+/// When an object is shown to readers, and whether that changed
+#[derive(Serialize)]
+pub struct PublishReport {
+    path: PathBuf,
+    /// One of `draft`, `published`, or `scheduled`
+    publication: &'static str,
+    /// When a scheduled object is published
+    at: Option<DateTime<Utc>>,
+    changed: bool,
+}
+
+impl Display for PublishReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = if self.changed { "now" } else { "already" };
+        write!(
+            f,
+            "{} is {} {}",
+            self.path.display(),
+            state,
+            self.publication
+        )?;
+        if let Some(at) = self.at {
+            write!(f, " for {}", at.to_rfc3339())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PublishError {
+    #[error("config error: {0}")]
+    Config(#[from] ConfigError),
+    #[error("device error: {0}")]
+    Device(#[from] DeviceError),
+    #[error("object does not exist: {0} -- has it been staged?")]
+    ObjectDoesNotExist(PathBuf),
+}
+
+impl PublishError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "publish.config",
+            Self::Device(..) => "publish.device",
+            Self::ObjectDoesNotExist(..) => "publish.object_does_not_exist",
+        }
+    }
+}
@@ -1,12 +1,20 @@
+use std::fmt::Display;
 use std::fs::File;
-use std::io::Write;
+use std::io::{Cursor, Write};
 use std::path::PathBuf;
 
 use cid::Cid;
+use serde::Serialize;
+use tempfile::NamedTempFile;
 
 use crate::cli::changes::ChangeLog;
 use crate::cli::config::{Config, ConfigError};
 use crate::cli::device::{Device, DeviceError};
+use crate::cli::output::write_lines;
+use crate::cli::progress::ProgressBars;
+use crate::ipfs::ProgressReporter;
+use crate::types::encryption::{self, Encryption, EncryptionError, SecretKey};
+use crate::types::{Manifest, Object};
 
 pub async fn file_needs_pull(
     device: &Device,
@@ -28,18 +36,76 @@ pub async fn file_needs_pull(
     }
 }
 
-pub async fn pull_file(device: &Device, cid: &Cid, path: &PathBuf) -> Result<(), PullError> {
-    // TODO: replace with gateway read
-    let data = device.read_ipfs_gateway_data(cid, None).await?;
+/// Write an object's content to disk as it's read from the gateway, so large files never
+/// have to be held in memory. It's written to a temporary file first, which only replaces
+/// the one at `path` once its hash is checked against the cid
+pub async fn pull_file(
+    device: &Device,
+    cid: &Cid,
+    path: &PathBuf,
+    progress: &dyn ProgressReporter,
+) -> Result<(), PullError> {
+    let mut object_path = path.clone();
+    object_path.pop();
+    std::fs::create_dir_all(&object_path)?;
+    let mut file = NamedTempFile::new_in(&object_path)?;
+    device
+        .read_ipfs_gateway_data_to(cid, &mut file, progress)
+        .await?;
+    file.flush()?;
+
+    let hash = device.hash_ipfs_data(file.reopen()?, false).await?;
+    if hash != *cid {
+        return Err(PullError::CidMismatch(hash, *cid));
+    }
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Drafts never leave this device, so carry them over from our base into a manifest
+/// we read from the remote
+pub fn with_local_drafts(mut manifest: Manifest, base: &Manifest) -> Manifest {
+    for (path, object) in base.objects() {
+        if object.publication().is_draft() && !manifest.objects().contains_key(path) {
+            manifest.insert_object(path, object);
+        }
+    }
+    manifest
+}
+
+/// Pull and decrypt an encrypted object, if this device is one of its recipients
+/// # Returns the cid of the decrypted file, to track it by
+pub async fn pull_encrypted_file(
+    device: &Device,
+    object: &Object,
+    encryption: &Encryption,
+    key: &SecretKey,
+    path: &PathBuf,
+) -> Result<Option<Cid>, PullError> {
+    if !encryption.is_recipient(key) {
+        tracing::warn!(
+            "{} is encrypted for other keys, skipping -- staging from this device would remove it",
+            path.display()
+        );
+        return Ok(None);
+    }
+    let sealed = device.read_ipfs_gateway_data(object.cid()).await?;
+    let data = encryption::decrypt(&sealed, encryption, key)?;
+    let cid = device
+        .hash_ipfs_data(Cursor::new(data.clone()), false)
+        .await?;
+    if !file_needs_pull(device, path, &cid).await? {
+        return Ok(Some(cid));
+    }
     let mut object_path = path.clone();
     object_path.pop();
     std::fs::create_dir_all(object_path)?;
     let mut file = std::fs::File::create(path)?;
     file.write_all(&data)?;
-    Ok(())
+    Ok(Some(cid))
 }
 
-pub async fn pull(config: &Config) -> Result<(), PullError> {
+pub async fn pull(config: &Config) -> Result<PullReport, PullError> {
     let on_disk_device = config.on_disk_device()?;
     let alias = on_disk_device.alias();
     let base_root_cid = Config::root_cid(config)?;
@@ -57,6 +123,7 @@ pub async fn pull(config: &Config) -> Result<(), PullError> {
     if root_cid != Cid::default() {
         tracing::info!("root cid is not set");
         manifest = device.read_manifest(&root_cid, true).await?;
+        manifest = with_local_drafts(manifest, &base_manifest);
     }
 
     if manifest == base_manifest {
@@ -66,21 +133,82 @@ pub async fn pull(config: &Config) -> Result<(), PullError> {
     }
 
     let objects = manifest.objects();
+    let mut change_log = ChangeLog::new(alias, &manifest, &root_cid);
+    let key = config.encryption_key()?;
 
+    let mut report = PullReport {
+        root_cid: root_cid.to_string(),
+        pulled: Vec::new(),
+        decrypted: Vec::new(),
+    };
+    let mut needs_pull = Vec::new();
     for (path, object) in objects.iter() {
         let working_path = config.working_dir().join(path);
-        if !file_needs_pull(&device, &working_path, object.cid()).await? {
+        // Drafts were never pushed, so whatever we have is all there is
+        if object.publication().is_draft() {
+            continue;
+        }
+        // Track decrypted files by their own cids, so they don't look modified
+        if let Some(encryption) = object.encryption() {
+            let pulled =
+                pull_encrypted_file(&device, object, encryption, &key, &working_path).await?;
+            if let Some(cid) = pulled {
+                change_log.set_base_cid(path, cid);
+                report.decrypted.push(path.clone());
+            }
             continue;
         }
+        if file_needs_pull(&device, &working_path, object.cid()).await? {
+            needs_pull.push((path, object.cid(), working_path));
+        }
+    }
 
-        // TODO: this should use the gateway
-        pull_file(&device, object.cid(), &working_path).await?;
+    let progress = ProgressBars::new(Some(needs_pull.len() as u64));
+    for (path, cid, working_path) in needs_pull {
+        progress.start(&path.display().to_string(), None);
+        pull_file(&device, cid, &working_path, &progress).await?;
+        progress.finish();
+        report.pulled.push(PulledFile {
+            path: path.clone(),
+            cid: cid.to_string(),
+        });
     }
+    progress.clear();
 
-    let change_log = ChangeLog::new(alias, &manifest, &root_cid);
     config.set_change_log(change_log)?;
+    config.set_schemas(manifest.schemas())?;
 
-    Ok(())
+    Ok(report)
+}
+
+/// What a pull wrote to the working directory
+#[derive(Serialize)]
+pub struct PullReport {
+    root_cid: String,
+    /// Files whose content changed, and were written
+    pulled: Vec<PulledFile>,
+    /// Encrypted files this device could read, and were decrypted in place
+    decrypted: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+pub struct PulledFile {
+    path: PathBuf,
+    cid: String,
+}
+
+impl Display for PullReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for file in &self.pulled {
+            writeln!(f, "Pulled {} as {}", file.path.display(), file.cid)?;
+        }
+        write_lines(
+            f,
+            "Decrypted ",
+            self.decrypted.iter().map(|path| path.display()),
+        )?;
+        writeln!(f, "Up to date with {}", self.root_cid)
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -93,6 +221,25 @@ pub enum PullError {
     Device(#[from] DeviceError),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("encryption error: {0}")]
+    Encryption(#[from] EncryptionError),
     #[error("path is a directory")]
     PathIsDirectory(PathBuf),
+    #[error("pulled content hashed to {0}, not {1}")]
+    CidMismatch(Cid, Cid),
+}
+
+impl PullError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "pull.config",
+            Self::Cid(..) => "pull.cid",
+            Self::Device(..) => "pull.device",
+            Self::Io(..) => "pull.io",
+            Self::Encryption(..) => "pull.encryption",
+            Self::PathIsDirectory(..) => "pull.path_is_directory",
+            Self::CidMismatch(..) => "pull.cid_mismatch",
+        }
+    }
 }
@@ -1,43 +1,212 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use cid::Cid;
+use serde::Serialize;
 
-use crate::cli::changes::ChangeType;
+use crate::cli::changes::{ChangeType, Log};
 use crate::cli::config::{Config, ConfigError};
 use crate::cli::device::{Device, DeviceError};
-use crate::types::Manifest;
+use crate::cli::progress::{ProgressBars, ProgressReader};
+use crate::ipfs::ProgressReporter;
+use crate::types::content_type::OCTET_STREAM;
+use crate::types::feed::{Enclosure, Feed, FeedItem, FeedKind, Site};
+use crate::types::front_matter;
+use crate::types::media::{Media, Variant};
+use crate::types::schema::{Audio, Schema, Writing};
+use crate::types::search::{Document, SearchIndex};
+use crate::types::{Manifest, Object, Publication};
 
-/// Push a file to the remote ipfs node
+/// Push a file to the remote ipfs node, streaming it from disk
 pub async fn push_file(
     device: &Device,
     file_path: &PathBuf,
     attempt: u32,
+    progress: &Arc<ProgressBars>,
 ) -> Result<Cid, PushError> {
     let sleep_time = 4 + 4u64.pow(attempt);
     // Sleep for a bit to avoid rate limits
     if attempt > 0 {
-        println!(
+        progress.println(format!(
             "Sleeping for {} seconds before pushing the file",
             sleep_time
-        );
+        ));
     }
     std::thread::sleep(std::time::Duration::from_secs(sleep_time));
     let file = File::open(file_path)?;
-    let cid = device.write_ipfs_data(file, true).await?;
-    println!("Pushed {} as {}", file_path.display(), cid);
+    progress.start(
+        &file_path.display().to_string(),
+        Some(file.metadata()?.len()),
+    );
+    let reader = ProgressReader::new(file, progress.clone());
+    let cid = device.write_ipfs_data(reader, true).await?;
+    progress.finish();
     Ok(cid)
 }
 
-pub async fn push(config: &Config, minimal: bool, force: bool) -> Result<(), PushError> {
+/// Push data staged on the local ipfs node, but not in the working dir, to the remote
+/// # Returns whether the data needed pushing
+pub async fn push_local(device: &Device, cid: &Cid) -> Result<bool, PushError> {
+    if device.stat_ipfs_data(cid, true).await?.is_some() {
+        return Ok(false);
+    }
+    let data = device.read_ipfs_data(cid, false).await?;
+    let pushed_cid = device.write_ipfs_data(Cursor::new(data), true).await?;
+    if pushed_cid != *cid {
+        return Err(PushError::CidMismatch(pushed_cid, *cid));
+    }
+    Ok(true)
+}
+
+/// Push the resized variants of an object from the local ipfs node to the remote,
+/// skipping any the remote already has
+/// # Returns the variants that needed pushing
+pub async fn push_variants<'a>(
+    device: &Device,
+    object: &'a Object,
+) -> Result<Vec<&'a Variant>, PushError> {
+    let variants = match object.media() {
+        Some(Media::Image(info)) => &info.variants,
+        _ => return Ok(Vec::new()),
+    };
+    let mut pushed = Vec::new();
+    for variant in variants {
+        if push_local(device, &variant.cid).await? {
+            pushed.push(variant);
+        }
+    }
+    Ok(pushed)
+}
+
+/// Whether an object has changed since the last push, and should be pushed
+/// # Returns None if the object isn't in the log
+fn needs_push(log: &Log, path: &Path, object: &Object, force: bool) -> Option<bool> {
+    // Drafts stay on the local node until they're published
+    if object.publication().is_draft() {
+        return Some(false);
+    }
+    match log.get(path)? {
+        (_cid, ChangeType::Base | ChangeType::Removed) => Some(force),
+        _ => Some(true),
+    }
+}
+
+/// Index the titles and descriptions of every tagged object, along with the bodies of writing.
+/// Bodies are read from what was staged, not the working directory, so the index matches
+/// the content being published
+async fn search_index(device: &Device, manifest: &Manifest) -> Result<SearchIndex, PushError> {
+    let mut index = SearchIndex::default();
+    for (path, object) in manifest.objects() {
+        // Encrypted objects aren't for everyone, so they aren't made any easier to find
+        if object.encryption().is_some() {
+            continue;
+        }
+        let metadata = match manifest.schemas().validate(object.metadata()) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let title = match metadata["title"].as_str() {
+            Some(title) => title,
+            None => continue,
+        };
+        let body = if metadata["type"] == Writing::NAME {
+            let data = device.read_ipfs_data(object.cid(), false).await?;
+            let text = String::from_utf8_lossy(&data);
+            Some(front_matter::strip(&text).to_string())
+        } else {
+            None
+        };
+        index.insert(Document {
+            path: path.clone(),
+            cid: *object.cid(),
+            title,
+            description: metadata["description"].as_str(),
+            body: body.as_deref(),
+        });
+    }
+    Ok(index)
+}
+
+/// Build feeds of the writing and audio readers can see right now.
+/// Audio is listed as podcast episodes, with enclosures served by the gateway
+fn feeds(
+    device: &Device,
+    working_dir: &Path,
+    manifest: &Manifest,
+    site: &Site,
+    now: &DateTime<Utc>,
+) -> std::io::Result<Vec<Feed>> {
+    let mut writing = Feed::new(site.clone(), FeedKind::Writing);
+    let mut podcast = Feed::new(site.clone(), FeedKind::Podcast);
+    for (path, object) in manifest.objects() {
+        // Encrypted objects aren't for everyone, and scheduled ones aren't out yet
+        if object.encryption().is_some() || !object.publication().is_published_at(now) {
+            continue;
+        }
+        let metadata = match manifest.schemas().validate(object.metadata()) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let title = match metadata["title"].as_str() {
+            Some(title) => title.to_string(),
+            None => continue,
+        };
+        let published = match object.publication() {
+            Publication::Scheduled { at } => *at,
+            _ => *object.created_at(),
+        };
+        let mut item = FeedItem {
+            path: path.clone(),
+            cid: *object.cid(),
+            title,
+            description: metadata["description"].as_str().map(str::to_string),
+            published,
+            updated: (*object.updated_at()).max(published),
+            enclosure: None,
+        };
+        if metadata["type"] == Writing::NAME {
+            writing.insert(item);
+        } else if metadata["type"] == Audio::NAME {
+            let duration_secs = match object.media() {
+                Some(Media::Audio(info)) => Some(info.duration_ms / 1000),
+                _ => None,
+            };
+            item.enclosure = Some(Enclosure {
+                url: device.ipfs_gateway_url(object.cid()).to_string(),
+                length: std::fs::metadata(working_dir.join(path))?.len(),
+                content_type: object.content_type().unwrap_or(OCTET_STREAM).to_string(),
+                duration_secs,
+            });
+            podcast.insert(item);
+        }
+    }
+    Ok(vec![writing, podcast])
+}
+
+pub async fn push(
+    config: &Config,
+    minimal: bool,
+    force: bool,
+    sharded: Option<bool>,
+    site: Option<&Site>,
+) -> Result<PushReport, PushError> {
     let working_dir = config.working_dir().clone();
     let device = config.device()?;
     let disk_root_cid = config.root_cid()?;
     let disk_base = config.base()?;
     let change_log = config.change_log()?;
     let log = change_log.log();
-    let (root_cid, base) = change_log.first_version().unwrap();
-    let (next_root_cid, next_base) = change_log.last_version().unwrap();
+    let (root_cid, base) = change_log
+        .first_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?;
+    let (next_root_cid, next_base) = change_log
+        .last_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?;
 
     // Check our root matches our on-disk root
     if root_cid != &disk_root_cid {
@@ -46,7 +215,10 @@ pub async fn push(config: &Config, minimal: bool, force: bool) -> Result<(), Pus
 
     // Check our base matches our on-disk base
     if base != &disk_base {
-        return Err(PushError::MissmatchedBase(base.clone(), disk_base));
+        return Err(PushError::MissmatchedBase(
+            Box::new(base.clone()),
+            Box::new(disk_base),
+        ));
     }
 
     if !force {
@@ -63,30 +235,41 @@ pub async fn push(config: &Config, minimal: bool, force: bool) -> Result<(), Pus
 
     let objects = next_base.objects();
 
+    let mut report = PushReport {
+        previous_root_cid: root_cid.to_string(),
+        ..Default::default()
+    };
+
     // Tell the remote to pin all the objects
+    let progress = Arc::new(ProgressBars::new(None));
     for (path, object) in objects.iter() {
-        match log.get(path) {
-            Some((_cid, ChangeType::Base | ChangeType::Removed)) => {
-                if !force {
-                    continue;
-                }
-            }
-            Some(_) => {}
-            None => {
-                return Err(PushError::MissingLogEntry(path.clone()));
-            }
+        let needs_push = needs_push(log, path, object, force)
+            .ok_or_else(|| PushError::MissingLogEntry(path.clone()))?;
+        if !needs_push {
+            continue;
+        }
+        // Encrypted objects were sealed when staged, so their ciphertext only lives locally
+        if object.encryption().is_some() {
+            push_local(&device, object.cid()).await?;
+            report.pushed.push(PushedObject {
+                path: path.clone(),
+                cid: object.cid().to_string(),
+                encrypted: true,
+            });
+            continue;
         }
         let tries: u32 = 5;
         for attempt in 0..tries {
-            let cid = match push_file(&device, &working_dir.join(path), attempt).await {
+            let cid = match push_file(&device, &working_dir.join(path), attempt, &progress).await {
                 Ok(cid) => cid,
                 Err(e) => {
                     if attempt == tries - 1 {
-                        println!("Failed to push {}", path.display());
-                        return Err(PushError::PushFailed);
+                        progress.clear();
+                        tracing::error!("failed to push {}", path.display());
+                        return Err(PushError::PushFailed(path.clone(), Box::new(e)));
                     }
-                    println!("Error pinning {}: {}", path.display(), e);
-                    println!("Retrying...");
+                    progress.println(format!("Error pinning {}: {}", path.display(), e));
+                    progress.println("Retrying...");
                     continue;
                 }
             };
@@ -95,28 +278,164 @@ pub async fn push(config: &Config, minimal: bool, force: bool) -> Result<(), Pus
             }
             break;
         }
+        report.pushed.push(PushedObject {
+            path: path.clone(),
+            cid: object.cid().to_string(),
+            encrypted: false,
+        });
+        for variant in push_variants(&device, object).await? {
+            report.variants.push(PushedVariant {
+                path: path.clone(),
+                width: variant.width,
+                cid: variant.cid.to_string(),
+            });
+        }
+    }
+    progress.clear();
+
+    // Drafts are kept in our base, but not in the manifest we publish
+    let mut published = next_base.clone();
+    published.retain_objects(|object| !object.publication().is_draft());
+
+    // Publish a search index alongside the manifest, so the site can be searched without a server
+    let mut next_base = next_base.clone();
+    // Note: written as a UnixFS file, since an index can outgrow a single block
+    let index = search_index(&device, &published).await?;
+    let search_cid = device
+        .write_ipfs_data(Cursor::new(index.to_vec()?), true)
+        .await?;
+    report.search_cid = search_cid.to_string();
+    next_base.set_search(Some(search_cid));
+    published.set_search(Some(search_cid));
+
+    // Publish feeds of writing and audio, so readers can subscribe to the site
+    match site {
+        Some(site) => {
+            let mut feed_cids = BTreeMap::new();
+            for feed in feeds(&device, &working_dir, &published, site, &Utc::now())? {
+                if feed.is_empty() {
+                    continue;
+                }
+                let name = feed.kind().name();
+                let formats = [
+                    ("atom", feed.atom()),
+                    ("rss", feed.rss()),
+                    ("json", serde_json::to_string_pretty(&feed.json())?),
+                ];
+                for (extension, data) in formats {
+                    let file_name = format!("{}.{}", name, extension);
+                    let cid = device
+                        .write_ipfs_data(Cursor::new(data.into_bytes()), true)
+                        .await?;
+                    report.feeds.insert(file_name.clone(), cid.to_string());
+                    feed_cids.insert(file_name, cid);
+                }
+            }
+            next_base.set_feeds(feed_cids.clone());
+            published.set_feeds(feed_cids);
+        }
+        None => tracing::info!("no site url configured, not updating feeds"),
     }
+    let next_base = &next_base;
 
     // Write the dor store against the remote
-    let new_root_cid = device.write_manifest(next_base, true).await?;
+    let new_root_cid = if keep_sharded(&device, root_cid, sharded).await? {
+        device.write_sharded_manifest(&published, true).await?
+    } else {
+        device.write_manifest(&published, true).await?
+    };
+
+    report.root_cid = new_root_cid.to_string();
 
     // If we are in minimal mode, we are done here
     if minimal {
-        println!("Minimal mode, not updating root cid");
-        println!("Manifest written to {}", new_root_cid);
-        return Ok(());
+        return Ok(report);
     }
 
-    println!("Updating root cid from {} to {}", root_cid, new_root_cid);
     // Push the new root cid to the eth client
-    device.update_root_cid(*root_cid, new_root_cid).await?;
+    let transaction = device.update_root_cid(*root_cid, new_root_cid).await?;
+    report.updated_root_cid = true;
+    report.transaction = transaction.map(|hash| format!("{:#x}", hash));
     let mut change_log = change_log.clone();
     change_log.wipe(next_base, &new_root_cid);
     config.set_root_cid(&new_root_cid)?;
     config.set_base(next_base)?;
     config.set_change_log(change_log)?;
 
-    Ok(())
+    Ok(report)
+}
+
+/// What a push sent to the remote, and the root cid it published
+#[derive(Serialize, Default)]
+pub struct PushReport {
+    pushed: Vec<PushedObject>,
+    /// Resized images the remote didn't have yet
+    variants: Vec<PushedVariant>,
+    search_cid: String,
+    /// Feed file names, and the cids they were pushed as
+    feeds: BTreeMap<String, String>,
+    previous_root_cid: String,
+    /// The cid the manifest was written to
+    root_cid: String,
+    /// Whether the contract now points at the root cid. It doesn't in minimal mode
+    updated_root_cid: bool,
+    /// The hash of the transaction that updated the root cid, once it was mined
+    transaction: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct PushedObject {
+    path: PathBuf,
+    cid: String,
+    encrypted: bool,
+}
+
+#[derive(Serialize)]
+pub struct PushedVariant {
+    path: PathBuf,
+    width: u32,
+    cid: String,
+}
+
+impl Display for PushReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for object in &self.pushed {
+            let encrypted = if object.encrypted { "encrypted " } else { "" };
+            writeln!(
+                f,
+                "Pushed {}{} as {}",
+                encrypted,
+                object.path.display(),
+                object.cid
+            )?;
+        }
+        for variant in &self.variants {
+            writeln!(
+                f,
+                "Pushed {}w variant of {} as {}",
+                variant.width,
+                variant.path.display(),
+                variant.cid
+            )?;
+        }
+        writeln!(f, "Pushed search index as {}", self.search_cid)?;
+        for (name, cid) in &self.feeds {
+            writeln!(f, "Pushed {} feed as {}", name, cid)?;
+        }
+        if !self.updated_root_cid {
+            writeln!(f, "Minimal mode, not updating root cid")?;
+            return writeln!(f, "Manifest written to {}", self.root_cid);
+        }
+        writeln!(
+            f,
+            "Updated root cid from {} to {}",
+            self.previous_root_cid, self.root_cid
+        )?;
+        if let Some(transaction) = &self.transaction {
+            writeln!(f, "Transaction: {}", transaction)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -129,14 +448,154 @@ pub enum PushError {
     CidMismatch(Cid, Cid),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("serde error: {0}")]
+    Serde(#[from] serde_json::Error),
     #[error("no changes to push")]
     NoChanges,
     #[error("missmatched root cid: {0} != {1}")]
     MissmatchedRootCid(Cid, Cid),
     #[error("missmatched base: {0:?} != {1:?}")]
-    MissmatchedBase(Manifest, Manifest),
-    #[error("push failed")]
-    PushFailed,
+    MissmatchedBase(Box<Manifest>, Box<Manifest>),
+    #[error("failed to push {} after retrying", .0.display())]
+    PushFailed(PathBuf, #[source] Box<PushError>),
     #[error("missing log entry for {0}")]
     MissingLogEntry(PathBuf),
 }
+
+impl PushError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "push.config",
+            Self::Device(..) => "push.device",
+            Self::CidMismatch(..) => "push.cid_mismatch",
+            Self::Io(..) => "push.io",
+            Self::Serde(..) => "push.serde",
+            Self::NoChanges => "push.no_changes",
+            Self::MissmatchedRootCid(..) => "push.mismatched_root_cid",
+            Self::MissmatchedBase(..) => "push.mismatched_base",
+            Self::PushFailed(_, error) => error.code(),
+            Self::MissingLogEntry(..) => "push.missing_log_entry",
+        }
+    }
+}
+
+/// Whether to write a sharded manifest. Unless asked to switch, the manifest keeps the
+/// layout of the root it replaces
+async fn keep_sharded(
+    device: &Device,
+    root_cid: &Cid,
+    sharded: Option<bool>,
+) -> Result<bool, PushError> {
+    match sharded {
+        Some(sharded) => Ok(sharded),
+        None if *root_cid == Cid::default() => Ok(false),
+        None => Ok(device.is_sharded_manifest(root_cid, true).await?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Multihash;
+    use ethers::signers::LocalWallet;
+    use ethers::types::Address;
+    use url::Url;
+
+    use super::*;
+    use crate::cli::changes::ChangeLog;
+    use crate::eth::{EthClient, EthRemote};
+    use crate::ipfs::{IpfsClient, IpfsGateway, IpfsRemote};
+
+    /// The Cid of a raw block, hashed with blake3
+    fn raw_cid(data: &[u8]) -> Cid {
+        let hash = Multihash::wrap(0x1e, blake3::hash(data).as_bytes()).unwrap();
+        Cid::new_v1(0x55, hash)
+    }
+
+    /// A device whose remote ipfs api serves the given blocks, and nothing else
+    fn device(blocks: Vec<(Cid, Vec<u8>)>) -> Device {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url: Url = format!("http://{}", server.server_addr()).parse().unwrap();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let block = blocks.iter().find(|(cid, _)| {
+                    request
+                        .url()
+                        .starts_with(&format!("/api/v0/block/get?arg={}", cid))
+                });
+                let _ = match block {
+                    Some((_, data)) => {
+                        request.respond(tiny_http::Response::from_data(data.clone()))
+                    }
+                    None => request.respond(tiny_http::Response::empty(404)),
+                };
+            }
+        });
+        let ipfs = IpfsClient::try_from(IpfsRemote {
+            api_url: url.clone(),
+            gateway_url: url.clone(),
+            fallback_gateway_urls: vec![],
+        })
+        .unwrap();
+        let eth = EthClient::try_from(EthRemote {
+            rpc_url: "http://127.0.0.1:8545".parse().unwrap(),
+            chain_id: 31337,
+        })
+        .unwrap();
+        Device::new(
+            Address::zero(),
+            IpfsClient::default(),
+            ipfs,
+            IpfsGateway::new(url),
+            eth,
+            LocalWallet::new(&mut rand::thread_rng()),
+        )
+    }
+
+    #[tokio::test]
+    async fn manifests_keep_their_layout_unless_asked_to_switch() {
+        let mut manifest = Manifest::default();
+        manifest.insert_object(Path::new("writing/hello.md"), &Object::new(Cid::default()));
+        let flat = serde_json::to_vec(&manifest).unwrap();
+        let sharded = serde_json::to_vec(&manifest.shard().unwrap().0).unwrap();
+        let (flat_cid, sharded_cid) = (raw_cid(&flat), raw_cid(&sharded));
+        let device = device(vec![(flat_cid, flat), (sharded_cid, sharded)]);
+
+        assert!(!keep_sharded(&device, &flat_cid, None).await.unwrap());
+        assert!(keep_sharded(&device, &sharded_cid, None).await.unwrap());
+        assert!(!keep_sharded(&device, &sharded_cid, Some(false))
+            .await
+            .unwrap());
+        assert!(keep_sharded(&device, &flat_cid, Some(true)).await.unwrap());
+        // Nothing has been pushed yet
+        assert!(!keep_sharded(&device, &Cid::default(), None).await.unwrap());
+    }
+
+    #[test]
+    fn drafts_are_pushed_once_published() {
+        let path = PathBuf::from("writing/draft.md");
+        let mut object = Object::new(Cid::default());
+        object.set_publication(Publication::Draft);
+        let mut manifest = Manifest::default();
+        manifest.insert_object(&path, &object);
+
+        // Staged as a draft, so the first push leaves it alone
+        let mut change_log =
+            ChangeLog::new("test".to_string(), &Manifest::default(), &Cid::default());
+        let mut log = change_log.log().clone();
+        log.insert(path.clone(), (*object.cid(), ChangeType::Added));
+        change_log.update(&log, &manifest, &Cid::default());
+        assert!(!needs_push(change_log.log(), &path, &object, false).unwrap());
+        change_log.wipe(&manifest, &Cid::default());
+
+        // Publishing it means the next push sends it
+        change_log.set_published(&path);
+        object.set_publication(Publication::Published);
+        manifest.insert_object(&path, &object);
+        assert!(needs_push(change_log.log(), &path, &object, false).unwrap());
+
+        // And only that push
+        change_log.wipe(&manifest, &Cid::default());
+        assert!(!needs_push(change_log.log(), &path, &object, false).unwrap());
+    }
+}
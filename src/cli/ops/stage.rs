@@ -1,14 +1,20 @@
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
 
 use cid::Cid;
+use serde_json::{Map, Value};
 
 use super::diff::{diff, DiffError};
 
-use crate::cli::changes::ChangeType;
+use crate::cli::changes::{ChangeType, DisplayableLog};
 use crate::cli::config::{Config, ConfigError};
 use crate::cli::device::{Device, DeviceError};
-use crate::types::Object;
+use crate::types::encryption::{self, EncryptionError, PublicKey};
+use crate::types::media::{ImageInfo, Media, Variant};
+use crate::types::schema::{Schema, SchemaDocument, Visual, Writing};
+use crate::types::{content_type, front_matter, media};
+use crate::types::{Derived, Object};
 
 /// Stage a file against the local ipfs node
 pub async fn stage_file(device: &Device, file_path: &PathBuf) -> Result<Cid, StageError> {
@@ -18,14 +24,166 @@ pub async fn stage_file(device: &Device, file_path: &PathBuf) -> Result<Cid, Sta
     Ok(cid)
 }
 
-pub async fn stage(config: &Config) -> Result<(), StageError> {
+/// Which paths to encrypt, and who for, when staging
+/// - encrypt: paths to start encrypting
+/// - recipients: public keys to encrypt them for, besides this device's own
+/// - decrypt: paths to stop encrypting
+#[derive(Debug, Clone, Default)]
+pub struct Privacy {
+    pub encrypt: Vec<PathBuf>,
+    pub recipients: Vec<PublicKey>,
+    pub decrypt: Vec<PathBuf>,
+}
+
+/// Encrypt a file for the recipients and stage the ciphertext against the local ipfs node.
+/// The plaintext is checked against the cid in the change log first
+async fn stage_encrypted_file(
+    device: &Device,
+    file_path: &PathBuf,
+    cid: &Cid,
+    recipients: &[PublicKey],
+) -> Result<(Cid, encryption::Encryption), StageError> {
+    let data = std::fs::read(file_path)?;
+    let hashed_cid = device
+        .hash_ipfs_data(Cursor::new(data.clone()), false)
+        .await?;
+    if hashed_cid != *cid {
+        return Err(StageError::CidMismatch(hashed_cid, *cid));
+    }
+    let (sealed, encryption) = encryption::encrypt(&data, recipients)?;
+    let sealed_cid = device.write_ipfs_data(Cursor::new(sealed), false).await?;
+    Ok((sealed_cid, encryption))
+}
+
+/// Add resized variants of an image to the local ipfs node, recording them in its info.
+/// Only images the visual schema accepts are resized
+async fn stage_variants(
+    device: &Device,
+    schemas: &SchemaDocument,
+    working_path: &Path,
+    info: &mut ImageInfo,
+) -> Result<(), StageError> {
+    let visual = working_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .zip(schemas.get(Visual::NAME))
+        .is_some_and(|(ext, schema)| schema.accepts_extension(ext));
+    if !visual {
+        return Ok(());
+    }
+    let resized = match media::resize(working_path, info) {
+        Ok(resized) => resized,
+        Err(err) => {
+            tracing::warn!("{}: {}", working_path.display(), err);
+            return Ok(());
+        }
+    };
+    for resized in resized {
+        let cid = device
+            .write_ipfs_data(Cursor::new(resized.data), false)
+            .await?;
+        info.variants.push(Variant {
+            width: resized.width,
+            height: resized.height,
+            cid,
+        });
+    }
+    Ok(())
+}
+
+/// Derive Writing metadata from the front matter of a markdown file, if it has any.
+/// Only fields the writing schema knows about are kept
+fn front_matter_metadata(
+    schemas: &SchemaDocument,
+    working_path: &Path,
+) -> std::io::Result<Option<Value>> {
+    let text = std::fs::read_to_string(working_path)?;
+    let front_matter = match front_matter::parse(&text) {
+        Ok(Some(front_matter)) => front_matter,
+        Ok(None) => return Ok(None),
+        Err(err) => {
+            tracing::warn!("{}: {}", working_path.display(), err);
+            return Ok(None);
+        }
+    };
+    let schema = match schemas.get(Writing::NAME) {
+        Some(schema) => schema,
+        None => return Ok(None),
+    };
+    let mut value = Map::new();
+    for field in schema.fields() {
+        if let Some(field_value) = front_matter.get(field.name()) {
+            value.insert(field.name().to_string(), field_value.clone());
+        }
+    }
+    match schema.validate(Writing::NAME, Value::Object(value)) {
+        Ok(value) => Ok(Some(value)),
+        Err(err) => {
+            tracing::warn!(
+                "{}: front matter is not valid writing metadata: {}",
+                working_path.display(),
+                err
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Stage changes in the working directory.
+/// If `strip_front_matter` is set, front matter parsed from staged markdown files
+/// is hidden when they're rendered.
+/// Paths are encrypted or decrypted according to `privacy`, and encrypted objects
+/// stay encrypted for the same recipients when they change
+pub async fn stage(
+    config: &Config,
+    strip_front_matter: bool,
+    privacy: &Privacy,
+) -> Result<DisplayableLog, StageError> {
     let device = config.device()?;
     let working_dir = config.working_dir().clone();
-    let updates = diff(config).await?;
+    let mut updates = diff(config).await?;
     let mut change_log = config.change_log()?;
     let base_manifest = config.base()?;
-    let (last_root_cid, last_manifest) = change_log.last_version().unwrap().clone();
+    let (last_root_cid, last_manifest) = change_log
+        .last_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?
+        .clone();
     let mut update_manifest = base_manifest.clone();
+    // Pick up any edits to the schema document
+    let schemas = config.schemas()?;
+    if let Some(schemas) = &schemas {
+        update_manifest.set_schemas(schemas.clone());
+    }
+
+    // Encrypted objects are always readable by this device
+    let mut recipients = vec![config.encryption_key()?.public_key()];
+    for recipient in &privacy.recipients {
+        if !recipients.contains(recipient) {
+            recipients.push(*recipient);
+        }
+    }
+    // Unchanged files that are being encrypted or decrypted need to be staged again
+    for path in privacy.encrypt.iter().chain(privacy.decrypt.iter()) {
+        match updates.get_mut(path) {
+            Some((_, change_type @ ChangeType::Base)) => {
+                *change_type = ChangeType::Modified;
+            }
+            Some(_) => {}
+            None => tracing::warn!("{} is not in the working dir", path.display()),
+        }
+    }
+
+    // Objects staged before we recorded content types get them now
+    for (path, (_cid, diff_type)) in updates.iter() {
+        if diff_type != &ChangeType::Base {
+            continue;
+        }
+        if let Some(object) = update_manifest.get_object_mut(path) {
+            if object.content_type().is_none() {
+                object.set_content_type(Some(content_type::sniff(&working_dir.join(path))?));
+            }
+        }
+    }
 
     let change_log_iter = updates.iter();
     // Iterate over the ChangeLog -- play updates against the base ... probably better to do this
@@ -38,12 +196,34 @@ pub async fn stage(config: &Config) -> Result<(), StageError> {
 
         let working_path = working_dir.join(path);
         if diff_type == &ChangeType::Added || diff_type == &ChangeType::Modified {
-            // Add the file to the local ipfs node
-            let added_cid = stage_file(&device, &working_path).await?;
-            // Make sure the cid matches the one in the change_log
-            if added_cid != *cid {
-                return Err(StageError::CidMismatch(added_cid, *cid));
-            }
+            let existing = update_manifest
+                .objects()
+                .get(path)
+                .and_then(|object| object.encryption());
+            let encrypt_for = if privacy.decrypt.contains(path) {
+                None
+            } else if privacy.encrypt.contains(path) {
+                Some(recipients.clone())
+            } else {
+                existing.map(|encryption| encryption.recipients())
+            };
+
+            // Add the file, or its ciphertext, to the local ipfs node
+            let (added_cid, encryption) = match &encrypt_for {
+                Some(recipients) => {
+                    let (sealed_cid, encryption) =
+                        stage_encrypted_file(&device, &working_path, cid, recipients).await?;
+                    (sealed_cid, Some(encryption))
+                }
+                None => {
+                    let added_cid = stage_file(&device, &working_path).await?;
+                    // Make sure the cid matches the one in the change_log
+                    if added_cid != *cid {
+                        return Err(StageError::CidMismatch(added_cid, *cid));
+                    }
+                    (added_cid, None)
+                }
+            };
             // Insert the file into the Manifest
             if diff_type == &ChangeType::Added {
                 let object = Object::new(added_cid);
@@ -52,6 +232,45 @@ pub async fn stage(config: &Config) -> Result<(), StageError> {
                 let object = update_manifest.get_object_mut(path).unwrap();
                 object.update(added_cid);
             }
+
+            let object = update_manifest.get_object_mut(path).unwrap();
+            object.set_encryption(encryption);
+            // Recorded for encrypted objects too, so readers know how to show them once decrypted
+            object.set_content_type(Some(content_type::sniff(&working_path)?));
+            // Anything we'd derive from an encrypted object would give its content away
+            if encrypt_for.is_some() {
+                object.set_media(None);
+                continue;
+            }
+
+            // Images and audio carry technical metadata we can show without loading them
+            let mut media = match media::extract(&working_path) {
+                Ok(media) => media,
+                Err(err) => {
+                    tracing::warn!("{}: {}", working_path.display(), err);
+                    None
+                }
+            };
+            // Visuals get thumbnails and smaller sizes, so pages don't have to load originals
+            if let Some(Media::Image(info)) = &mut media {
+                stage_variants(&device, update_manifest.schemas(), &working_path, info).await?;
+            }
+            update_manifest
+                .get_object_mut(path)
+                .unwrap()
+                .set_media(media);
+
+            // Markdown may describe itself with front matter, unless it's been tagged by hand
+            if path.extension().and_then(|ext| ext.to_str()) == Some("md") {
+                let metadata = front_matter_metadata(update_manifest.schemas(), &working_path)?;
+                let object = update_manifest.get_object_mut(path).unwrap();
+                if let (Some(metadata), false) = (metadata, object.is_tagged()) {
+                    let derived = Derived::FrontMatter {
+                        strip: strip_front_matter,
+                    };
+                    object.set_derived_metadata(metadata, derived);
+                }
+            }
         }
 
         // If the file is a file, we just remove it from the Manifest
@@ -61,9 +280,24 @@ pub async fn stage(config: &Config) -> Result<(), StageError> {
         }
     }
 
+    // Removed objects may have emptied out tagged directories
+    update_manifest.prune_directories();
+
+    // Let editors know if their schema edits invalidated any metadata
+    if schemas.is_some() {
+        for (path, object) in update_manifest.objects() {
+            if object.metadata().is_null() {
+                continue;
+            }
+            if let Err(err) = update_manifest.schemas().validate(object.metadata()) {
+                tracing::warn!("{} no longer matches its schema: {}", path.display(), err);
+            }
+        }
+    }
+
     if update_manifest == last_manifest {
         tracing::info!("no changes to stage");
-        return Ok(());
+        return Ok(change_log.displayable());
     }
 
     update_manifest.set_previous_root(last_root_cid);
@@ -73,9 +307,10 @@ pub async fn stage(config: &Config) -> Result<(), StageError> {
 
     change_log.update(&updates, &update_manifest, &update_root_cid);
 
+    let changes = change_log.displayable();
     config.set_change_log(change_log)?;
 
-    Ok(())
+    Ok(changes)
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -98,4 +333,24 @@ pub enum StageError {
     Diff(#[from] DiffError),
     #[error("device error: {0}")]
     Device(#[from] DeviceError),
+    #[error("encryption error: {0}")]
+    Encryption(#[from] EncryptionError),
+}
+
+impl StageError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Cid(..) => "stage.cid",
+            Self::CidMismatch(..) => "stage.cid_mismatch",
+            Self::Config(..) => "stage.config",
+            Self::FsTree(..) => "stage.fs_tree",
+            Self::Io(..) => "stage.io",
+            Self::Serde(..) => "stage.serde",
+            Self::PathPrefix(..) => "stage.path_prefix",
+            Self::Diff(..) => "stage.diff",
+            Self::Device(..) => "stage.device",
+            Self::Encryption(..) => "stage.encryption",
+        }
+    }
 }
@@ -1,20 +1,56 @@
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::{Component, Path, PathBuf};
 
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::cli::changes::ChangeLog;
 use crate::cli::device::DeviceError;
-use crate::types::{Audio, Schema, SchemaError, Visual, Writing};
+use crate::cli::output::write_lines;
+use crate::cli::wizard::{prompt_metadata, WizardError};
+use crate::types::schema::SchemaDefinition;
+use crate::types::{content_type, Manifest, Object, SchemaError};
 
 use crate::cli::config::{Config, ConfigError};
 
-// TODO: this whole tagging system is dissapointing. Look at all that bloat!
-//  I either need to rethink this, or learn more about macros
+/// Read metadata for a schema, either from a raw Json value, or interactively over stdin
+/// and stderr if no value was given. Interactive tagging starts from the existing metadata.
+/// # Returns None if the user backed out of interactive tagging
+fn input_tag(
+    name: &str,
+    schema: &SchemaDefinition,
+    value: Option<&str>,
+    existing: &Value,
+) -> Result<Option<Value>, TagError> {
+    let value: Value = match value {
+        Some(value) => serde_json::from_str(value)?,
+        None => {
+            let mut stdin = std::io::stdin().lock();
+            // Prompts go to stderr, so stdout only ever carries the report
+            let mut stderr = std::io::stderr();
+            let value = prompt_metadata(name, schema, existing, &mut stdin, &mut stderr)?;
+            return Ok(value);
+        }
+    };
+    for field in schema.fields() {
+        eprintln!("{} | {}", field.name(), field.description());
+    }
 
-fn input_tag<S>(path: &Path, value: &str) -> Result<Value, TagError>
-where
-    S: Schema,
-{
-    let path = path.to_path_buf();
+    // Write the object as a schematized value
+    match schema.validate(name, value) {
+        Ok(value) => Ok(Some(value)),
+        Err(SchemaError::Invalid(errors)) => {
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+            Err(TagError::InvalidMetadata(errors.len()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn check_extension(schema: &SchemaDefinition, path: &Path) -> Result<(), TagError> {
     let extension = match path.extension() {
         Some(ext) => ext,
         None => return Err(TagError::NoExtension),
@@ -23,49 +59,299 @@ where
         Some(ext) => ext,
         None => return Err(TagError::NoExtension),
     };
-    if !S::valid_extensions().contains(&extension_str) {
+    if !schema.accepts_extension(extension_str) {
         return Err(TagError::UnsupportedFileType);
     }
-    let value: Value = serde_json::from_str(value)?;
-    let fields = S::fields();
-    for (field, description) in fields {
-        println!("{} | {}", field, description);
-    }
+    Ok(())
+}
 
-    let schema = S::try_from(value.clone()).map_err(|_| TagError::Conversion)?;
-    // Write the object as a schematized value
-    Ok(schema.into_schema_value())
+/// Extensions can lie, so check the content type we sniffed when staging too.
+/// Objects staged before content types were recorded are only checked by extension
+fn check_content_type(schema: &SchemaDefinition, object: &Object) -> Result<(), TagError> {
+    let content_type = match object.content_type() {
+        Some(content_type) => content_type,
+        None => return Ok(()),
+    };
+    let expected = schema
+        .extensions()
+        .iter()
+        .flat_map(|extension| content_type::for_extension(extension))
+        .collect::<Vec<_>>();
+    // Schemas for extensions we don't know the content types of can't be checked
+    if expected.is_empty() || expected.contains(&content_type::normalize(content_type)) {
+        return Ok(());
+    }
+    Err(TagError::UnsupportedContentType(content_type.to_string()))
 }
 
-pub async fn tag(config: &Config, name: &str, path: &PathBuf, value: &str) -> Result<(), TagError> {
-    // load the manifest schema
-    let device = config.device()?;
+/// Tag an object or directory with a schema. If no value is given, prompt for it interactively
+pub async fn tag(
+    config: &Config,
+    name: &str,
+    path: &Path,
+    value: Option<&str>,
+) -> Result<TagReport, TagError> {
     let mut change_log = config.change_log()?;
-    let (_cid, base_manifest) = change_log.last_version().unwrap();
+    let (_cid, base_manifest) = change_log
+        .last_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?;
     let mut manifest = base_manifest.clone();
-    let object = match manifest.get_object_mut(path) {
-        Some(o) => o,
-        None => return Err(TagError::ObjectDoesNotExist(path.clone())),
+    // Tag against the schema document editors maintain, if there is one
+    if let Some(schemas) = config.schemas()? {
+        manifest.set_schemas(schemas);
+    }
+
+    let target = Target::resolve(&manifest, name, path)?;
+    let value = match input_tag(name, target.schema, value, &target.existing)? {
+        Some(value) => value,
+        None => {
+            return Ok(TagReport {
+                tagged: Vec::new(),
+                untagged: vec![target.path],
+            })
+        }
     };
+    let tags = vec![(target.path, target.is_directory, value)];
+    apply_tags(config, &mut change_log, manifest, tags).await
+}
 
-    let value = match name {
-        "writing" => input_tag::<Writing>(path, value),
-        "audio" => input_tag::<Audio>(path, value),
-        "visual" => input_tag::<Visual>(path, value),
-        val => return Err(TagError::SchemaDoesNotExist(val.to_string())),
-    }?;
+/// Tag many objects and directories at once from a sidecar file, producing a single
+/// new version of the ChangeLog. Every entry is validated before any are applied.
+/// The file may be either:
+/// - Json: an array of `{ "path": ..., "name": ..., "value": { ... } }` entries
+/// - Csv: with `path` and `name` columns, and a column for each field. Empty cells are skipped
+pub async fn tag_from_file(config: &Config, file: &Path) -> Result<TagReport, TagError> {
+    let mut change_log = config.change_log()?;
+    let (_cid, base_manifest) = change_log
+        .last_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?;
+    let mut manifest = base_manifest.clone();
+    if let Some(schemas) = config.schemas()? {
+        manifest.set_schemas(schemas);
+    }
+
+    let entries = read_entries(&manifest, file)?;
+    let tags = validate_entries(&manifest, entries)?;
+    apply_tags(config, &mut change_log, manifest, tags).await
+}
+
+/// Resolve and validate every entry of a batch, reporting all of the problems found
+/// rather than stopping at the first. A path may only be tagged once per batch
+fn validate_entries(
+    manifest: &Manifest,
+    entries: Vec<Entry>,
+) -> Result<Vec<(PathBuf, bool, Value)>, TagError> {
+    let mut tags = Vec::new();
+    let mut failures = Vec::new();
+    let mut rows = HashMap::new();
+    for (i, entry) in entries.into_iter().enumerate() {
+        let row = i + 1;
+        let target = match Target::resolve(manifest, &entry.name, &entry.path) {
+            Ok(target) => target,
+            Err(err) => {
+                failures.push(format!("entry {} ({}): {}", row, entry.path.display(), err));
+                continue;
+            }
+        };
+        if let Some(first) = rows.insert(target.path.clone(), row) {
+            failures.push(format!(
+                "entry {} ({}): already tagged by entry {}",
+                row,
+                entry.path.display(),
+                first
+            ));
+            continue;
+        }
+        match target.schema.validate(&entry.name, entry.value) {
+            Ok(value) => tags.push((target.path, target.is_directory, value)),
+            Err(SchemaError::Invalid(errors)) => {
+                for error in errors {
+                    failures.push(format!(
+                        "entry {} ({}): {}",
+                        row,
+                        entry.path.display(),
+                        error
+                    ));
+                }
+            }
+            Err(err) => {
+                failures.push(format!("entry {} ({}): {}", row, entry.path.display(), err));
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        for failure in failures.iter() {
+            eprintln!("{}", failure);
+        }
+        return Err(TagError::InvalidBatch(failures.len()));
+    }
+    Ok(tags)
+}
 
-    object.set_metdata(value);
+/// Write validated metadata into the manifest, and record it as a single new version
+async fn apply_tags(
+    config: &Config,
+    change_log: &mut ChangeLog,
+    mut manifest: Manifest,
+    tags: Vec<(PathBuf, bool, Value)>,
+) -> Result<TagReport, TagError> {
+    let device = config.device()?;
+    let mut report = TagReport {
+        tagged: Vec::new(),
+        untagged: Vec::new(),
+    };
+    for (path, is_directory, value) in tags {
+        if is_directory {
+            manifest.set_directory_metadata(&path, value);
+        } else if let Some(object) = manifest.get_object_mut(&path) {
+            object.set_metdata(value);
+        }
+        report.tagged.push(path);
+    }
 
+    let (_cid, base_manifest) = change_log
+        .last_version()
+        .ok_or(ConfigError::ChangeLogNotFound)?;
     if base_manifest != &manifest {
         let cid = device.hash_manifest(&manifest, false).await?;
         let wtf_log = change_log.clone();
         let log = wtf_log.log();
         change_log.update(log, &manifest, &cid);
 
-        config.set_change_log(change_log)?;
+        config.set_change_log(change_log.clone())?;
+    }
+    Ok(report)
+}
+
+/// What was tagged, and what was left alone
+#[derive(Serialize)]
+pub struct TagReport {
+    tagged: Vec<PathBuf>,
+    /// Paths interactive tagging was backed out of
+    untagged: Vec<PathBuf>,
+}
+
+impl Display for TagReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_lines(f, "Tagged ", self.tagged.iter().map(|path| path.display()))?;
+        for path in &self.untagged {
+            writeln!(f, "Leaving {} untagged", path.display())?;
+        }
+        Ok(())
     }
-    Ok(())
+}
+
+/// An object or directory that's about to be tagged
+struct Target<'a> {
+    path: PathBuf,
+    is_directory: bool,
+    schema: &'a SchemaDefinition,
+    existing: Value,
+}
+
+impl<'a> Target<'a> {
+    /// Check that a path exists and may be tagged with the named schema
+    fn resolve(manifest: &'a Manifest, name: &str, path: &Path) -> Result<Self, TagError> {
+        // Paths are relative to the working directory -- `.` is the root directory
+        let path = path
+            .components()
+            .filter(|component| component != &Component::CurDir)
+            .collect::<PathBuf>();
+        let is_directory = manifest.is_directory(&path);
+        if !is_directory && manifest.objects().get(&path).is_none() {
+            return Err(TagError::ObjectDoesNotExist(path));
+        }
+
+        let schema = manifest
+            .schemas()
+            .get(name)
+            .ok_or(TagError::SchemaDoesNotExist(name.to_string()))?;
+        let existing = if is_directory {
+            if !schema.tags_directories() {
+                return Err(TagError::UnsupportedDirectory);
+            }
+            manifest
+                .directory(&path)
+                .map(|directory| directory.metadata().clone())
+                .unwrap_or_default()
+        } else {
+            check_extension(schema, &path)?;
+            let object = &manifest.objects()[&path];
+            check_content_type(schema, object)?;
+            object.metadata().clone()
+        };
+        Ok(Self {
+            path,
+            is_directory,
+            schema,
+            existing,
+        })
+    }
+}
+
+/// A single entry in a bulk tagging file
+#[derive(Deserialize)]
+struct Entry {
+    path: PathBuf,
+    name: String,
+    value: Value,
+}
+
+/// Read bulk tagging entries from a Json or Csv file, depending on its extension
+fn read_entries(manifest: &Manifest, file: &Path) -> Result<Vec<Entry>, TagError> {
+    let extension = file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("json") => {
+            let data = std::fs::read(file)?;
+            Ok(serde_json::from_slice(&data)?)
+        }
+        Some("csv") => {
+            let mut reader = csv::Reader::from_path(file)?;
+            let headers = reader.headers()?.clone();
+            // Cells are typed by their entry's schema, so it has to be known before
+            //  any of them are read, wherever the `name` column is
+            let name_column = headers.iter().position(|header| header == "name");
+            let mut entries = Vec::new();
+            for record in reader.records() {
+                let record = record?;
+                let name = name_column
+                    .and_then(|column| record.get(column))
+                    .unwrap_or_default();
+                let mut entry = Entry {
+                    path: PathBuf::new(),
+                    name: name.to_string(),
+                    value: Value::Object(Default::default()),
+                };
+                for (header, cell) in headers.iter().zip(record.iter()) {
+                    match header {
+                        "path" => entry.path = PathBuf::from(cell),
+                        "name" => {}
+                        _ if cell.is_empty() => {}
+                        field => {
+                            entry.value[field] = csv_cell(manifest, &entry.name, field, cell);
+                        }
+                    }
+                }
+                entries.push(entry);
+            }
+            Ok(entries)
+        }
+        _ => Err(TagError::UnsupportedBatchFile(file.to_path_buf())),
+    }
+}
+
+/// Csv cells are all strings, so read them as whatever type their field expects
+fn csv_cell(manifest: &Manifest, name: &str, field: &str, cell: &str) -> Value {
+    manifest
+        .schemas()
+        .get(name)
+        .and_then(|schema| schema.fields().iter().find(|f| f.name() == field))
+        .and_then(|field| field.r#type().parse(cell))
+        .unwrap_or(Value::String(cell.to_string()))
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -76,16 +362,171 @@ pub enum TagError {
     SchemaDoesNotExist(String),
     #[error("serde error: {0}")]
     Serde(#[from] serde_json::Error),
-    #[error("error encoding schema")]
+    #[error("invalid metadata: {0}")]
     Schema(#[from] SchemaError),
     #[error("device error: {0}")]
     Device(#[from] DeviceError),
     #[error("unsupported file type")]
     UnsupportedFileType,
+    #[error("unsupported content type: {0}")]
+    UnsupportedContentType(String),
+    #[error("wizard error: {0}")]
+    Wizard(#[from] WizardError),
+    #[error("metadata has {0} invalid field(s)")]
+    InvalidMetadata(usize),
+    #[error("{0} problem(s) found in bulk tagging file -- nothing was tagged")]
+    InvalidBatch(usize),
+    #[error("bulk tagging file must be .json or .csv: {0}")]
+    UnsupportedBatchFile(PathBuf),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("csv error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("schema cannot be used to tag a directory")]
+    UnsupportedDirectory,
     #[error("object does not exist: {0}")]
     ObjectDoesNotExist(PathBuf),
-    #[error("conversion from value")]
-    Conversion,
     #[error("file does not have an extension")]
     NoExtension,
 }
+
+impl TagError {
+    /// A stable name for what went wrong, for scripts to match on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(..) => "tag.config",
+            Self::SchemaDoesNotExist(..) => "tag.schema_does_not_exist",
+            Self::Serde(..) => "tag.serde",
+            Self::Schema(..) => "tag.schema",
+            Self::Device(..) => "tag.device",
+            Self::UnsupportedFileType => "tag.unsupported_file_type",
+            Self::UnsupportedContentType(..) => "tag.unsupported_content_type",
+            Self::Wizard(..) => "tag.wizard",
+            Self::InvalidMetadata(..) => "tag.invalid_metadata",
+            Self::InvalidBatch(..) => "tag.invalid_batch",
+            Self::UnsupportedBatchFile(..) => "tag.unsupported_batch_file",
+            Self::Io(..) => "tag.io",
+            Self::Csv(..) => "tag.csv",
+            Self::UnsupportedDirectory => "tag.unsupported_directory",
+            Self::ObjectDoesNotExist(..) => "tag.object_does_not_exist",
+            Self::NoExtension => "tag.no_extension",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+    use serde_json::json;
+
+    use super::*;
+    use crate::types::schema::SchemaDocument;
+
+    fn manifest() -> Manifest {
+        let mut manifest = Manifest::default();
+        for path in ["writing/hello.md", "writing/world.md", "audio/track.mp3"] {
+            manifest.insert_object(Path::new(path), &Object::new(Cid::default()));
+        }
+        let schemas: SchemaDocument = serde_json::from_value(json!({
+            "writing": {
+                "extensions": ["md"],
+                "fields": [{ "name": "title", "type": "string" }]
+            },
+            "audio": {
+                "extensions": ["mp3"],
+                "fields": [
+                    { "name": "title", "type": "string" },
+                    { "name": "track", "type": "integer" }
+                ]
+            }
+        }))
+        .unwrap();
+        manifest.set_schemas(schemas);
+        manifest
+    }
+
+    fn read(manifest: &Manifest, extension: &str, data: &str) -> Vec<Entry> {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join(format!("tags.{}", extension));
+        std::fs::write(&file, data).unwrap();
+        read_entries(manifest, &file).unwrap()
+    }
+
+    #[test]
+    fn json_batches_are_validated_together() {
+        let manifest = manifest();
+        let entries = read(
+            &manifest,
+            "json",
+            r#"[
+                { "path": "writing/hello.md", "name": "writing", "value": { "title": "Hello" } },
+                { "path": "./audio/track.mp3", "name": "audio", "value": { "title": "A", "track": 1 } }
+            ]"#,
+        );
+        let tags = validate_entries(&manifest, entries).unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[0].0, PathBuf::from("writing/hello.md"));
+        assert_eq!(tags[1].0, PathBuf::from("audio/track.mp3"));
+        assert_eq!(tags[1].2["track"], json!(1));
+
+        // One bad entry fails the whole batch, and every problem is counted
+        let entries = read(
+            &manifest,
+            "json",
+            r#"[
+                { "path": "writing/hello.md", "name": "writing", "value": { "title": "Hello" } },
+                { "path": "writing/missing.md", "name": "writing", "value": { "title": "Gone" } },
+                { "path": "audio/track.mp3", "name": "audio", "value": { "title": 1, "track": "one" } }
+            ]"#,
+        );
+        assert!(matches!(
+            validate_entries(&manifest, entries),
+            Err(TagError::InvalidBatch(3))
+        ));
+    }
+
+    #[test]
+    fn paths_may_only_be_tagged_once_per_batch() {
+        let manifest = manifest();
+        let entries = read(
+            &manifest,
+            "json",
+            r#"[
+                { "path": "writing/hello.md", "name": "writing", "value": { "title": "Hello" } },
+                { "path": "./writing/hello.md", "name": "writing", "value": { "title": "Again" } }
+            ]"#,
+        );
+        assert!(matches!(
+            validate_entries(&manifest, entries),
+            Err(TagError::InvalidBatch(1))
+        ));
+    }
+
+    #[test]
+    fn csv_cells_are_typed_by_their_entry_schema() {
+        let manifest = manifest();
+        // The name column comes after the fields it types
+        let entries = read(
+            &manifest,
+            "csv",
+            "path,title,track,name\n\
+             audio/track.mp3,A,7,audio\n\
+             writing/world.md,World,,writing\n",
+        );
+        let tags = validate_entries(&manifest, entries).unwrap();
+        assert_eq!(tags[0].2["track"], json!(7));
+        assert_eq!(tags[1].2["title"], json!("World"));
+        assert!(tags[1].2.get("track").is_none());
+
+        // Cells that aren't the type their field expects fail validation
+        let entries = read(
+            &manifest,
+            "csv",
+            "path,track,title,name\naudio/track.mp3,seven,A,audio\n",
+        );
+        assert!(matches!(
+            validate_entries(&manifest, entries),
+            Err(TagError::InvalidBatch(1))
+        ));
+    }
+}
@@ -0,0 +1,42 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// How commands report what they did
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Text for people to read
+    #[default]
+    Text,
+    /// A single json document for scripts to parse. See the README for each command's
+    /// structure -- fields are only ever added to them
+    Json,
+}
+
+impl OutputFormat {
+    /// Print the report a command made of what it did
+    pub fn print<R: Serialize + Display>(&self, report: &R) -> Result<(), serde_json::Error> {
+        match self {
+            Self::Text => {
+                let text = report.to_string();
+                if !text.is_empty() {
+                    println!("{}", text.trim_end());
+                }
+            }
+            Self::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        }
+        Ok(())
+    }
+}
+
+/// Write a report's lines, one per item
+pub fn write_lines<T: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    prefix: &str,
+    items: impl IntoIterator<Item = T>,
+) -> std::fmt::Result {
+    for item in items {
+        writeln!(f, "{}{}", prefix, item)?;
+    }
+    Ok(())
+}
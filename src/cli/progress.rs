@@ -0,0 +1,116 @@
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
+
+use crate::ipfs::ProgressReporter;
+
+/// Renders transfers to the terminal as a bar for the file moving now,
+/// under a bar for every file, each with their throughput
+pub struct ProgressBars {
+    multi: MultiProgress,
+    total: ProgressBar,
+    file: Mutex<Option<ProgressBar>>,
+    bytes: AtomicU64,
+}
+
+impl ProgressBars {
+    /// # Args
+    /// - files: how many files will be transferred, if known up front
+    pub fn new(files: Option<u64>) -> Self {
+        let multi = MultiProgress::new();
+        let total = match files {
+            Some(files) => ProgressBar::new(files).with_style(
+                ProgressStyle::with_template(
+                    "[{elapsed_precise}] {bar:40} {pos}/{len} files {msg}",
+                )
+                .unwrap(),
+            ),
+            None => ProgressBar::no_length().with_style(
+                ProgressStyle::with_template("[{elapsed_precise}] {pos} files {msg}").unwrap(),
+            ),
+        };
+        let total = multi.add(total);
+        Self {
+            multi,
+            total,
+            file: Mutex::new(None),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Print a line above the bars, without breaking them up
+    pub fn println(&self, line: impl AsRef<str>) {
+        let _ = self.multi.println(line);
+    }
+
+    /// Clear the bars, once every transfer is done
+    pub fn clear(&self) {
+        if let Some(file) = self.file.lock().unwrap().take() {
+            file.finish_and_clear();
+        }
+        self.total.finish_and_clear();
+    }
+}
+
+impl ProgressReporter for ProgressBars {
+    fn start(&self, name: &str, len: Option<u64>) {
+        let bar = match len {
+            Some(len) => ProgressBar::new(len).with_style(
+                ProgressStyle::with_template(
+                    "{msg} {bar:40} {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+                )
+                .unwrap(),
+            ),
+            None => ProgressBar::no_length().with_style(
+                ProgressStyle::with_template("{msg} {bytes} ({bytes_per_sec})").unwrap(),
+            ),
+        };
+        let bar = self.multi.add(bar.with_message(name.to_string()));
+        // A transfer that's restarted replaces its last attempt
+        if let Some(last) = self.file.lock().unwrap().replace(bar) {
+            last.finish_and_clear();
+        }
+    }
+
+    fn advance(&self, bytes: u64) {
+        if let Some(file) = self.file.lock().unwrap().as_ref() {
+            file.inc(bytes);
+        }
+        let total = self.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        let per_sec = total as f64 / self.total.elapsed().as_secs_f64().max(1.0);
+        self.total.set_message(format!(
+            "{} ({}/s)",
+            HumanBytes(total),
+            HumanBytes(per_sec as u64)
+        ));
+    }
+
+    fn finish(&self) {
+        if let Some(file) = self.file.lock().unwrap().take() {
+            file.finish_and_clear();
+        }
+        self.total.inc(1);
+    }
+}
+
+/// Tells a reporter about every byte read through it, for showing the progress of uploads
+pub struct ProgressReader<R> {
+    inner: R,
+    progress: Arc<dyn ProgressReporter>,
+}
+
+impl<R> ProgressReader<R> {
+    pub fn new(inner: R, progress: Arc<dyn ProgressReporter>) -> Self {
+        Self { inner, progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.advance(read as u64);
+        Ok(read)
+    }
+}
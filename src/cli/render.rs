@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Component, Path, PathBuf};
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+
+use crate::cli::device::{Device, DeviceError};
+use crate::types::content_type::Viewer;
+use crate::types::markdown::{link_relative_sources, markdown_to_html};
+use crate::types::schema::{Audio, Collection, Schema, Visual, Writing};
+use crate::types::{front_matter, Derived, Entry, Manifest, Object, Publication};
+
+/// Characters that have to be escaped within a path segment of a link
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Where object pages are written within the site
+const OBJECTS_DIR: &str = "objects";
+/// Where the raw content of objects is served from within the site
+const CONTENT_DIR: &str = "content";
+/// Where directory listings are written within the site
+const BROWSE_DIR: &str = "browse";
+/// Where the site's static markdown and icons are served from within the site
+pub const STATIC_DIR: &str = "static";
+
+/// The schemas that get a listing page of their own, and what to call them
+const SECTIONS: [(&str, &str); 3] = [
+    (Writing::NAME, "Writing"),
+    (Audio::NAME, "Audio"),
+    (Visual::NAME, "Visual"),
+];
+
+/// Where the content of objects should be read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentSource {
+    /// What's been pushed. The working copy is used if it hasn't changed since the
+    /// object was staged, otherwise the content is fetched from the gateway
+    Published,
+    /// What's been staged on the local ipfs node, falling back to the working copy
+    Staged,
+}
+
+/// Renders a manifest as a tree of plain html, which works without javascript, an RPC,
+/// or a gateway. Every link within the tree is relative, so it can be served from anywhere
+pub struct SiteRenderer<'a> {
+    device: &'a Device,
+    working_dir: &'a Path,
+    manifest: &'a Manifest,
+    static_dir: &'a Path,
+    title: &'a str,
+    source: ContentSource,
+}
+
+impl<'a> SiteRenderer<'a> {
+    pub fn new(
+        device: &'a Device,
+        working_dir: &'a Path,
+        manifest: &'a Manifest,
+        static_dir: &'a Path,
+        title: &'a str,
+        source: ContentSource,
+    ) -> Self {
+        Self {
+            device,
+            working_dir,
+            manifest,
+            static_dir,
+            title,
+            source,
+        }
+    }
+
+    /// Render every page of the site, keyed by their path within the tree
+    pub async fn pages(&self) -> Result<BTreeMap<PathBuf, String>, RenderError> {
+        let mut pages = BTreeMap::new();
+
+        // Pages written from the site's static markdown
+        let index = match fs::read_to_string(self.static_dir.join("index.md")) {
+            Ok(text) => markdown_to_html(&text),
+            Err(_) => String::new(),
+        };
+        let page = PathBuf::from("index.html");
+        pages.insert(page.clone(), self.layout(&page, self.title, &index));
+        if self.has_about() {
+            let about = fs::read_to_string(self.static_dir.join("about.md"))?;
+            let page = PathBuf::from("about.html");
+            let html = self.layout(&page, "About", &markdown_to_html(&about));
+            pages.insert(page, html);
+        }
+
+        // Listings of each kind of object, newest first
+        for (schema, heading) in SECTIONS {
+            let page = PathBuf::from(format!("{}.html", schema));
+            let mut objects = self
+                .manifest
+                .objects()
+                .iter()
+                .filter(|(_, object)| object.metadata()["type"] == schema)
+                .collect::<Vec<_>>();
+            objects.sort_by(|(_, a), (_, b)| b.created_at().cmp(a.created_at()));
+            let rows = objects
+                .into_iter()
+                .map(|(path, object)| {
+                    format!(
+                        "<tr><td><a href=\"{}\">{}</a></td><td>{}</td></tr>",
+                        href(&page, &object_page(path)),
+                        escape(&object_title(path, object)),
+                        object.created_at().date_naive()
+                    )
+                })
+                .collect::<String>();
+            let body = format!(
+                "<h1>{}</h1>\n<table>\n<tr><th>Title</th><th>Date</th></tr>\n{}</table>",
+                heading, rows
+            );
+            let html = self.layout(&page, heading, &body);
+            pages.insert(page, html);
+        }
+
+        // Listings of every directory, starting from the root
+        let mut dirs = vec![PathBuf::new()];
+        while let Some(dir) = dirs.pop() {
+            let page = browse_page(&dir);
+            let collection = self
+                .manifest
+                .directory(&dir)
+                .and_then(|directory| Collection::try_from(directory.metadata().clone()).ok());
+            let (heading, description) = match collection {
+                Some(collection) => (collection.title, collection.description),
+                None if dir.as_os_str().is_empty() => ("Everything".to_string(), String::new()),
+                None => (dir.display().to_string(), String::new()),
+            };
+            let mut items = String::new();
+            for entry in self.manifest.ls(&dir) {
+                let (link, name) = match &entry {
+                    Entry::Object { path, object } => {
+                        (object_page(path), object_title(path, object))
+                    }
+                    Entry::Directory {
+                        path, directory, ..
+                    } => {
+                        dirs.push(path.clone());
+                        let name = directory
+                            .and_then(|directory| {
+                                Collection::try_from(directory.metadata().clone()).ok()
+                            })
+                            .map(|collection| collection.title)
+                            .unwrap_or(format!("{}/", file_name(path)));
+                        (browse_page(path), name)
+                    }
+                };
+                items.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a></li>\n",
+                    href(&page, &link),
+                    escape(&name)
+                ));
+            }
+            let body = format!(
+                "<h1>{}</h1>\n<p>{}</p>\n<ul>\n{}</ul>",
+                escape(&heading),
+                escape(&description),
+                items
+            );
+            let html = self.layout(&page, &heading, &body);
+            pages.insert(page, html);
+        }
+
+        // A page for every object
+        for (path, object) in self.manifest.objects() {
+            let page = object_page(path);
+            let body = self.object_body(&page, path, object).await?;
+            let html = self.layout(&page, &object_title(path, object), &body);
+            pages.insert(page, html);
+        }
+        Ok(pages)
+    }
+
+    /// The object whose content is served at a path within the tree, if any
+    pub fn content(&self, path: &Path) -> Option<(&'a PathBuf, &'a Object)> {
+        let path = path.strip_prefix(CONTENT_DIR).ok()?;
+        self.manifest.objects().get_key_value(path)
+    }
+
+    /// Read the content of an object, from wherever the renderer was told to
+    pub async fn object_data(&self, path: &Path, object: &Object) -> Result<Vec<u8>, RenderError> {
+        let working_path = self.working_dir.join(path);
+        match self.source {
+            ContentSource::Published => {
+                if let Ok(data) = fs::read(&working_path) {
+                    let cid = self
+                        .device
+                        .hash_ipfs_data(Cursor::new(data.clone()), false)
+                        .await?;
+                    if cid == *object.cid() {
+                        return Ok(data);
+                    }
+                }
+                tracing::info!(
+                    "{} has changed since it was staged, reading it from the gateway",
+                    path.display()
+                );
+                Ok(self.device.read_ipfs_gateway_data(object.cid()).await?)
+            }
+            ContentSource::Staged => match self.device.read_ipfs_data(object.cid(), false).await {
+                Ok(data) => Ok(data),
+                Err(_) => Ok(fs::read(working_path)?),
+            },
+        }
+    }
+
+    fn has_about(&self) -> bool {
+        self.static_dir.join("about.md").exists()
+    }
+
+    /// Wrap the body of a page in the site's layout
+    fn layout(&self, page: &Path, heading: &str, body: &str) -> String {
+        let mut nav = vec![("index.html", "Home")];
+        if self.has_about() {
+            nav.push(("about.html", "About"));
+        }
+        let sections = SECTIONS.map(|(schema, heading)| (format!("{}.html", schema), heading));
+        let browse = browse_page(Path::new(""));
+        let links = nav
+            .iter()
+            .map(|(target, name)| (target.to_string(), *name))
+            .chain(sections)
+            .chain([(browse.display().to_string(), "Browse")])
+            .map(|(target, name)| {
+                format!(
+                    "<li><a href=\"{}\">{}</a></li>",
+                    href(page, Path::new(&target)),
+                    name
+                )
+            })
+            .collect::<String>();
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{heading} | {title}</title>
+<style>
+body {{ max-width: 48rem; margin: 0 auto; padding: 1rem; font-family: sans-serif; line-height: 1.5; }}
+nav ul {{ display: flex; flex-wrap: wrap; gap: 1rem; list-style: none; padding: 0; }}
+img, video, iframe {{ max-width: 100%; }}
+iframe {{ width: 100%; height: 80vh; border: none; }}
+pre {{ white-space: pre-wrap; }}
+td, th {{ padding: 0.25rem 0.5rem; text-align: left; }}
+.notice {{ padding: 0.5rem; background: #fef3c7; }}
+</style>
+</head>
+<body>
+<header><a href="{home}">{title}</a></header>
+<nav><ul>{links}</ul></nav>
+<main>
+{body}
+</main>
+</body>
+</html>
+"#,
+            heading = escape(heading),
+            title = escape(self.title),
+            home = href(page, Path::new("index.html")),
+            links = links,
+            body = body,
+        )
+    }
+
+    /// Show an object's metadata and content, however its content type calls for
+    async fn object_body(
+        &self,
+        page: &Path,
+        path: &Path,
+        object: &Object,
+    ) -> Result<String, RenderError> {
+        let src = href(page, &content_path(path));
+        let content = match Viewer::for_object(object, path) {
+            Viewer::Markdown => {
+                let data = self.object_data(path, object).await?;
+                let text = String::from_utf8_lossy(&data);
+                let text: &str = &text;
+                // Hide front matter we've already pulled into the object's metadata, if asked to
+                let text = match object.derived() {
+                    Some(Derived::FrontMatter { strip: true }) => front_matter::strip(text),
+                    _ => text,
+                };
+                // Link media the object refers to relatively to their copies in the site
+                let text = link_relative_sources(text, path, self.manifest.objects(), |path, _| {
+                    href(page, &content_path(path))
+                });
+                markdown_to_html(&text)
+            }
+            Viewer::Text => {
+                let data = self.object_data(path, object).await?;
+                format!("<pre>{}</pre>", escape(&String::from_utf8_lossy(&data)))
+            }
+            Viewer::Image => format!(
+                "<img src=\"{}\" alt=\"{}\">",
+                src,
+                escape(&object_title(path, object))
+            ),
+            Viewer::Audio => format!("<audio controls src=\"{}\"></audio>", src),
+            Viewer::Video => format!("<video controls src=\"{}\"></video>", src),
+            Viewer::Pdf => format!("<iframe src=\"{}\"></iframe>", src),
+            Viewer::Download => String::new(),
+        };
+        // Previews show what isn't out yet, so say so
+        let notice = match (self.source, object.publication()) {
+            (ContentSource::Staged, Publication::Draft) => {
+                "<p class=\"notice\">Draft -- this won't be pushed until it's published</p>\n"
+                    .to_string()
+            }
+            (ContentSource::Staged, Publication::Scheduled { at }) => format!(
+                "<p class=\"notice\">Scheduled -- readers will see this from {}</p>\n",
+                at.to_rfc3339()
+            ),
+            _ => String::new(),
+        };
+        let description = object.metadata()["description"]
+            .as_str()
+            .map(|description| format!("<p><em>{}</em></p>\n", escape(description)))
+            .unwrap_or_default();
+        Ok(format!(
+            "{}<h1>{}</h1>\n{}<p>{} &middot; <a href=\"{}\">{}</a></p>\n{}",
+            notice,
+            escape(&object_title(path, object)),
+            description,
+            object.created_at().date_naive(),
+            src,
+            escape(file_name(path)),
+            content
+        ))
+    }
+}
+
+/// The page for an object within the site
+fn object_page(path: &Path) -> PathBuf {
+    let mut page = Path::new(OBJECTS_DIR).join(path).into_os_string();
+    page.push(".html");
+    page.into()
+}
+
+/// Where an object's content is served from within the site
+pub fn content_path(path: &Path) -> PathBuf {
+    Path::new(CONTENT_DIR).join(path)
+}
+
+/// The listing for a directory within the site. The empty path is the root directory
+fn browse_page(dir: &Path) -> PathBuf {
+    Path::new(BROWSE_DIR).join(dir).join("index.html")
+}
+
+/// A link from one page of the site to another path within it
+fn href(from: &Path, to: &Path) -> String {
+    let depth = from
+        .parent()
+        .map_or(0, |parent| parent.components().count());
+    let target = to
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(segment) => Some(segment.to_string_lossy()),
+            _ => None,
+        })
+        .map(|segment| utf8_percent_encode(&segment, SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("{}{}", "../".repeat(depth), target)
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+/// Read the title of an object, whichever schema it was tagged with, falling back to its name
+fn object_title(path: &Path, object: &Object) -> String {
+    object.metadata()["title"]
+        .as_str()
+        .unwrap_or(file_name(path))
+        .to_string()
+}
+
+/// Escape text for use within html
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("device error: {0}")]
+    Device(#[from] DeviceError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_are_relative_to_the_page() {
+        let page = object_page(Path::new("writing/hello world.md"));
+        assert_eq!(page, PathBuf::from("objects/writing/hello world.md.html"));
+        assert_eq!(
+            href(&page, &content_path(Path::new("writing/hello world.md"))),
+            "../../content/writing/hello%20world.md"
+        );
+        assert_eq!(href(&page, Path::new("index.html")), "../../index.html");
+        assert_eq!(
+            href(Path::new("index.html"), &browse_page(Path::new(""))),
+            "browse/index.html"
+        );
+        // Links only ever point within the site
+        assert_eq!(
+            href(Path::new("index.html"), Path::new("../#secret?")),
+            "%23secret%3F"
+        );
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::io::{BufRead, Write};
+
+use serde_json::{Map, Value};
+
+use crate::types::schema::SchemaDefinition;
+use crate::types::SchemaError;
+
+/// Interactively build metadata for a schema, one field at a time.
+/// Values are pre-filled from existing metadata, if it was tagged with the same schema.
+/// Reads answers line by line from `input`, so it can be scripted over stdin.
+/// # Returns the validated metadata, or None if the user didn't confirm it
+pub fn prompt_metadata<R: BufRead, W: Write>(
+    name: &str,
+    schema: &SchemaDefinition,
+    existing: &Value,
+    input: &mut R,
+    output: &mut W,
+) -> Result<Option<Value>, WizardError> {
+    let existing = match existing["type"].as_str() {
+        Some(r#type) if r#type == name => existing.clone(),
+        _ => Value::Null,
+    };
+
+    writeln!(output, "Tagging with {}: {}", name, schema.description())?;
+    let mut map = Map::new();
+    for field in schema.fields() {
+        let current = existing
+            .get(field.name())
+            .or(field.default())
+            .filter(|value| !value.is_null());
+        if let Some(values) = field.values() {
+            for (i, value) in values.iter().enumerate() {
+                writeln!(output, "  {}) {}", i + 1, display(value))?;
+            }
+        }
+        loop {
+            write!(output, "{} ({})", field.name(), field.description())?;
+            if let Some(current) = current {
+                write!(output, " [{}]", display(current))?;
+            } else if !field.required() {
+                write!(output, " [optional]")?;
+            }
+            write!(output, ": ")?;
+            output.flush()?;
+
+            let answer = read_line(input)?;
+            let value = if answer.is_empty() {
+                match current {
+                    Some(current) => current.clone(),
+                    None if field.required() => {
+                        writeln!(output, "{} is required", field.name())?;
+                        continue;
+                    }
+                    None => break,
+                }
+            } else {
+                // Enum choices may be picked by number
+                let choice = answer
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|i| field.values()?.get(i.checked_sub(1)?));
+                match choice {
+                    Some(choice) => choice.clone(),
+                    None => match field.r#type().parse(&answer) {
+                        Some(value) => value,
+                        None => {
+                            writeln!(output, "expected {}", field.r#type())?;
+                            continue;
+                        }
+                    },
+                }
+            };
+            match field.validate(value) {
+                Ok(value) => {
+                    map.insert(field.name().to_string(), value);
+                    break;
+                }
+                Err(error) => writeln!(output, "{}", error)?,
+            }
+        }
+    }
+
+    let value = schema.validate(name, Value::Object(map))?;
+    writeln!(output, "{}", serde_json::to_string_pretty(&value)?)?;
+    write!(output, "Save this metadata? [y/N]: ")?;
+    output.flush()?;
+    let answer = read_line(input)?.to_lowercase();
+    if answer == "y" || answer == "yes" {
+        Ok(Some(value))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Read a trimmed line, treating the end of input as an error so we never spin
+fn read_line<R: BufRead>(input: &mut R) -> Result<String, WizardError> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(WizardError::EndOfInput);
+    }
+    Ok(line.trim().to_string())
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WizardError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("serde error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("schema error: {0}")]
+    Schema(#[from] SchemaError),
+    #[error("input ended before tagging was finished")]
+    EndOfInput,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::types::schema::SchemaDocument;
+
+    fn run(name: &str, existing: Value, script: &str) -> Result<Option<Value>, WizardError> {
+        let schemas = SchemaDocument::default();
+        let schema = schemas.get(name).unwrap();
+        let mut output = Vec::new();
+        prompt_metadata(
+            name,
+            schema,
+            &existing,
+            &mut Cursor::new(script),
+            &mut output,
+        )
+    }
+
+    #[test]
+    fn fields_are_prompted_in_order() {
+        // Bad answers are asked again, and choices can be picked by number
+        let value = run(
+            "writing",
+            Value::Null,
+            "Hello\n\nA lil hello!\nlimerick\n3\ny\n",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "type": "writing",
+                "title": "Hello",
+                "description": "A lil hello!",
+                "genre": "Blog"
+            })
+        );
+    }
+
+    #[test]
+    fn existing_metadata_is_prefilled() {
+        let existing = json!({
+            "type": "collection",
+            "title": "Poems",
+            "description": "Some poems",
+            "order": "newest"
+        });
+        let value = run("collection", existing.clone(), "\nMore poems\n\nyes\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(value["title"], "Poems");
+        assert_eq!(value["description"], "More poems");
+        assert_eq!(value["order"], "newest");
+        assert_eq!(run("collection", existing, "\n\n\nn\n").unwrap(), None);
+    }
+
+    #[test]
+    fn running_out_of_input_is_an_error() {
+        assert!(matches!(
+            run("visual", Value::Null, "A drawing\n"),
+            Err(WizardError::EndOfInput)
+        ));
+    }
+}
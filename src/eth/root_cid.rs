@@ -71,8 +71,7 @@ impl RootCid {
 
     /// Read the current cid from the contract
     pub async fn read(&self) -> Result<Cid, RootCidError> {
-        // TODO: This is janky, but we should have the contract available by now
-        let contract = self.0.contract().unwrap();
+        let contract = self.0.contract().ok_or(RootCidError::MissingContract)?;
 
         let cid: Cid = contract
             .method::<_, CidToken>("read", ())
@@ -93,8 +92,7 @@ impl RootCid {
         previous_cid: Cid,
         cid: Cid,
     ) -> Result<Option<TransactionReceipt>, RootCidError> {
-        // TODO: This is janky, but we should have the contract available by now
-        let contract = self.0.contract().unwrap();
+        let contract = self.0.contract().ok_or(RootCidError::MissingContract)?;
         let chain_id = self.0.chain_id();
         let signer = match self.0.signer() {
             Some(signer) => signer,
@@ -114,11 +112,11 @@ impl RootCid {
             .send_transaction(tx, None)
             .await
             .map_err(|e| RootCidError::Default(e.to_string()))?;
-        println!("Signed tx: {:?}", signed_tx);
+        tracing::info!("signed tx: {:?}", signed_tx);
         let reciept = signed_tx
             .await
             .map_err(|e| RootCidError::Default(e.to_string()))?;
-        println!("Reciept: {:?}", reciept);
+        tracing::info!("reciept: {:?}", reciept);
         Ok(reciept)
     }
 }
@@ -131,6 +129,8 @@ pub enum RootCidError {
     #[cfg(not(target_arch = "wasm32"))]
     #[error("No signer")]
     MissingSigner,
+    #[error("no contract to call")]
+    MissingContract,
     #[error("abi error: {0}")]
     Abi(#[from] ethers::abi::Error),
     #[error("serde json error: {0}")]
@@ -0,0 +1,147 @@
+use std::io::Cursor;
+
+use cid::Cid;
+
+use super::verify::{read_varint, verify_block, VerifyError};
+
+/// The longest a section length can be encoded as
+const MAX_VARINT_LEN: usize = 10;
+/// The longest section we'll wait on: a block of up to 2 MiB, the most any Ipfs
+/// implementation will send, plus the Cid it's filed under
+const MAX_SECTION_LEN: u64 = (2 << 20) + 128;
+
+/// Reads the blocks out of a CARv1 archive as it arrives, verifying each against the Cid
+/// it's filed under. The header is skipped -- we already know which root we asked for
+#[derive(Default)]
+pub struct CarReader {
+    buf: Vec<u8>,
+    read_header: bool,
+}
+
+impl CarReader {
+    /// Add the next bytes of the archive
+    pub fn extend(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Take the next verified block, if all of it has arrived
+    pub fn next_block(&mut self) -> Result<Option<(Cid, Vec<u8>)>, CarError> {
+        // The first section is the dag-cbor header
+        if !self.read_header {
+            if self.next_section()?.is_none() {
+                return Ok(None);
+            }
+            self.read_header = true;
+        }
+        let Some(section) = self.next_section()? else {
+            return Ok(None);
+        };
+
+        let mut cursor = Cursor::new(section);
+        let cid = Cid::read_bytes(&mut cursor).map_err(|_| CarError::Malformed("invalid cid"))?;
+        let block = cursor.get_ref()[cursor.position() as usize..].to_vec();
+        verify_block(&cid, &block)?;
+        Ok(Some((cid, block)))
+    }
+
+    /// Check the archive ended where a section did
+    pub fn finish(self) -> Result<(), CarError> {
+        match (self.read_header, self.buf.is_empty()) {
+            (false, _) => Err(CarError::MissingHeader),
+            (true, false) => Err(CarError::Malformed("truncated section")),
+            (true, true) => Ok(()),
+        }
+    }
+
+    /// Take the next varint length prefixed section, if all of it has arrived
+    fn next_section(&mut self) -> Result<Option<Vec<u8>>, CarError> {
+        let mut data = self.buf.as_slice();
+        let Some(len) = read_varint(&mut data) else {
+            if self.buf.len() >= MAX_VARINT_LEN {
+                return Err(CarError::Malformed("invalid section length"));
+            }
+            return Ok(None);
+        };
+        // Lengths come from whoever served the archive, so don't buffer without end
+        if len > MAX_SECTION_LEN {
+            return Err(CarError::Malformed("section too long"));
+        }
+        let len = len as usize;
+        if data.len() < len {
+            return Ok(None);
+        }
+        let start = self.buf.len() - data.len();
+        let section = self.buf[start..start + len].to_vec();
+        self.buf.drain(..start + len);
+        Ok(Some(section))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CarError {
+    #[error("car has no header")]
+    MissingHeader,
+    #[error("malformed car: {0}")]
+    Malformed(&'static str),
+    #[error("car holds an unverifiable block: {0}")]
+    Verify(#[from] VerifyError),
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::multihash::Multihash;
+
+    use super::*;
+    use crate::ipfs::verify::RAW_CODEC;
+
+    /// Encode a length prefixed section, for data short enough to take a one byte length
+    fn section(data: &[u8]) -> Vec<u8> {
+        let mut section = vec![data.len() as u8];
+        section.extend_from_slice(data);
+        section
+    }
+
+    #[test]
+    fn blocks_are_read_and_verified_as_they_arrive() {
+        let hash = Multihash::wrap(0x1e, blake3::hash(b"hello").as_bytes()).unwrap();
+        let cid = Cid::new_v1(RAW_CODEC, hash);
+        let car = |block: &[u8]| {
+            // The header's content doesn't matter to us
+            let mut car = section(b"header");
+            car.extend(section(&[cid.to_bytes(), block.to_vec()].concat()));
+            car
+        };
+
+        // Blocks come out once all of them has arrived, however the archive is split up
+        let mut reader = CarReader::default();
+        for byte in car(b"hello") {
+            assert!(reader.next_block().unwrap().is_none());
+            reader.extend(&[byte]);
+        }
+        assert_eq!(reader.next_block().unwrap(), Some((cid, b"hello".to_vec())));
+        assert!(reader.finish().is_ok());
+
+        let mut reader = CarReader::default();
+        reader.extend(&car(b"tampered"));
+        assert!(matches!(
+            reader.next_block(),
+            Err(CarError::Verify(VerifyError::HashMismatch(_)))
+        ));
+
+        let mut reader = CarReader::default();
+        reader.extend(&car(b"hello")[..10]);
+        assert!(reader.next_block().unwrap().is_none());
+        assert!(matches!(reader.finish(), Err(CarError::Malformed(_))));
+    }
+
+    #[test]
+    fn sections_longer_than_a_block_are_rejected() {
+        // A section claiming to be 3 MiB long
+        let mut reader = CarReader::default();
+        reader.extend(&[0x80, 0x80, 0xc0, 0x01]);
+        assert!(matches!(
+            reader.next_block(),
+            Err(CarError::Malformed("section too long"))
+        ));
+    }
+}
@@ -5,6 +5,7 @@ use http::uri::Scheme;
 use ipfs_api_backend_hyper::{IpfsClient as HyperIpfsClient, TryFromUri};
 
 pub use ipfs_api_backend_hyper::request::Add as AddRequest;
+pub use ipfs_api_backend_hyper::request::BlockPut as BlockPutRequest;
 
 use super::{IpfsError, IpfsRemote};
 
@@ -12,6 +13,8 @@ use super::{IpfsError, IpfsRemote};
 const DEFAULT_CID_VERSION: u32 = 1;
 /// Default hash function to use when adding or hashing data against the IPFS API
 const DEFAULT_HASH_FUNCTION: &str = "blake3";
+/// Default format to use when putting raw blocks against the IPFS API
+const DEFAULT_BLOCK_FORMAT: &str = "raw";
 
 /// Wrapper around a Hyper IPFS backend
 #[derive(Default)]
@@ -25,7 +28,7 @@ impl TryFrom<IpfsRemote> for IpfsClient {
         let scheme = Scheme::try_from(url.scheme())?;
         let username = url.username();
         let maybe_password = url.password();
-        let host_str = url.host_str().unwrap();
+        let host_str = url.host_str().ok_or(IpfsError::MissingHost(url.clone()))?;
         // TODO: for some reason, the port is not being parsed correctly, and is always None
         let port = url.port().unwrap_or(5001);
         let client = match maybe_password {
@@ -63,4 +66,12 @@ pub fn add_data_request() -> AddRequest<'static> {
     add
 }
 
+#[allow(clippy::field_reassign_with_default)]
+pub fn put_block_request() -> BlockPutRequest<'static> {
+    let mut put = BlockPutRequest::default();
+    put.format = Some(DEFAULT_BLOCK_FORMAT);
+    put.mhtype = Some(DEFAULT_HASH_FUNCTION);
+    put
+}
+
 pub type IpfsClientError = ipfs_api_backend_hyper::Error;
@@ -1,64 +1,311 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Write;
+use std::time::Duration;
 
 use cid::Cid;
-use http::uri::Scheme;
+use http::header::ACCEPT;
 use reqwest::Client;
 use url::Url;
 
+use futures_util::StreamExt;
+
+use super::car::CarReader;
+use super::progress::{NoProgress, ProgressReporter};
+use super::verify::{block_content, verify_block};
 use super::{IpfsError, IpfsRemote};
 
-/// A wrapper around a gateway url
-pub struct IpfsGateway(Url);
+/// How long to wait on a gateway before trying the next one
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// The content type trustless gateways serve single raw blocks as
+const RAW_BLOCK_CONTENT_TYPE: &str = "application/vnd.ipld.raw";
+/// The content type trustless gateways serve whole DAGs as
+const CAR_CONTENT_TYPE: &str = "application/vnd.ipld.car;version=1";
+
+/// An ordered list of gateway urls. Blocks are read from the first gateway that serves
+/// them in time, and are verified against their Cid, so any public gateway can be used
+/// without trusting it to serve untampered content
+pub struct IpfsGateway {
+    urls: Vec<Url>,
+    timeout: Duration,
+}
 
 impl Default for IpfsGateway {
     fn default() -> Self {
-        Self(Url::parse("http://127.0.0.1:8080").unwrap())
+        Self::new(Url::parse("http://127.0.0.1:8080").unwrap())
     }
 }
 
 impl From<IpfsRemote> for IpfsGateway {
     fn from(remote: IpfsRemote) -> Self {
-        Self(remote.gateway_url.clone())
+        Self::new(remote.gateway_url).with_fallbacks(remote.fallback_gateway_urls)
     }
 }
 
 impl From<Url> for IpfsGateway {
     fn from(url: Url) -> Self {
-        Self(url)
+        Self::new(url)
     }
 }
 
 impl IpfsGateway {
-    #[allow(dead_code)]
     pub fn new(url: Url) -> Self {
-        Self(url)
-    }
-
-    // TODO: this isn't working quite right
-    pub async fn get(&self, cid: &Cid, path: Option<PathBuf>) -> Result<Vec<u8>, IpfsError> {
-        let maybe_port = self.0.port();
-        let scheme = Scheme::try_from(self.0.scheme())?;
-        let host_str = match maybe_port {
-            Some(port) => format!("{}:{}", self.0.host_str().unwrap(), port),
-            None => self.0.host_str().unwrap().to_string(),
-        };
-        let url = match path {
-            Some(p) => Url::parse(&format!(
-                "{}://{}/ipfs/{}/{}",
-                scheme,
-                host_str,
-                cid,
-                p.display()
-            )),
-            None => Url::parse(&format!("{}://{}/ipfs/{}", scheme, host_str, cid)),
-        }?;
-        // TODO: not 100% sure why I need to use trust_dns here, but this works
-        #[cfg(not(target_arch = "wasm32"))]
-        let client = Client::builder().trust_dns(true).build()?;
-        #[cfg(target_arch = "wasm32")]
-        let client = Client::builder().build()?;
-        let resp = client.get(url).send().await?;
-        let bytes = resp.bytes().await?;
-        Ok(bytes.to_vec())
+        Self {
+            urls: vec![url],
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Gateways to try, in order, when the ones before them fail
+    pub fn with_fallbacks(mut self, urls: impl IntoIterator<Item = Url>) -> Self {
+        self.urls.extend(urls);
+        self
+    }
+
+    /// How long to wait on each gateway for a block
+    #[allow(dead_code)]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The url the first gateway serves a cid at
+    pub fn url(&self, cid: &Cid) -> Url {
+        let mut url = self.urls[0].clone();
+        url.set_path(&format!("/ipfs/{}", cid));
+        url
+    }
+
+    /// Read the content a cid names, verifying every block it's made of
+    pub async fn get(&self, cid: &Cid) -> Result<Vec<u8>, IpfsError> {
+        let mut data = Vec::new();
+        self.get_to(cid, &mut data, &NoProgress).await?;
+        Ok(data)
+    }
+
+    /// Stream the content a cid names into a writer, verifying every block it's made of.
+    /// The whole DAG is requested as a CAR from a trustless gateway, and any blocks it
+    /// doesn't hold are read one at a time. Raw cids name their content directly.
+    /// UnixFS files are reassembled by following their links in order
+    /// # Args
+    /// - cid: the cid to read
+    /// - out: where to write the content
+    /// - progress: told about every byte of content written
+    pub async fn get_to<W: Write>(
+        &self,
+        cid: &Cid,
+        out: &mut W,
+        progress: &dyn ProgressReporter,
+    ) -> Result<(), IpfsError> {
+        let mut dag = DagWriter::new(cid, out, progress);
+        for url in &self.urls {
+            match self.get_car_from(url, cid, &mut dag).await {
+                Ok(()) => break,
+                // What was written stays written, so the next gateway picks up where this left off
+                Err(e) => tracing::warn!("failed to read a car of {} from {}: {}", cid, url, e),
+            }
+        }
+        while let Some(cid) = dag.next_needed() {
+            let block = self.get_block(&cid).await?;
+            dag.put(cid, block)?;
+        }
+        Ok(())
+    }
+
+    /// Feed the verified blocks of a CAR into a DAG as they arrive
+    async fn get_car_from<W: Write>(
+        &self,
+        gateway_url: &Url,
+        cid: &Cid,
+        dag: &mut DagWriter<'_, W>,
+    ) -> Result<(), IpfsError> {
+        let mut url = gateway_url.clone();
+        url.set_path(&format!("/ipfs/{}", cid));
+        url.set_query(Some("format=car&dag-scope=all"));
+        let request = client()?.get(url).header(ACCEPT, CAR_CONTENT_TYPE).send();
+        let response = timeout(self.timeout, request)
+            .await
+            .ok_or(IpfsError::Timeout(gateway_url.clone()))??
+            .error_for_status()?;
+
+        // Only give up on a gateway once it stops sending, however long the whole CAR takes
+        let mut body = response.bytes_stream();
+        let mut car = CarReader::default();
+        while let Some(chunk) = timeout(self.timeout, body.next())
+            .await
+            .ok_or(IpfsError::Timeout(gateway_url.clone()))?
+        {
+            car.extend(&chunk?);
+            while let Some((cid, block)) = car.next_block()? {
+                dag.put(cid, block)?;
+            }
+        }
+        car.finish()?;
+        Ok(())
+    }
+
+    /// Read a single block, trying each gateway in turn until one serves it intact
+    async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, IpfsError> {
+        let mut last_error = None;
+        for url in &self.urls {
+            match self.get_block_from(url, cid).await {
+                Ok(block) => return Ok(block),
+                Err(e) => {
+                    tracing::warn!("failed to read {} from {}: {}", cid, url, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or(IpfsError::NoGateways))
+    }
+
+    async fn get_block_from(&self, gateway_url: &Url, cid: &Cid) -> Result<Vec<u8>, IpfsError> {
+        let mut url = gateway_url.clone();
+        url.set_path(&format!("/ipfs/{}", cid));
+        url.set_query(Some("format=raw"));
+        let request = client()?
+            .get(url)
+            .header(ACCEPT, RAW_BLOCK_CONTENT_TYPE)
+            .send();
+        let read = async { request.await?.error_for_status()?.bytes().await };
+        let block = timeout(self.timeout, read)
+            .await
+            .ok_or(IpfsError::Timeout(gateway_url.clone()))??;
+        verify_block(cid, &block)?;
+        Ok(block.to_vec())
+    }
+}
+
+/// Writes out the content of a DAG in order, as its blocks come in in any order.
+/// Only blocks linked to by what's been written are held on to, so a gateway can't
+/// make us buffer blocks the DAG never asked for
+struct DagWriter<'a, W> {
+    /// Blocks yet to be written, the next one last
+    stack: Vec<Cid>,
+    /// How many times each block is still on the stack. A file can link the same block
+    /// more than once, and a CAR only holds it once
+    wanted: HashMap<Cid, usize>,
+    /// Blocks that came in before they could be written
+    pending: HashMap<Cid, Vec<u8>>,
+    out: &'a mut W,
+    progress: &'a dyn ProgressReporter,
+}
+
+impl<'a, W: Write> DagWriter<'a, W> {
+    fn new(root: &Cid, out: &'a mut W, progress: &'a dyn ProgressReporter) -> Self {
+        Self {
+            stack: vec![*root],
+            wanted: HashMap::from([(*root, 1)]),
+            pending: HashMap::new(),
+            out,
+            progress,
+        }
+    }
+
+    /// The block the rest of the content is waiting on, if any
+    fn next_needed(&self) -> Option<Cid> {
+        self.stack.last().copied()
+    }
+
+    /// Take a verified block, writing out all the content that's now in order.
+    /// Blocks that aren't wanted are dropped
+    fn put(&mut self, cid: Cid, block: Vec<u8>) -> Result<(), IpfsError> {
+        if !self.wanted.contains_key(&cid) {
+            tracing::debug!("dropping unrequested block {}", cid);
+            return Ok(());
+        }
+        self.pending.insert(cid, block);
+        // Depth first, so content comes out in order
+        while let Some(cid) = self
+            .stack
+            .last()
+            .copied()
+            .filter(|cid| self.pending.contains_key(cid))
+        {
+            self.stack.pop();
+            // Blocks linked again further on are held on to until their last use
+            let block = match self.wanted.get_mut(&cid) {
+                Some(count) if *count > 1 => {
+                    *count -= 1;
+                    self.pending[&cid].clone()
+                }
+                _ => {
+                    self.wanted.remove(&cid);
+                    self.pending.remove(&cid).unwrap()
+                }
+            };
+            let (content, links) = block_content(&cid, block)?;
+            self.out.write_all(&content)?;
+            self.progress.advance(content.len() as u64);
+            for link in &links {
+                *self.wanted.entry(*link).or_default() += 1;
+            }
+            self.stack.extend(links.into_iter().rev());
+        }
+        Ok(())
+    }
+}
+
+fn client() -> Result<Client, IpfsError> {
+    // TODO: not 100% sure why I need to use trust_dns here, but this works
+    #[cfg(not(target_arch = "wasm32"))]
+    let client = Client::builder().trust_dns(true).build()?;
+    #[cfg(target_arch = "wasm32")]
+    let client = Client::builder().build()?;
+    Ok(client)
+}
+
+/// Run a future, giving up on it after a while
+#[cfg(not(target_arch = "wasm32"))]
+async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    tokio::time::timeout(duration, future).await.ok()
+}
+
+/// Run a future, giving up on it after a while
+#[cfg(target_arch = "wasm32")]
+async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    use futures_util::future::{select, Either};
+
+    let sleep = gloo_timers::future::sleep(duration);
+    futures_util::pin_mut!(future, sleep);
+    match select(future, sleep).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipfs::verify::tests::{file_node, raw_cid};
+
+    #[test]
+    fn blocks_the_dag_did_not_ask_for_are_dropped() {
+        let root = raw_cid(b"hello");
+        let mut out = Vec::new();
+        let mut dag = DagWriter::new(&root, &mut out, &NoProgress);
+
+        dag.put(raw_cid(b"junk"), b"junk".to_vec()).unwrap();
+        assert!(dag.pending.is_empty());
+        assert_eq!(dag.next_needed(), Some(root));
+
+        dag.put(root, b"hello".to_vec()).unwrap();
+        assert_eq!(dag.next_needed(), None);
+        assert_eq!(out, b"hello");
+    }
+
+    #[test]
+    fn blocks_linked_more_than_once_are_written_each_time() {
+        // A CAR only holds a block once, however many times it's linked
+        let leaf = raw_cid(b"\0\0");
+        let (root, root_block) = file_node(b"", &[leaf, leaf]);
+        let mut out = Vec::new();
+        let mut dag = DagWriter::new(&root, &mut out, &NoProgress);
+
+        dag.put(root, root_block).unwrap();
+        dag.put(leaf, b"\0\0".to_vec()).unwrap();
+        assert_eq!(dag.next_needed(), None);
+        assert!(dag.pending.is_empty() && dag.wanted.is_empty());
+        assert_eq!(out, b"\0\0\0\0");
     }
 }
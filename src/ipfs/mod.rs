@@ -3,13 +3,26 @@ use std::fmt::Display;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+mod car;
+#[cfg(not(target_arch = "wasm32"))]
 mod client;
 mod gateway;
+mod progress;
+mod verify;
 
-pub use client::{add_data_request, hash_data_request, IpfsClient, IpfsClientError};
+#[cfg(not(target_arch = "wasm32"))]
+pub use client::{
+    add_data_request, hash_data_request, put_block_request, IpfsClient, IpfsClientError,
+};
+#[cfg(not(target_arch = "wasm32"))]
 pub use ipfs_api_backend_hyper::IpfsApi;
 
+pub use car::CarError;
 pub use gateway::IpfsGateway;
+pub use progress::ProgressReporter;
+#[cfg(not(target_arch = "wasm32"))]
+pub use verify::dag_content;
+pub use verify::VerifyError;
 
 /// A connection to an IPFS remote
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +33,9 @@ pub struct IpfsRemote {
     /// Url pointing to a public IPFS gateway
     /// Should not require or include authentication
     pub gateway_url: Url,
+    /// Gateways to fall back on, in order, when the first one fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_gateway_urls: Vec<Url>,
 }
 
 impl Default for IpfsRemote {
@@ -28,6 +44,7 @@ impl Default for IpfsRemote {
         Self {
             api_url: Url::parse("http://127.0.0.1:5001").unwrap(),
             gateway_url: Url::parse("http://127.0.0.1:8080").unwrap(),
+            fallback_gateway_urls: Vec::new(),
         }
     }
 }
@@ -36,7 +53,11 @@ impl Display for IpfsRemote {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let api_url = self.api_url.clone();
         let gateway_url = self.gateway_url.clone();
-        write!(f, "api_url: {}\ngateway_url: {}", api_url, gateway_url)
+        write!(f, "api_url: {}\ngateway_url: {}", api_url, gateway_url)?;
+        for url in &self.fallback_gateway_urls {
+            write!(f, "\nfallback_gateway_url: {}", url)?;
+        }
+        Ok(())
     }
 }
 
@@ -53,6 +74,18 @@ pub enum IpfsError {
     #[cfg(not(target_arch = "wasm32"))]
     #[error("Failed to build client")]
     Client(#[from] IpfsClientError),
+    #[error("url has no host: {0}")]
+    MissingHost(Url),
     #[error("Failed to parse port")]
     Port(#[from] std::num::ParseIntError),
+    #[error("gateway timed out: {0}")]
+    Timeout(Url),
+    #[error("no gateways configured")]
+    NoGateways,
+    #[error("gateway served an unverifiable block: {0}")]
+    Verify(#[from] VerifyError),
+    #[error("gateway served an unreadable car: {0}")]
+    Car(#[from] CarError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
 }
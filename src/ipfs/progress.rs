@@ -0,0 +1,18 @@
+/// Told how transfers to and from Ipfs are going, so they can be shown to whoever is waiting
+pub trait ProgressReporter: Send + Sync {
+    /// A transfer of some named content started. Its length is given if it's known up front
+    fn start(&self, name: &str, len: Option<u64>);
+    /// Some more bytes of the current transfer went through
+    fn advance(&self, bytes: u64);
+    /// The current transfer finished
+    fn finish(&self);
+}
+
+/// Reports progress to no one
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn start(&self, _name: &str, _len: Option<u64>) {}
+    fn advance(&self, _bytes: u64) {}
+    fn finish(&self) {}
+}
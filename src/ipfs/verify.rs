@@ -0,0 +1,314 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::future::Future;
+
+use cid::Cid;
+#[cfg(not(target_arch = "wasm32"))]
+use futures_util::stream::{try_unfold, Stream};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::IpfsError;
+
+/// Multicodec for raw blocks, whose content is the block itself
+pub const RAW_CODEC: u64 = 0x55;
+/// Multicodec for dag-pb blocks, which hold UnixFS nodes
+pub const DAG_PB_CODEC: u64 = 0x70;
+/// Multihash code for blake3 -- what we hash objects with against Ipfs
+const BLAKE3_CODE: u64 = 0x1e;
+/// Multihash code for sha2-256 -- what Ipfs hashes with by default
+const SHA2_256_CODE: u64 = 0x12;
+
+/// UnixFS data types we know how to read the content of
+const UNIXFS_RAW: u64 = 0;
+const UNIXFS_FILE: u64 = 2;
+
+/// Check that a block is the one a Cid names, by hashing it the way the Cid says to
+pub fn verify_block(cid: &Cid, block: &[u8]) -> Result<(), VerifyError> {
+    let hash = cid.hash();
+    let digest = match hash.code() {
+        BLAKE3_CODE => blake3::hash(block).as_bytes().to_vec(),
+        SHA2_256_CODE => {
+            use sha2::Digest;
+            sha2::Sha256::digest(block).to_vec()
+        }
+        code => return Err(VerifyError::UnsupportedHash(code)),
+    };
+    if hash.digest() != digest.as_slice() {
+        return Err(VerifyError::HashMismatch(*cid));
+    }
+    Ok(())
+}
+
+/// Split a verified block into the content it holds itself, and the Cids of the blocks
+/// holding the rest of its content, in order
+pub fn block_content(cid: &Cid, block: Vec<u8>) -> Result<(Vec<u8>, Vec<Cid>), VerifyError> {
+    match cid.codec() {
+        RAW_CODEC => Ok((block, Vec::new())),
+        DAG_PB_CODEC => {
+            let node = UnixFsNode::decode(&block)?;
+            Ok((node.data, node.links))
+        }
+        codec => Err(VerifyError::UnsupportedCodec(codec)),
+    }
+}
+
+/// Stream the content a Cid names a block at a time, reading each block with `get_block`
+/// and verifying it against its Cid. UnixFS files spanning many blocks are walked depth
+/// first, so their content comes out in order
+#[cfg(not(target_arch = "wasm32"))]
+pub fn dag_content<'a, F, Fut, E>(
+    root: &Cid,
+    get_block: F,
+) -> impl Stream<Item = Result<Vec<u8>, E>> + 'a
+where
+    F: Fn(Cid) -> Fut + 'a,
+    Fut: Future<Output = Result<Vec<u8>, E>> + 'a,
+    E: From<IpfsError> + 'a,
+{
+    try_unfold(
+        (vec![*root], get_block),
+        |(mut stack, get_block)| async move {
+            let Some(cid) = stack.pop() else {
+                return Ok(None);
+            };
+            let block = get_block(cid).await?;
+            verify_block(&cid, &block).map_err(IpfsError::from)?;
+            let (content, links) = block_content(&cid, block).map_err(IpfsError::from)?;
+            stack.extend(links.into_iter().rev());
+            Ok(Some((content, (stack, get_block))))
+        },
+    )
+}
+
+/// A node of a UnixFS file. Its content is its own data, followed by the content of its links
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnixFsNode {
+    pub data: Vec<u8>,
+    pub links: Vec<Cid>,
+}
+
+impl UnixFsNode {
+    /// Decode a dag-pb block holding part of a UnixFS file
+    pub fn decode(block: &[u8]) -> Result<Self, VerifyError> {
+        let mut links = Vec::new();
+        let mut unixfs = None;
+        for field in Fields::new(block) {
+            match field? {
+                (1, Value::Bytes(data)) => unixfs = Some(data),
+                (2, Value::Bytes(link)) => links.push(decode_link(link)?),
+                _ => {}
+            }
+        }
+
+        let mut kind = None;
+        let mut data = Vec::new();
+        for field in Fields::new(unixfs.ok_or(VerifyError::Malformed("missing UnixFS data"))?) {
+            match field? {
+                (1, Value::Varint(value)) => kind = Some(value),
+                (2, Value::Bytes(bytes)) => data = bytes.to_vec(),
+                _ => {}
+            }
+        }
+        match kind {
+            Some(UNIXFS_RAW | UNIXFS_FILE) => Ok(Self { data, links }),
+            Some(kind) => Err(VerifyError::UnsupportedUnixFs(kind)),
+            None => Err(VerifyError::Malformed("missing UnixFS type")),
+        }
+    }
+}
+
+/// Read the Cid a dag-pb link points to
+fn decode_link(link: &[u8]) -> Result<Cid, VerifyError> {
+    for field in Fields::new(link) {
+        if let (1, Value::Bytes(hash)) = field? {
+            return Cid::try_from(hash).map_err(|_| VerifyError::Malformed("invalid link cid"));
+        }
+    }
+    Err(VerifyError::Malformed("link without a cid"))
+}
+
+/// Read an unsigned LEB128 varint off the front of some data, as protobuf and CARs use
+pub fn read_varint(data: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first()?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// The value of a protobuf field, for the wire types dag-pb and UnixFS use
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Iterate over the fields of a protobuf message, as (field number, value) pairs
+struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn varint(&mut self) -> Result<u64, VerifyError> {
+        read_varint(&mut self.data).ok_or(VerifyError::Malformed("invalid varint"))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], VerifyError> {
+        if self.data.len() < len {
+            return Err(VerifyError::Malformed("truncated field"));
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>), VerifyError> {
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            0 => Value::Varint(self.varint()?),
+            1 => self.take(8).map(|_| Value::Fixed)?,
+            2 => {
+                let len = self.varint()? as usize;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => self.take(4).map(|_| Value::Fixed)?,
+            _ => return Err(VerifyError::Malformed("unknown wire type")),
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>), VerifyError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        // Don't keep reading past something we couldn't parse
+        if field.is_err() {
+            self.data = &[];
+        }
+        Some(field)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("unsupported codec: {0:#x}")]
+    UnsupportedCodec(u64),
+    #[error("unsupported hash function: {0:#x}")]
+    UnsupportedHash(u64),
+    #[error("unsupported UnixFS node type: {0}")]
+    UnsupportedUnixFs(u64),
+    #[error("block does not match its cid: {0}")]
+    HashMismatch(Cid),
+    #[error("malformed dag-pb block: {0}")]
+    Malformed(&'static str),
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::collections::HashMap;
+
+    use cid::multihash::Multihash;
+    use futures_util::TryStreamExt;
+
+    use super::*;
+
+    pub(crate) fn raw_cid(data: &[u8]) -> Cid {
+        let hash = Multihash::wrap(BLAKE3_CODE, blake3::hash(data).as_bytes()).unwrap();
+        Cid::new_v1(RAW_CODEC, hash)
+    }
+
+    /// A dag-pb block holding a UnixFS File node with some data of its own, then links
+    pub(crate) fn file_node(data: &[u8], links: &[Cid]) -> (Cid, Vec<u8>) {
+        let mut unixfs = vec![1 << 3, UNIXFS_FILE as u8];
+        unixfs.extend(bytes_field(2, data));
+        let mut block = Vec::new();
+        for link in links {
+            block.extend(bytes_field(2, &bytes_field(1, &link.to_bytes())));
+        }
+        block.extend(bytes_field(1, &unixfs));
+        let hash = Multihash::wrap(BLAKE3_CODE, blake3::hash(&block).as_bytes()).unwrap();
+        (Cid::new_v1(DAG_PB_CODEC, hash), block)
+    }
+
+    /// Encode a length delimited protobuf field
+    fn bytes_field(number: u8, bytes: &[u8]) -> Vec<u8> {
+        let mut field = vec![number << 3 | 2, bytes.len() as u8];
+        field.extend_from_slice(bytes);
+        field
+    }
+
+    #[test]
+    fn raw_blocks_must_match_their_cid() {
+        let cid = raw_cid(b"hello");
+        assert!(verify_block(&cid, b"hello").is_ok());
+        assert!(matches!(
+            verify_block(&cid, b"tampered"),
+            Err(VerifyError::HashMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn unixfs_files_list_their_data_and_links() {
+        let leaf = raw_cid(b" world");
+        // A UnixFS File node holding "hello", then linking to a raw leaf
+        let mut unixfs = vec![1 << 3, UNIXFS_FILE as u8];
+        unixfs.extend(bytes_field(2, b"hello"));
+        let mut block = bytes_field(2, &bytes_field(1, &leaf.to_bytes()));
+        block.extend(bytes_field(1, &unixfs));
+
+        let node = UnixFsNode::decode(&block).unwrap();
+        assert_eq!(node.data, b"hello");
+        assert_eq!(node.links, vec![leaf]);
+
+        // Directories aren't files
+        let directory = bytes_field(1, &[1 << 3, 1]);
+        assert!(matches!(
+            UnixFsNode::decode(&directory),
+            Err(VerifyError::UnsupportedUnixFs(1))
+        ));
+    }
+
+    #[tokio::test]
+    async fn multi_block_files_are_read_in_order() {
+        // root ("") -> [inner ("hello") -> [" world"], "!"]
+        let world = raw_cid(b" world");
+        let bang = raw_cid(b"!");
+        let (inner, inner_block) = file_node(b"hello", &[world]);
+        let (root, root_block) = file_node(b"", &[inner, bang]);
+        let mut blocks = HashMap::from([
+            (world, b" world".to_vec()),
+            (bang, b"!".to_vec()),
+            (inner, inner_block),
+            (root, root_block),
+        ]);
+
+        let read = |blocks: HashMap<Cid, Vec<u8>>| async move {
+            let get_block = |cid: Cid| {
+                let block = blocks.get(&cid).cloned();
+                async move { block.ok_or(IpfsError::NoGateways) }
+            };
+            dag_content(&root, get_block).try_concat().await
+        };
+        assert_eq!(read(blocks.clone()).await.unwrap(), b"hello world!");
+
+        // A tampered leaf fails the whole read
+        blocks.insert(world, b" there".to_vec());
+        assert!(matches!(
+            read(blocks).await,
+            Err(IpfsError::Verify(VerifyError::HashMismatch(cid))) if cid == world
+        ));
+    }
+}
@@ -1,10 +1,10 @@
 mod eth;
 mod types;
 
+mod ipfs;
+
 #[cfg(not(target_arch = "wasm32"))]
 mod cli;
-#[cfg(not(target_arch = "wasm32"))]
-mod ipfs;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm;
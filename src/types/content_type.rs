@@ -0,0 +1,132 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Read;
+use std::path::Path;
+
+use super::Object;
+
+/// The content type of anything we can't tell apart from raw bytes
+pub const OCTET_STREAM: &str = "application/octet-stream";
+/// How much of a file to read when sniffing its content type
+#[cfg(not(target_arch = "wasm32"))]
+const SNIFF_LENGTH: u64 = 8192;
+
+/// How the site should show an object, based on its content type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Viewer {
+    Markdown,
+    Text,
+    Image,
+    Audio,
+    Video,
+    Pdf,
+    /// Anything else is linked to, rather than shown inline
+    Download,
+}
+
+impl Viewer {
+    pub fn for_content_type(content_type: &str) -> Self {
+        let content_type = normalize(content_type);
+        match content_type.split_once('/') {
+            Some((_, "markdown")) => Self::Markdown,
+            Some(("application", "pdf")) => Self::Pdf,
+            Some(("application", "json")) => Self::Text,
+            Some(("text", _)) => Self::Text,
+            Some(("image", _)) => Self::Image,
+            Some(("audio", _)) => Self::Audio,
+            Some(("video", _)) => Self::Video,
+            _ => Self::Download,
+        }
+    }
+
+    /// Choose how to show an object from its content type. Objects staged before
+    /// content types were recorded can only be told apart by their extension
+    pub fn for_object(object: &Object, path: &Path) -> Self {
+        match object.content_type() {
+            Some(content_type) => Self::for_content_type(content_type),
+            None if path.extension().and_then(|ext| ext.to_str()) == Some("md") => Self::Markdown,
+            None => Self::Download,
+        }
+    }
+}
+
+/// Strip parameters and the `x-` prefix of unregistered subtypes, so that
+/// aliases like `audio/x-wav` and `audio/wav` compare equal
+pub fn normalize(content_type: &str) -> String {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    match essence.split_once('/') {
+        Some((top, sub)) => format!("{}/{}", top, sub.strip_prefix("x-").unwrap_or(sub)),
+        None => essence,
+    }
+}
+
+/// The content types objects with an extension are expected to have
+#[cfg(not(target_arch = "wasm32"))]
+pub fn for_extension(extension: &str) -> Vec<String> {
+    mime_guess::from_ext(extension)
+        .iter()
+        .map(|mime| normalize(mime.essence_str()))
+        .collect()
+}
+
+/// Detect the content type of a file. Magic bytes win when there are any,
+/// since extensions can lie. Text formats don't have them, so those fall back
+/// to the file's extension, and then to plain text if the file is valid utf-8
+#[cfg(not(target_arch = "wasm32"))]
+pub fn sniff(path: &Path) -> std::io::Result<String> {
+    let mut head = Vec::new();
+    std::fs::File::open(path)?
+        .take(SNIFF_LENGTH)
+        .read_to_end(&mut head)?;
+    if let Some(kind) = infer::get(&head) {
+        return Ok(kind.mime_type().to_string());
+    }
+    if let Some(mime) = mime_guess::from_path(path).first() {
+        return Ok(mime.essence_str().to_string());
+    }
+    // A multibyte character may have been cut off at the end of what we read
+    match std::str::from_utf8(&head) {
+        Ok(_) => Ok("text/plain".to_string()),
+        Err(err) if err.error_len().is_none() => Ok("text/plain".to_string()),
+        Err(_) => Ok(OCTET_STREAM.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniff_bytes(name: &str, data: &[u8]) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(name);
+        std::fs::write(&path, data).unwrap();
+        sniff(&path).unwrap()
+    }
+
+    #[test]
+    fn magic_bytes_win_over_extensions() {
+        // A png, misnamed as an mp3
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert_eq!(sniff_bytes("song.mp3", png), "image/png");
+        assert_eq!(sniff_bytes("paper.pdf", b"%PDF-1.7\n"), "application/pdf");
+        assert_eq!(sniff_bytes("hello.md", b"# Hello"), "text/markdown");
+        assert_eq!(sniff_bytes("notes", "caf\u{e9}".as_bytes()), "text/plain");
+        assert_eq!(sniff_bytes("blob", &[0xff, 0xfe, 0x00, 0x81]), OCTET_STREAM);
+    }
+
+    #[test]
+    fn content_types_choose_viewers() {
+        assert_eq!(normalize("audio/x-wav"), "audio/wav");
+        assert_eq!(normalize("Text/Plain; charset=utf-8"), "text/plain");
+        assert!(for_extension("wav").contains(&normalize("audio/x-wav")));
+        assert_eq!(Viewer::for_content_type("text/markdown"), Viewer::Markdown);
+        assert_eq!(Viewer::for_content_type("application/pdf"), Viewer::Pdf);
+        assert_eq!(Viewer::for_content_type("video/webm"), Viewer::Video);
+        assert_eq!(Viewer::for_content_type("text/csv"), Viewer::Text);
+        assert_eq!(Viewer::for_content_type(OCTET_STREAM), Viewer::Download);
+    }
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Metadata attached to a directory of Objects
+/// - updated_at: the time the metadata was last updated
+/// - metadata: This can be any piece of Json metadata you want
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Directory {
+    updated_at: DateTime<Utc>,
+    metadata: Value,
+}
+
+impl Directory {
+    pub fn new(metadata: Value) -> Self {
+        Self {
+            updated_at: Utc::now(),
+            metadata,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn updated_at(&self) -> &DateTime<Utc> {
+        &self.updated_at
+    }
+
+    #[allow(dead_code)]
+    pub fn metadata(&self) -> &Value {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, value: Value) {
+        self.metadata = value;
+        self.updated_at = Utc::now();
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use x25519_dalek::{EphemeralSecret, StaticSecret};
+
+/// Context for deriving key wrapping keys from a shared secret
+const WRAPPING_CONTEXT: &str = "krondor-org 2024-01-01 object key wrapping";
+
+/// An X25519 secret key, held by a reader of private objects
+#[derive(Clone)]
+pub struct SecretKey(StaticSecret);
+
+/// An X25519 public key, which object keys are wrapped for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey(x25519_dalek::PublicKey);
+
+/// How an Object's content is encrypted. The content is sealed with
+/// XChaCha20-Poly1305 under a random per-object key, which is then
+/// wrapped for each recipient
+/// - nonce: the nonce the content was sealed with
+/// - recipients: the content key, wrapped for each recipient
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Encryption {
+    #[serde(with = "hex_bytes")]
+    nonce: [u8; 24],
+    recipients: Vec<WrappedKey>,
+}
+
+/// The content key of an Object, wrapped for a single recipient
+/// - recipient: who can unwrap the key
+/// - ephemeral: the public half of the key pair the key was wrapped with
+/// - nonce: the nonce the key was wrapped with
+/// - key: the wrapped key
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WrappedKey {
+    recipient: PublicKey,
+    ephemeral: PublicKey,
+    #[serde(with = "hex_bytes")]
+    nonce: [u8; 24],
+    #[serde(with = "hex_bytes")]
+    key: [u8; 48],
+}
+
+impl SecretKey {
+    pub fn generate() -> Self {
+        Self(StaticSecret::random_from_rng(OsRng))
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(x25519_dalek::PublicKey::from(&self.0))
+    }
+}
+
+impl Encryption {
+    /// Who the content key was wrapped for
+    pub fn recipients(&self) -> Vec<PublicKey> {
+        self.recipients
+            .iter()
+            .map(|wrapped| wrapped.recipient)
+            .collect()
+    }
+
+    /// Whether the key can unwrap the content key
+    pub fn is_recipient(&self, key: &SecretKey) -> bool {
+        self.recipients().contains(&key.public_key())
+    }
+}
+
+/// Seal data under a new content key, wrapped for each of the recipients
+pub fn encrypt(
+    data: &[u8],
+    recipients: &[PublicKey],
+) -> Result<(Vec<u8>, Encryption), EncryptionError> {
+    let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let sealed = XChaCha20Poly1305::new(&key).encrypt(&nonce, data)?;
+
+    let mut wrapped_keys = Vec::new();
+    for recipient in recipients {
+        let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+        let ephemeral_public = PublicKey(x25519_dalek::PublicKey::from(&ephemeral));
+        let shared = ephemeral.diffie_hellman(&recipient.0);
+        let wrapping_key = wrapping_key(shared.as_bytes(), &ephemeral_public, recipient);
+        let wrap_nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let wrapped =
+            XChaCha20Poly1305::new(&wrapping_key.into()).encrypt(&wrap_nonce, key.as_slice())?;
+        wrapped_keys.push(WrappedKey {
+            recipient: *recipient,
+            ephemeral: ephemeral_public,
+            nonce: wrap_nonce.into(),
+            key: wrapped.try_into().map_err(|_| EncryptionError::Cipher)?,
+        });
+    }
+
+    let encryption = Encryption {
+        nonce: nonce.into(),
+        recipients: wrapped_keys,
+    };
+    Ok((sealed, encryption))
+}
+
+/// Open sealed data with a recipient's secret key
+pub fn decrypt(
+    sealed: &[u8],
+    encryption: &Encryption,
+    secret: &SecretKey,
+) -> Result<Vec<u8>, EncryptionError> {
+    let public = secret.public_key();
+    let wrapped = encryption
+        .recipients
+        .iter()
+        .find(|wrapped| wrapped.recipient == public)
+        .ok_or(EncryptionError::NotARecipient)?;
+    let shared = secret.0.diffie_hellman(&wrapped.ephemeral.0);
+    let wrapping_key = wrapping_key(shared.as_bytes(), &wrapped.ephemeral, &public);
+    let key = XChaCha20Poly1305::new(&wrapping_key.into())
+        .decrypt(XNonce::from_slice(&wrapped.nonce), wrapped.key.as_slice())?;
+    let data = XChaCha20Poly1305::new_from_slice(&key)
+        .map_err(|_| EncryptionError::Cipher)?
+        .decrypt(XNonce::from_slice(&encryption.nonce), sealed)?;
+    Ok(data)
+}
+
+/// Bind the wrapping key to both halves of the exchange, so a wrapped key
+/// can't be replayed for another recipient
+fn wrapping_key(shared: &[u8; 32], ephemeral: &PublicKey, recipient: &PublicKey) -> [u8; 32] {
+    let mut material = Vec::with_capacity(96);
+    material.extend_from_slice(shared);
+    material.extend_from_slice(ephemeral.0.as_bytes());
+    material.extend_from_slice(recipient.0.as_bytes());
+    blake3::derive_key(WRAPPING_CONTEXT, &material)
+}
+
+impl Display for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl FromStr for SecretKey {
+    type Err = EncryptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(StaticSecret::from(parse_key(s)?)))
+    }
+}
+
+impl Display for PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = EncryptionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(x25519_dalek::PublicKey::from(parse_key(s)?)))
+    }
+}
+
+impl Serialize for PublicKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn parse_key(s: &str) -> Result<[u8; 32], EncryptionError> {
+    hex::decode(s.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(EncryptionError::InvalidKey)
+}
+
+/// Serialize fixed size byte arrays as hex strings
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s)
+            .map_err(serde::de::Error::custom)?
+            .try_into()
+            .map_err(|_| serde::de::Error::custom(format!("expected {} bytes", N)))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("invalid key: expected 32 hex encoded bytes")]
+    InvalidKey,
+    #[error("this key can't decrypt the object")]
+    NotARecipient,
+    #[error("could not encrypt or decrypt the object")]
+    Cipher,
+}
+
+impl From<chacha20poly1305::Error> for EncryptionError {
+    fn from(_: chacha20poly1305::Error) -> Self {
+        Self::Cipher
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recipients_can_decrypt() {
+        let (alice, bob, eve) = (
+            SecretKey::generate(),
+            SecretKey::generate(),
+            SecretKey::generate(),
+        );
+        let data = b"for subscribers only";
+        let (sealed, encryption) = encrypt(data, &[alice.public_key(), bob.public_key()]).unwrap();
+        assert_ne!(sealed.as_slice(), data.as_slice());
+
+        // Round trip through the manifest's encoding
+        let encryption: Encryption =
+            serde_json::from_value(serde_json::to_value(&encryption).unwrap()).unwrap();
+        assert_eq!(decrypt(&sealed, &encryption, &alice).unwrap(), data);
+        assert_eq!(decrypt(&sealed, &encryption, &bob).unwrap(), data);
+        assert!(matches!(
+            decrypt(&sealed, &encryption, &eve),
+            Err(EncryptionError::NotARecipient)
+        ));
+
+        let mut tampered = sealed.clone();
+        tampered[0] ^= 1;
+        assert!(matches!(
+            decrypt(&tampered, &encryption, &alice),
+            Err(EncryptionError::Cipher)
+        ));
+    }
+
+    #[test]
+    fn keys_round_trip_as_hex() {
+        let secret = SecretKey::generate();
+        let parsed: SecretKey = secret.to_string().parse().unwrap();
+        assert_eq!(parsed.public_key(), secret.public_key());
+        let public = secret.public_key();
+        assert_eq!(public.to_string().parse::<PublicKey>().unwrap(), public);
+        assert!("not a key".parse::<PublicKey>().is_err());
+    }
+}
@@ -0,0 +1,431 @@
+use std::path::PathBuf;
+
+use atom_syndication as atom;
+use chrono::{DateTime, Utc};
+use cid::Cid;
+use rss::extension::itunes::{
+    ITunesCategory, ITunesChannelExtension, ITunesItemExtension, ITunesOwner, NAMESPACE,
+};
+use serde_json::{json, Map, Value};
+use url::Url;
+
+/// The JSON Feed version we write
+const JSON_FEED_VERSION: &str = "https://jsonfeed.org/version/1.1";
+
+/// The site feeds link back to
+/// - title: the name of the site
+/// - url: where the web app is hosted. Items link to their object pages on it
+/// - author: who to credit for the site's content, if anyone
+/// - email: how podcast directories can reach the author
+/// - image: cover art for the podcast feed
+/// - category: the podcast's Apple Podcasts category, like `Arts` or `Arts > Books`
+#[derive(Debug, Clone)]
+pub struct Site {
+    pub title: String,
+    pub url: Url,
+    pub author: Option<String>,
+    pub email: Option<String>,
+    pub image: Option<Url>,
+    pub category: Option<String>,
+}
+
+/// The kinds of feeds we publish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedKind {
+    /// Writing, linked to its object pages
+    Writing,
+    /// Audio, as podcast episodes with enclosures
+    Podcast,
+}
+
+impl FeedKind {
+    /// The stable name feeds of this kind are published under, without an extension
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Writing => "writing",
+            Self::Podcast => "podcast",
+        }
+    }
+}
+
+/// A file attached to an item, like a podcast episode's audio
+/// - url: where the file can be downloaded from
+/// - length: its size in bytes
+/// - content_type: its MIME type
+/// - duration_secs: how long it plays for, if it's audio
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enclosure {
+    pub url: String,
+    pub length: u64,
+    pub content_type: String,
+    pub duration_secs: Option<u64>,
+}
+
+/// An object to list in a feed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedItem {
+    pub path: PathBuf,
+    pub cid: Cid,
+    pub title: String,
+    pub description: Option<String>,
+    pub published: DateTime<Utc>,
+    pub updated: DateTime<Utc>,
+    pub enclosure: Option<Enclosure>,
+}
+
+/// A feed of objects, which may be written as Atom, RSS 2.0, or a JSON Feed
+#[derive(Debug, Clone)]
+pub struct Feed {
+    site: Site,
+    kind: FeedKind,
+    items: Vec<FeedItem>,
+}
+
+impl Feed {
+    pub fn new(site: Site, kind: FeedKind) -> Self {
+        Self {
+            site,
+            kind,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn kind(&self) -> FeedKind {
+        self.kind
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Add an item, keeping the most recently updated items first
+    pub fn insert(&mut self, item: FeedItem) {
+        let index = self
+            .items
+            .partition_point(|existing| existing.updated >= item.updated);
+        self.items.insert(index, item);
+    }
+
+    /// The web app's page for an object
+    fn item_link(&self, item: &FeedItem) -> String {
+        let mut url = self.site.url.clone();
+        url.query_pairs_mut()
+            .clear()
+            .append_pair("route", "object")
+            .append_pair("query", &item.cid.to_string());
+        url.to_string()
+    }
+
+    /// A stable id for an item. Cids change with every edit, so items are identified by path
+    fn item_id(&self, item: &FeedItem) -> String {
+        let mut url = self.site.url.clone();
+        url.set_fragment(Some(&item.path.display().to_string()));
+        url.to_string()
+    }
+
+    fn title(&self) -> String {
+        match self.kind {
+            FeedKind::Writing => format!("{} -- Writing", self.site.title),
+            FeedKind::Podcast => format!("{} -- Audio", self.site.title),
+        }
+    }
+
+    fn updated(&self) -> DateTime<Utc> {
+        self.items
+            .first()
+            .map(|item| item.updated)
+            .unwrap_or_default()
+    }
+
+    pub fn atom(&self) -> String {
+        let author = self.site.author.as_ref().map(|name| atom::Person {
+            name: name.clone(),
+            ..Default::default()
+        });
+        let entries = self
+            .items
+            .iter()
+            .map(|item| {
+                let mut links = vec![atom::Link {
+                    href: self.item_link(item),
+                    rel: "alternate".to_string(),
+                    ..Default::default()
+                }];
+                if let Some(enclosure) = &item.enclosure {
+                    links.push(atom::Link {
+                        href: enclosure.url.clone(),
+                        rel: "enclosure".to_string(),
+                        mime_type: Some(enclosure.content_type.clone()),
+                        length: Some(enclosure.length.to_string()),
+                        ..Default::default()
+                    });
+                }
+                atom::Entry {
+                    title: item.title.as_str().into(),
+                    id: self.item_id(item),
+                    updated: item.updated.into(),
+                    published: Some(item.published.into()),
+                    summary: item.description.as_deref().map(Into::into),
+                    links,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let feed = atom::Feed {
+            title: self.title().into(),
+            id: format!("{}#{}", self.site.url, self.kind.name()),
+            updated: self.updated().into(),
+            authors: author.into_iter().collect(),
+            links: vec![atom::Link {
+                href: self.site.url.to_string(),
+                rel: "alternate".to_string(),
+                ..Default::default()
+            }],
+            entries,
+            ..Default::default()
+        };
+        feed.to_string()
+    }
+
+    pub fn rss(&self) -> String {
+        let podcast = self.kind == FeedKind::Podcast;
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                let enclosure = item.enclosure.as_ref().map(|enclosure| rss::Enclosure {
+                    url: enclosure.url.clone(),
+                    length: enclosure.length.to_string(),
+                    mime_type: enclosure.content_type.clone(),
+                });
+                let itunes_ext = podcast.then(|| ITunesItemExtension {
+                    author: self.site.author.clone(),
+                    duration: item
+                        .enclosure
+                        .as_ref()
+                        .and_then(|enclosure| enclosure.duration_secs)
+                        .map(itunes_duration),
+                    summary: item.description.clone(),
+                    explicit: Some("false".to_string()),
+                    episode_type: Some("full".to_string()),
+                    ..Default::default()
+                });
+                rss::Item {
+                    title: Some(item.title.clone()),
+                    link: Some(self.item_link(item)),
+                    description: item.description.clone(),
+                    guid: Some(rss::Guid {
+                        value: self.item_id(item),
+                        permalink: false,
+                    }),
+                    pub_date: Some(item.published.to_rfc2822()),
+                    enclosure,
+                    itunes_ext,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let mut channel = rss::Channel {
+            title: self.title(),
+            link: self.site.url.to_string(),
+            description: self.title(),
+            last_build_date: Some(self.updated().to_rfc2822()),
+            items,
+            ..Default::default()
+        };
+        if podcast {
+            channel
+                .namespaces
+                .insert("itunes".to_string(), NAMESPACE.to_string());
+            let owner =
+                (self.site.author.is_some() || self.site.email.is_some()).then(|| ITunesOwner {
+                    name: self.site.author.clone(),
+                    email: self.site.email.clone(),
+                });
+            channel.set_itunes_ext(ITunesChannelExtension {
+                author: self.site.author.clone(),
+                summary: Some(self.title()),
+                explicit: Some("false".to_string()),
+                image: self.site.image.as_ref().map(Url::to_string),
+                categories: self
+                    .site
+                    .category
+                    .as_deref()
+                    .map(itunes_category)
+                    .into_iter()
+                    .collect(),
+                owner,
+                ..Default::default()
+            });
+        }
+        channel.to_string()
+    }
+
+    pub fn json(&self) -> Value {
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                let mut value = Map::new();
+                value.insert("id".to_string(), json!(self.item_id(item)));
+                value.insert("url".to_string(), json!(self.item_link(item)));
+                value.insert("title".to_string(), json!(item.title));
+                if let Some(description) = &item.description {
+                    value.insert("summary".to_string(), json!(description));
+                    value.insert("content_text".to_string(), json!(description));
+                } else {
+                    value.insert("content_text".to_string(), json!(item.title));
+                }
+                value.insert(
+                    "date_published".to_string(),
+                    json!(item.published.to_rfc3339()),
+                );
+                value.insert(
+                    "date_modified".to_string(),
+                    json!(item.updated.to_rfc3339()),
+                );
+                if let Some(enclosure) = &item.enclosure {
+                    let mut attachment = json!({
+                        "url": enclosure.url,
+                        "mime_type": enclosure.content_type,
+                        "size_in_bytes": enclosure.length,
+                    });
+                    if let Some(duration) = enclosure.duration_secs {
+                        attachment["duration_in_seconds"] = json!(duration);
+                    }
+                    value.insert("attachments".to_string(), json!([attachment]));
+                }
+                Value::Object(value)
+            })
+            .collect::<Vec<_>>();
+        let mut feed = json!({
+            "version": JSON_FEED_VERSION,
+            "title": self.title(),
+            "home_page_url": self.site.url.to_string(),
+            "items": items,
+        });
+        if let Some(author) = &self.site.author {
+            feed["authors"] = json!([{ "name": author }]);
+        }
+        feed
+    }
+}
+
+/// iTunes categories may have a subcategory, written here as `Category > Subcategory`
+fn itunes_category(category: &str) -> ITunesCategory {
+    match category.split_once('>') {
+        Some((category, subcategory)) => ITunesCategory {
+            text: category.trim().to_string(),
+            subcategory: Some(Box::new(itunes_category(subcategory))),
+        },
+        None => ITunesCategory {
+            text: category.trim().to_string(),
+            subcategory: None,
+        },
+    }
+}
+
+/// iTunes durations are written as h:mm:ss
+fn itunes_duration(secs: u64) -> String {
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn site() -> Site {
+        Site {
+            title: "Krondor".to_string(),
+            url: Url::parse("https://krondor.org/").unwrap(),
+            author: Some("Al".to_string()),
+            email: Some("al@krondor.org".to_string()),
+            image: Some(Url::parse("https://krondor.org/static/cover.png").unwrap()),
+            category: Some("Arts > Books".to_string()),
+        }
+    }
+
+    fn item(path: &str, updated: DateTime<Utc>) -> FeedItem {
+        FeedItem {
+            path: PathBuf::from(path),
+            cid: Cid::default(),
+            title: format!("{} & co", path),
+            description: Some("A lil hello!".to_string()),
+            published: updated,
+            updated,
+            enclosure: None,
+        }
+    }
+
+    #[test]
+    fn items_are_sorted_and_linked_to_the_web_app() {
+        let now = Utc::now();
+        let mut feed = Feed::new(site(), FeedKind::Writing);
+        feed.insert(item("writing/old.md", now - Duration::days(2)));
+        feed.insert(item("writing/new.md", now));
+        feed.insert(item("writing/middle.md", now - Duration::days(1)));
+
+        let json = feed.json();
+        let ids = json["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                "https://krondor.org/#writing/new.md",
+                "https://krondor.org/#writing/middle.md",
+                "https://krondor.org/#writing/old.md",
+            ]
+        );
+        let link = format!("https://krondor.org/?route=object&query={}", Cid::default());
+        assert_eq!(json["items"][0]["url"], link);
+
+        // Both XML formats parse, and escape what they need to
+        let atom = feed.atom().parse::<atom::Feed>().unwrap();
+        assert_eq!(atom.entries()[0].title().as_str(), "writing/new.md & co");
+        assert_eq!(atom.entries()[0].links()[0].href(), link);
+        let rss = feed.rss().parse::<rss::Channel>().unwrap();
+        assert_eq!(rss.items().len(), 3);
+        assert!(rss.itunes_ext().is_none());
+    }
+
+    #[test]
+    fn podcasts_have_enclosures_and_itunes_tags() {
+        let mut feed = Feed::new(site(), FeedKind::Podcast);
+        let mut episode = item("audio/episode.mp3", Utc::now());
+        episode.enclosure = Some(Enclosure {
+            url: "https://ipfs.io/ipfs/bafy".to_string(),
+            length: 1024,
+            content_type: "audio/mpeg".to_string(),
+            duration_secs: Some(3725),
+        });
+        feed.insert(episode);
+
+        let rss = feed.rss();
+        assert!(rss.contains(r#"xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd""#));
+        let channel = rss.parse::<rss::Channel>().unwrap();
+        let item = &channel.items()[0];
+        assert_eq!(item.enclosure().unwrap().length(), "1024");
+        assert_eq!(item.itunes_ext().unwrap().duration(), Some("1:02:05"));
+        let itunes = channel.itunes_ext().unwrap();
+        assert_eq!(itunes.author(), Some("Al"));
+        assert_eq!(itunes.image(), Some("https://krondor.org/static/cover.png"));
+        assert_eq!(itunes.categories()[0].text(), "Arts");
+        assert_eq!(
+            itunes.categories()[0].subcategory().unwrap().text(),
+            "Books"
+        );
+        assert_eq!(itunes.owner().unwrap().email(), Some("al@krondor.org"));
+
+        let json = feed.json();
+        assert_eq!(
+            json["items"][0]["attachments"][0]["duration_in_seconds"],
+            3725
+        );
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(not(target_arch = "wasm32"))]
+use serde_json::Value;
+
+/// The formats front matter may be written in, told apart by their fences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    /// Fenced by `---`
+    Yaml,
+    /// Fenced by `+++`
+    Toml,
+}
+
+impl FrontMatterFormat {
+    fn fence(&self) -> &'static str {
+        match self {
+            Self::Yaml => "---",
+            Self::Toml => "+++",
+        }
+    }
+}
+
+/// Split a markdown document into its raw front matter and body, if it has front matter.
+/// Front matter must open on the first line, and close with a matching fence on its own line
+pub fn split(text: &str) -> Option<(FrontMatterFormat, &str, &str)> {
+    // Skip a byte order mark if there is one
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let format = [FrontMatterFormat::Yaml, FrontMatterFormat::Toml]
+        .into_iter()
+        .find(|format| first_line(text) == format.fence())?;
+    let rest = &text[text.find('\n')? + 1..];
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == format.fence() {
+            let body = &rest[offset + line.len()..];
+            return Some((format, &rest[..offset], body));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The markdown body of a document, without any front matter
+pub fn strip(text: &str) -> &str {
+    match split(text) {
+        Some((_, _, body)) => body,
+        None => text,
+    }
+}
+
+/// Parse the front matter of a markdown document into a Json object
+/// # Returns None if the document has no front matter
+#[cfg(not(target_arch = "wasm32"))]
+pub fn parse(text: &str) -> Result<Option<Value>, FrontMatterError> {
+    let (format, raw, _body) = match split(text) {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    let value: Value = match format {
+        FrontMatterFormat::Yaml => serde_yaml::from_str(raw)?,
+        FrontMatterFormat::Toml => toml::from_str(raw)?,
+    };
+    match value {
+        Value::Object(_) => Ok(Some(value)),
+        // An empty block of yaml is null
+        Value::Null => Ok(None),
+        _ => Err(FrontMatterError::NotAnObject),
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default().trim_end()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, thiserror::Error)]
+pub enum FrontMatterError {
+    #[error("invalid yaml front matter: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("invalid toml front matter: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("front matter is not a map of fields")]
+    NotAnObject,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn yaml_and_toml_front_matter_are_parsed() {
+        let yaml = "---\ntitle: Hello World\ndescription: A lil hello!\n---\n# Hello\n";
+        assert_eq!(
+            parse(yaml).unwrap(),
+            Some(json!({ "title": "Hello World", "description": "A lil hello!" }))
+        );
+        assert_eq!(strip(yaml), "# Hello\n");
+
+        let toml = "+++\r\ntitle = \"Hello World\"\r\ngenre = \"blog\"\r\n+++\r\nHi";
+        assert_eq!(
+            parse(toml).unwrap(),
+            Some(json!({ "title": "Hello World", "genre": "blog" }))
+        );
+        assert_eq!(strip(toml), "Hi");
+    }
+
+    #[test]
+    fn documents_without_front_matter_are_untouched() {
+        let text = "# Hello\n---\ntitle: nope\n---\n";
+        assert_eq!(parse(text).unwrap(), None);
+        assert_eq!(strip(text), text);
+        // An unclosed fence isn't front matter either
+        assert_eq!(parse("---\ntitle: nope\n").unwrap(), None);
+        assert!(matches!(
+            parse("---\n- a list\n---\n"),
+            Err(FrontMatterError::NotAnObject)
+        ));
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::directory::Directory;
+use super::migrations::{format_of, migrate, MigrationError};
+use super::object::Object;
+use super::schema::{Collection, CollectionOrder, SchemaDocument};
+
+mod sharded;
+
+pub use sharded::{BlockStore, ManifestRoot};
+
+/// The format version written by this build of the crate.
+/// Bump this, and register a migration in `migrations`, whenever the
+/// serialized shape of the Manifest changes in a way older readers would
+/// get wrong. Fields they can ignore are added with serde defaults instead.
+pub const MANIFEST_FORMAT: u32 = 6;
+
+/// Manifest: describes the state of content
+/// - format: the version of the manifest format this was written in
+/// - objects: a set of Objects that comprise website content
+/// - directories: metadata for directories of objects, keyed by their path
+/// - schemas: the schemas objects and directories may be tagged with
+/// - search: the cid of the SearchIndex published alongside the manifest, if there is one
+/// - feeds: the cids of the feeds published alongside the manifest, keyed by stable names
+///   like `writing.atom`
+/// - previous_root: a cid pointing back to the previous version of the manifest
+/// - version: version information on the crate
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    // Note: manifests written before formats were tracked have no
+    //  format field, so they're treated as the first format
+    #[serde(default = "legacy_format")]
+    format: u32,
+    objects: BTreeMap<PathBuf, Object>,
+    #[serde(default)]
+    directories: BTreeMap<PathBuf, Directory>,
+    #[serde(default)]
+    schemas: SchemaDocument,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    search: Option<Cid>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    feeds: BTreeMap<String, Cid>,
+    previous_root: Cid,
+    version: Version,
+}
+
+/// An entry directly within a directory of the Manifest
+#[allow(dead_code)]
+pub enum Entry<'a> {
+    Object {
+        path: &'a PathBuf,
+        object: &'a Object,
+    },
+    Directory {
+        path: PathBuf,
+        directory: Option<&'a Directory>,
+        /// When the most recently created object within the directory was created
+        latest: DateTime<Utc>,
+    },
+}
+
+#[allow(dead_code)]
+impl Entry<'_> {
+    pub fn path(&self) -> &Path {
+        match self {
+            Entry::Object { path, .. } => path,
+            Entry::Directory { path, .. } => path,
+        }
+    }
+
+    fn created_at(&self) -> &DateTime<Utc> {
+        match self {
+            Entry::Object { object, .. } => object.created_at(),
+            Entry::Directory { latest, .. } => latest,
+        }
+    }
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            format: MANIFEST_FORMAT,
+            objects: BTreeMap::new(),
+            directories: BTreeMap::new(),
+            schemas: SchemaDocument::default(),
+            search: None,
+            feeds: BTreeMap::new(),
+            previous_root: Cid::default(),
+            version: Version::default(),
+        }
+    }
+}
+
+fn legacy_format() -> u32 {
+    1
+}
+
+impl Manifest {
+    /// Read a Manifest from raw bytes, migrating it to the current format if needed
+    pub fn from_slice(data: &[u8]) -> Result<Self, ManifestError> {
+        let value: Value = serde_json::from_slice(data)?;
+        Self::from_value(value)
+    }
+
+    /// Read a Manifest from a Json value, migrating it to the current format if needed
+    pub fn from_value(value: Value) -> Result<Self, ManifestError> {
+        let value = migrate(value)?;
+        let manifest = serde_json::from_value(value)?;
+        Ok(manifest)
+    }
+
+    /// Read the format version of a serialized Manifest without migrating it
+    pub fn format_of(data: &[u8]) -> Result<u32, ManifestError> {
+        let value: Value = serde_json::from_slice(data)?;
+        Ok(format_of(&value)?)
+    }
+
+    #[allow(dead_code)]
+    pub fn format(&self) -> u32 {
+        self.format
+    }
+
+    pub fn objects(&self) -> &BTreeMap<PathBuf, Object> {
+        &self.objects
+    }
+
+    #[allow(dead_code)]
+    pub fn object_by_cid(&self, cid: &Cid) -> Option<(&PathBuf, &Object)> {
+        self.objects.iter().find(|(_, object)| object.cid() == cid)
+    }
+
+    #[allow(dead_code)]
+    pub fn directories(&self) -> &BTreeMap<PathBuf, Directory> {
+        &self.directories
+    }
+
+    pub fn schemas(&self) -> &SchemaDocument {
+        &self.schemas
+    }
+
+    #[allow(dead_code)]
+    pub fn search(&self) -> Option<&Cid> {
+        self.search.as_ref()
+    }
+
+    #[allow(dead_code)]
+    pub fn feeds(&self) -> &BTreeMap<String, Cid> {
+        &self.feeds
+    }
+
+    /// Get the metadata for a directory, if it has any
+    #[allow(dead_code)]
+    pub fn directory(&self, path: &Path) -> Option<&Directory> {
+        self.directories.get(path)
+    }
+
+    /// Whether the path is a directory containing at least one object.
+    /// The empty path is the root directory
+    pub fn is_directory(&self, path: &Path) -> bool {
+        self.objects_within(path)
+            .any(|(object_path, _)| object_path != path)
+    }
+
+    /// List the objects and directories directly within a directory.
+    /// If the directory is tagged as a Collection, entries are sorted by its order,
+    /// otherwise they're sorted by name
+    #[allow(dead_code)]
+    pub fn ls(&self, dir: &Path) -> Vec<Entry<'_>> {
+        let mut entries: Vec<Entry<'_>> = Vec::new();
+        for (path, object) in self.objects_within(dir) {
+            let relative = match path.strip_prefix(dir) {
+                Ok(relative) => relative,
+                Err(_) => continue,
+            };
+            let mut components = relative.components();
+            let name = match components.next() {
+                Some(name) => name,
+                None => continue,
+            };
+            // Objects directly within the directory
+            if components.next().is_none() {
+                entries.push(Entry::Object { path, object });
+                continue;
+            }
+            // Otherwise, this object is within a subdirectory
+            // Paths are sorted, so all of a subdirectory's objects are adjacent
+            let subdir = dir.join(name);
+            if let Some(Entry::Directory { path, latest, .. }) = entries.last_mut() {
+                if path == &subdir {
+                    *latest = (*latest).max(*object.created_at());
+                    continue;
+                }
+            }
+            entries.push(Entry::Directory {
+                directory: self.directories.get(&subdir),
+                path: subdir,
+                latest: *object.created_at(),
+            });
+        }
+
+        let order = self
+            .directory(dir)
+            .and_then(|directory| Collection::try_from(directory.metadata().clone()).ok())
+            .map(|collection| collection.order)
+            .unwrap_or_default();
+        match order {
+            CollectionOrder::Name => entries.sort_by(|a, b| a.path().cmp(b.path())),
+            CollectionOrder::Newest => entries.sort_by(|a, b| b.created_at().cmp(a.created_at())),
+            CollectionOrder::Oldest => entries.sort_by(|a, b| a.created_at().cmp(b.created_at())),
+        }
+        entries
+    }
+
+    /// Iterate over every object at or beneath a path
+    fn objects_within(&self, path: &Path) -> impl Iterator<Item = (&PathBuf, &Object)> {
+        // Paths are ordered by component, so everything beneath a path is contiguous
+        let path = path.to_path_buf();
+        self.objects
+            .range(path.clone()..)
+            .take_while(move |(object_path, _)| object_path.starts_with(&path))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Manifest {
+    pub fn set_previous_root(&mut self, cid: Cid) {
+        self.previous_root = cid;
+    }
+
+    pub fn set_schemas(&mut self, schemas: SchemaDocument) {
+        self.schemas = schemas;
+    }
+
+    pub fn set_search(&mut self, cid: Option<Cid>) {
+        self.search = cid;
+    }
+
+    /// Set the feeds published alongside the manifest
+    pub fn set_feeds(&mut self, feeds: BTreeMap<String, Cid>) {
+        self.feeds = feeds;
+    }
+
+    /// Stamp the manifest with the version of the crate that is writing it
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
+    pub fn insert_object(&mut self, path: &Path, object: &Object) {
+        self.objects.insert(path.to_path_buf(), object.clone());
+    }
+
+    pub fn remove_object(&mut self, path: &PathBuf) {
+        self.objects.remove(path);
+    }
+
+    pub fn get_object_mut(&mut self, path: &PathBuf) -> Option<&mut Object> {
+        self.objects.get_mut(path)
+    }
+
+    /// Set the metadata for a directory
+    pub fn set_directory_metadata(&mut self, path: &Path, value: Value) {
+        match self.directories.get_mut(path) {
+            Some(directory) => directory.set_metadata(value),
+            None => {
+                self.directories
+                    .insert(path.to_path_buf(), Directory::new(value));
+            }
+        }
+    }
+
+    /// Keep only the objects matching a predicate, dropping metadata for directories left empty
+    pub fn retain_objects(&mut self, mut f: impl FnMut(&Object) -> bool) {
+        self.objects.retain(|_path, object| f(object));
+        self.prune_directories();
+    }
+
+    /// Drop metadata for any directories that no longer contain objects
+    pub fn prune_directories(&mut self) {
+        let paths = self.directories.keys().cloned().collect::<Vec<_>>();
+        for path in paths {
+            if !self.is_directory(&path) {
+                self.directories.remove(&path);
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub build_profile: String,
+    pub build_features: String,
+    pub repo_version: String,
+    pub version: String,
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Version {
+    pub fn new() -> Self {
+        Self {
+            build_profile: env!("BUILD_PROFILE").to_string(),
+            build_features: env!("BUILD_FEATURES").to_string(),
+            repo_version: env!("REPO_VERSION").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestError {
+    #[error("serde error: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("migration error: {0}")]
+    Migration(#[from] MigrationError),
+    #[error("invalid object path: {0}")]
+    InvalidPath(PathBuf),
+}
@@ -0,0 +1,461 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use cid::multihash::Multihash;
+use cid::Cid;
+use futures_util::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{Manifest, ManifestError, Version};
+
+/// Multicodec code for raw binary blocks
+const RAW_CODEC: u64 = 0x55;
+/// Multihash code for blake3 -- matches what we hash objects with against Ipfs
+const BLAKE3_CODE: u64 = 0x1e;
+/// How large a Shard may grow before its entries are split into buckets.
+/// Ipfs won't move blocks over 1MiB, so this leaves plenty of room
+const MAX_SHARD_SIZE: usize = 256 * 1024;
+/// How many buckets an oversized Shard's entries are split into
+const BUCKETS: usize = 16;
+
+/// Compute the Cid of a raw block, exactly as Ipfs would when putting
+/// it with the raw format and a blake3 hash
+pub fn block_cid(data: &[u8]) -> Cid {
+    let hash = blake3::hash(data);
+    // Note: a 32 byte digest always fits within our 64 byte multihash
+    let multihash = Multihash::wrap(BLAKE3_CODE, hash.as_bytes()).expect("valid multihash");
+    Cid::new_v1(RAW_CODEC, multihash)
+}
+
+/// Anything that can read raw blocks by their Cid
+#[allow(async_fn_in_trait)]
+pub trait BlockStore {
+    type Error: From<ManifestError>;
+
+    async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, Self::Error>;
+}
+
+/// The root block a RootCid points to, in either of our layouts
+pub enum ManifestRoot {
+    /// Every object inline in a single block
+    Flat(Manifest),
+    /// Objects spread across a tree of linked Shards
+    Sharded(ShardedManifest),
+}
+
+impl ManifestRoot {
+    /// Parse a root block. Flat manifests are migrated to the current format,
+    /// sharded ones are migrated when they're loaded
+    pub fn from_slice(data: &[u8]) -> Result<Self, ManifestError> {
+        let value: Value = serde_json::from_slice(data)?;
+        if value.get("shards").is_some() {
+            let sharded = serde_json::from_value(value)?;
+            Ok(Self::Sharded(sharded))
+        } else {
+            Manifest::from_value(value).map(Self::Flat)
+        }
+    }
+}
+
+/// Root block of a sharded manifest. In place of an inline map of objects, it
+/// links to the Shard describing the top level directory of our content
+/// - format: the version of the manifest format this was written in
+/// - shards: the cid of the root Shard
+/// - schemas: the schemas objects and directories may be tagged with
+/// - search: the cid of the SearchIndex published alongside the manifest, if there is one
+/// - feeds: the cids of the feeds published alongside the manifest, keyed by stable names
+/// - previous_root: a cid pointing back to the previous version of the manifest
+/// - version: version information on the crate
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ShardedManifest {
+    format: u32,
+    shards: Cid,
+    // Note: kept raw so roots written before schemas existed can be migrated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schemas: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    search: Option<Cid>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    feeds: BTreeMap<String, Cid>,
+    previous_root: Cid,
+    version: Version,
+}
+
+/// A single directory within a sharded manifest
+/// - entries: the objects and subdirectories within the directory
+/// - buckets: in place of entries, for directories too large for a single block. Each
+///   links to a Shard holding the entries whose names hash into it
+/// - directory: the directory's own metadata, if it has any
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Shard {
+    entries: BTreeMap<String, ShardEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    buckets: Vec<Cid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    directory: Option<Value>,
+}
+
+/// An entry within a Shard
+/// Objects are kept as raw values so that shards written in older formats can
+/// still be read and migrated
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardEntry {
+    /// An object stored at this name
+    Object(Value),
+    /// A link to the Shard for the directory at this name
+    Shard(Cid),
+}
+
+impl Shard {
+    pub fn from_slice(data: &[u8]) -> Result<Self, ManifestError> {
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    fn to_vec(&self) -> Result<Vec<u8>, ManifestError> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+impl ShardedManifest {
+    // Note: only the web app reads the manifest a directory at a time
+    /// Lazily read the Cid of the Shard describing a directory, only fetching
+    /// the shards along the way. An empty path is the root directory
+    #[cfg(any(test, target_arch = "wasm32"))]
+    async fn shard<S: BlockStore>(&self, store: &S, dir: &Path) -> Result<Option<Cid>, S::Error> {
+        let mut cid = self.shards;
+        for segment in segments(dir)? {
+            let mut shard = Shard::from_slice(&store.get_block(&cid).await?)?;
+            // Each level of buckets splits on the next byte of the name's hash
+            let mut depth = 0;
+            while !shard.buckets.is_empty() {
+                let bucket = shard.buckets[bucket(&segment, depth)];
+                shard = Shard::from_slice(&store.get_block(&bucket).await?)?;
+                depth += 1;
+            }
+            cid = match shard.entries.get(&segment) {
+                Some(ShardEntry::Shard(cid)) => *cid,
+                _ => return Ok(None),
+            };
+        }
+        Ok(Some(cid))
+    }
+
+    /// Fetch only the shards for a directory and everything within it, and assemble them
+    /// into a Manifest holding just those objects, migrated to the current format if needed
+    /// # Returns None if there's no such directory
+    #[cfg(any(test, target_arch = "wasm32"))]
+    pub async fn load_within<S: BlockStore>(
+        &self,
+        store: &S,
+        dir: &Path,
+    ) -> Result<Option<Manifest>, S::Error> {
+        match self.shard(store, dir).await? {
+            Some(cid) => Ok(Some(self.load_from(store, dir, cid).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Fetch every shard and assemble them into a flat Manifest,
+    /// migrating it to the current format if needed
+    pub async fn load<S: BlockStore>(&self, store: &S) -> Result<Manifest, S::Error> {
+        self.load_from(store, Path::new(""), self.shards).await
+    }
+
+    async fn load_from<S: BlockStore>(
+        &self,
+        store: &S,
+        dir: &Path,
+        shards: Cid,
+    ) -> Result<Manifest, S::Error> {
+        let mut objects = serde_json::Map::new();
+        let mut directories = serde_json::Map::new();
+        let mut frontier = vec![(dir.to_path_buf(), shards)];
+        // Walk the tree a level at a time, fetching each level concurrently
+        while !frontier.is_empty() {
+            let blocks = try_join_all(frontier.iter().map(|(_, cid)| store.get_block(cid))).await?;
+            let mut next = Vec::new();
+            for ((dir, _), block) in frontier.into_iter().zip(blocks) {
+                let shard = Shard::from_slice(&block)?;
+                // Buckets hold entries of the same directory
+                for bucket in shard.buckets {
+                    next.push((dir.clone(), bucket));
+                }
+                if let Some(directory) = shard.directory {
+                    directories.insert(dir.to_string_lossy().to_string(), directory);
+                }
+                for (name, entry) in shard.entries {
+                    let path = dir.join(name);
+                    match entry {
+                        ShardEntry::Object(value) => {
+                            objects.insert(path.to_string_lossy().to_string(), value);
+                        }
+                        ShardEntry::Shard(cid) => next.push((path, cid)),
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        let mut value = serde_json::to_value(self).map_err(ManifestError::from)?;
+        let map = value.as_object_mut().expect("manifest is an object");
+        map.remove("shards");
+        map.insert("objects".to_string(), Value::Object(objects));
+        map.insert("directories".to_string(), Value::Object(directories));
+        Ok(Manifest::from_value(value)?)
+    }
+}
+
+impl Manifest {
+    /// Split the manifest into a tree of Shards, one per directory
+    /// # Returns the root block, and every Shard block keyed by its Cid
+    pub fn shard(&self) -> Result<(ShardedManifest, BTreeMap<Cid, Vec<u8>>), ManifestError> {
+        let mut tree = DirTree::default();
+        for (path, object) in self.objects.iter() {
+            tree.insert(&segments(path)?, serde_json::to_value(object)?);
+        }
+        for (path, directory) in self.directories.iter() {
+            tree.insert_directory(&segments(path)?, serde_json::to_value(directory)?);
+        }
+        let mut blocks = BTreeMap::new();
+        let shards = tree.write(&mut blocks, MAX_SHARD_SIZE)?;
+        let root = ShardedManifest {
+            format: self.format,
+            shards,
+            schemas: Some(serde_json::to_value(&self.schemas)?),
+            search: self.search,
+            feeds: self.feeds.clone(),
+            previous_root: self.previous_root,
+            version: self.version.clone(),
+        };
+        Ok((root, blocks))
+    }
+}
+
+/// In memory directory tree used to build Shards bottom up
+#[derive(Default)]
+struct DirTree {
+    objects: BTreeMap<String, Value>,
+    dirs: BTreeMap<String, DirTree>,
+    directory: Option<Value>,
+}
+
+impl DirTree {
+    fn insert(&mut self, segments: &[String], object: Value) {
+        match segments {
+            [] => {}
+            [name] => {
+                self.objects.insert(name.clone(), object);
+            }
+            [dir, rest @ ..] => self
+                .dirs
+                .entry(dir.clone())
+                .or_default()
+                .insert(rest, object),
+        }
+    }
+
+    fn insert_directory(&mut self, segments: &[String], directory: Value) {
+        match segments {
+            [] => self.directory = Some(directory),
+            [dir, rest @ ..] => self
+                .dirs
+                .entry(dir.clone())
+                .or_default()
+                .insert_directory(rest, directory),
+        }
+    }
+
+    /// Write the tree's Shards bottom up, splitting any larger than the limit into buckets
+    fn write(
+        self,
+        blocks: &mut BTreeMap<Cid, Vec<u8>>,
+        limit: usize,
+    ) -> Result<Cid, ManifestError> {
+        let mut entries = BTreeMap::new();
+        for (name, dir) in self.dirs {
+            let cid = dir.write(blocks, limit)?;
+            entries.insert(name, ShardEntry::Shard(cid));
+        }
+        for (name, object) in self.objects {
+            entries.insert(name, ShardEntry::Object(object));
+        }
+        write_shard(entries, self.directory, 0, blocks, limit)
+    }
+}
+
+fn write_shard(
+    entries: BTreeMap<String, ShardEntry>,
+    directory: Option<Value>,
+    depth: usize,
+    blocks: &mut BTreeMap<Cid, Vec<u8>>,
+    limit: usize,
+) -> Result<Cid, ManifestError> {
+    let mut shard = Shard {
+        entries,
+        buckets: Vec::new(),
+        directory,
+    };
+    let mut data = shard.to_vec()?;
+    // Once a name's hash runs out, its entries can't be split any further
+    if data.len() > limit && shard.entries.len() > 1 && depth < blake3::OUT_LEN {
+        let mut buckets = vec![BTreeMap::new(); BUCKETS];
+        for (name, entry) in std::mem::take(&mut shard.entries) {
+            buckets[bucket(&name, depth)].insert(name, entry);
+        }
+        shard.buckets = buckets
+            .into_iter()
+            .map(|entries| write_shard(entries, None, depth + 1, blocks, limit))
+            .collect::<Result<_, _>>()?;
+        data = shard.to_vec()?;
+    }
+    let cid = block_cid(&data);
+    blocks.insert(cid, data);
+    Ok(cid)
+}
+
+/// Which bucket an entry falls into, at a given depth of buckets
+fn bucket(name: &str, depth: usize) -> usize {
+    blake3::hash(name.as_bytes()).as_bytes()[depth % blake3::OUT_LEN] as usize % BUCKETS
+}
+
+/// Split a relative path into its named segments
+fn segments(path: &Path) -> Result<Vec<String>, ManifestError> {
+    path.components()
+        .filter(|component| component != &Component::CurDir)
+        .map(|component| match component {
+            Component::Normal(name) => Ok(name.to_string_lossy().to_string()),
+            _ => Err(ManifestError::InvalidPath(path.to_path_buf())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::types::directory::Directory;
+    use crate::types::Object;
+
+    /// A block store backed by the output of `Manifest::shard`
+    struct MemoryStore(BTreeMap<Cid, Vec<u8>>, Cell<usize>);
+
+    #[derive(Debug)]
+    struct MemoryStoreError;
+
+    impl From<ManifestError> for MemoryStoreError {
+        fn from(_: ManifestError) -> Self {
+            Self
+        }
+    }
+
+    impl BlockStore for MemoryStore {
+        type Error = MemoryStoreError;
+
+        async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, Self::Error> {
+            self.1.set(self.1.get() + 1);
+            self.0.get(cid).cloned().ok_or(MemoryStoreError)
+        }
+    }
+
+    fn manifest() -> Manifest {
+        let mut manifest = Manifest::default();
+        for path in [
+            "index.md",
+            "writing/a.md",
+            "writing/b.md",
+            "audio/2023/c.mp3",
+        ] {
+            let object = Object::new(block_cid(path.as_bytes()));
+            manifest.objects.insert(PathBuf::from(path), object);
+        }
+        let writing = Directory::new(Value::String("a series".to_string()));
+        manifest
+            .directories
+            .insert(PathBuf::from("writing"), writing);
+        manifest
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn sharded_manifest_loads_back() {
+        let manifest = manifest();
+        let (root, blocks) = manifest.shard().unwrap();
+        // root, writing, audio, audio/2023
+        assert_eq!(blocks.len(), 4);
+        let store = MemoryStore(blocks, Cell::new(0));
+        assert_eq!(block_on(root.load(&store)).unwrap(), manifest);
+    }
+
+    #[test]
+    fn directories_are_read_lazily() {
+        let manifest = manifest();
+        let (root, blocks) = manifest.shard().unwrap();
+        let store = MemoryStore(blocks, Cell::new(0));
+        let within = block_on(root.load_within(&store, Path::new("writing")))
+            .unwrap()
+            .unwrap();
+        let objects = within.objects().keys().collect::<Vec<_>>();
+        assert_eq!(objects, vec!["writing/a.md", "writing/b.md"]);
+        assert!(within.directory(Path::new("writing")).is_some());
+        // Only the root and writing shards were fetched
+        assert_eq!(store.1.get(), 2);
+        assert!(block_on(root.load_within(&store, Path::new("video")))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn large_directories_are_split_into_buckets() {
+        let mut manifest = manifest();
+        for i in 0..200 {
+            let path = PathBuf::from(format!("audio/2023/{}.mp3", i));
+            let object = Object::new(block_cid(path.to_string_lossy().as_bytes()));
+            manifest.objects.insert(path, object);
+        }
+        let mut tree = DirTree::default();
+        for (path, object) in manifest.objects.iter() {
+            tree.insert(
+                &segments(path).unwrap(),
+                serde_json::to_value(object).unwrap(),
+            );
+        }
+        let limit = 4 * 1024;
+        let mut blocks = BTreeMap::new();
+        let shards = tree.write(&mut blocks, limit).unwrap();
+        assert!(blocks.values().all(|block| block.len() <= limit));
+
+        let (mut root, _) = manifest.shard().unwrap();
+        root.shards = shards;
+        let store = MemoryStore(blocks, Cell::new(0));
+        let within = block_on(root.load_within(&store, Path::new("audio/2023")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(within.objects().len(), 201);
+        let loaded = block_on(root.load(&store)).unwrap();
+        assert_eq!(loaded.objects(), manifest.objects());
+    }
+
+    #[test]
+    fn unchanged_shards_keep_their_cids() {
+        let manifest = manifest();
+        let (_, before) = manifest.shard().unwrap();
+        let mut updated = manifest.clone();
+        updated
+            .objects
+            .insert(PathBuf::from("writing/d.md"), Object::default());
+        let (_, after) = updated.shard().unwrap();
+        // Only the writing shard and the root shard changed
+        let changed = after.keys().filter(|cid| !before.contains_key(cid)).count();
+        assert_eq!(changed, 2);
+    }
+}
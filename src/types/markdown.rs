@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use pulldown_cmark::{html, Options, Parser};
+use regex::Regex;
+
+use super::Object;
+
+/// Render markdown to html, with the extensions writing on the site is allowed to use
+pub fn markdown_to_html(content: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_SMART_PUNCTUATION);
+
+    let parser = Parser::new_ext(content, options);
+    let mut html = String::new();
+    html::push_html(&mut html, parser);
+    html
+}
+
+/// Point `src="./..."` references in a markdown object at wherever the referenced
+/// objects are served from. References to paths that aren't objects are left alone
+/// # Args
+/// - content: the markdown to rewrite
+/// - object_path: the path of the object the markdown was read from
+/// - objects: the objects references may point at
+/// - url: where to link a referenced object, given its path
+pub fn link_relative_sources(
+    content: &str,
+    object_path: &Path,
+    objects: &BTreeMap<PathBuf, Object>,
+    url: impl Fn(&Path, &Object) -> String,
+) -> String {
+    let base_path = object_path.parent().unwrap_or(Path::new(""));
+    let re = Regex::new(r#"src="\./([^"]+)""#).unwrap();
+    re.replace_all(content, |caps: &regex::Captures| {
+        let path = normalize_path(&base_path.join(&caps[1]));
+        match objects.get(&path) {
+            Some(object) => format!(r#"src="{}""#, url(&path, object)),
+            None => caps[0].to_string(),
+        }
+    })
+    .to_string()
+}
+
+/// Resolve `..` components, without touching the filesystem
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized_path = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized_path.pop();
+            }
+            Component::CurDir => {}
+            _ => {
+                normalized_path.push(component);
+            }
+        }
+    }
+    normalized_path
+}
+
+#[cfg(test)]
+mod tests {
+    use cid::Cid;
+
+    use super::*;
+
+    #[test]
+    fn relative_sources_are_linked_to_objects() {
+        let mut objects = BTreeMap::new();
+        objects.insert(PathBuf::from("visual/cat.png"), Object::new(Cid::default()));
+        let content = concat!(
+            r#"<img src="./../visual/cat.png"> "#,
+            r#"<img src="./missing.png"> "#,
+            r#"<img src="https://example.com/dog.png">"#
+        );
+        let linked = link_relative_sources(
+            content,
+            Path::new("writing/hello.md"),
+            &objects,
+            |path, _| format!("/content/{}", path.display()),
+        );
+        assert_eq!(
+            linked,
+            concat!(
+                r#"<img src="/content/visual/cat.png"> "#,
+                r#"<img src="./missing.png"> "#,
+                r#"<img src="https://example.com/dog.png">"#
+            )
+        );
+        assert!(markdown_to_html("~~gone~~").contains("<del>gone</del>"));
+    }
+}
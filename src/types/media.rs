@@ -0,0 +1,363 @@
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+/// Technical metadata about an Object's content, extracted when it's staged.
+/// Unlike tagged metadata, this is never edited by hand
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Media {
+    Image(ImageInfo),
+    Audio(AudioInfo),
+}
+
+/// - width, height: dimensions in pixels
+/// - format: the image format, e.g. `jpeg`
+/// - taken_at: when the image was taken, per its EXIF data
+/// - camera: the make and model of the camera, per its EXIF data
+/// - variants: resized copies of the image, smallest first
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taken_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub camera: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<Variant>,
+}
+
+/// A resized copy of an image, added to Ipfs alongside the original
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub width: u32,
+    pub height: u32,
+    pub cid: Cid,
+}
+
+#[allow(dead_code)]
+impl ImageInfo {
+    /// The smallest variant, suitable for listings
+    pub fn thumbnail(&self) -> Option<&Variant> {
+        self.variants.first()
+    }
+
+    /// The smallest variant at least as wide as a width, if any is. Otherwise only
+    /// the original is wide enough
+    pub fn fitting(&self, width: u32) -> Option<&Variant> {
+        self.variants.iter().find(|variant| variant.width >= width)
+    }
+}
+
+/// The widths images are resized to. Images no wider than one of these aren't resized to it
+#[cfg(not(target_arch = "wasm32"))]
+pub const VARIANT_WIDTHS: [u32; 3] = [320, 640, 1280];
+
+/// A resized copy of an image, encoded and ready to be added to Ipfs
+#[cfg(not(target_arch = "wasm32"))]
+pub struct Resized {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// - duration_ms: the length of the track in milliseconds
+/// - sample_rate: samples per second, e.g. `44100`
+/// - channels: the number of channels
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AudioInfo {
+    pub duration_ms: u64,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+#[allow(dead_code)]
+impl AudioInfo {
+    /// The length of the track in seconds, as media elements measure it
+    pub fn duration_secs(&self) -> f64 {
+        self.duration_ms as f64 / 1000.0
+    }
+
+    /// The length of the track as `m:ss`
+    pub fn display_duration(&self) -> String {
+        let secs = self.duration_ms / 1000;
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// Extract technical metadata from a file, based on its extension
+/// # Returns None if the file isn't an image or audio file we know how to read
+#[cfg(not(target_arch = "wasm32"))]
+pub fn extract(path: &Path) -> Result<Option<Media>, MediaError> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+    match extension.as_deref() {
+        Some("png" | "jpg" | "jpeg" | "gif" | "webp") => Ok(Some(Media::Image(image(path)?))),
+        Some("mp3" | "wav" | "flac" | "ogg") => Ok(Some(Media::Audio(audio(path)?))),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn image(path: &Path) -> Result<ImageInfo, MediaError> {
+    use std::io::Read;
+
+    let size = imagesize::size(path)?;
+    let mut header = [0; 16];
+    let read = std::fs::File::open(path)?.read(&mut header)?;
+    let format = match imagesize::image_type(&header[..read])? {
+        imagesize::ImageType::Png => "png",
+        imagesize::ImageType::Jpeg => "jpeg",
+        imagesize::ImageType::Gif => "gif",
+        imagesize::ImageType::Webp => "webp",
+        _ => "unknown",
+    };
+
+    // Plenty of images don't carry EXIF data, which is fine
+    let (taken_at, camera) = match exif(path) {
+        Ok(exif) => {
+            let field = |tag| {
+                exif.get_field(tag, ::exif::In::PRIMARY)
+                    .map(|field| {
+                        field
+                            .display_value()
+                            .to_string()
+                            .trim_matches('"')
+                            .trim()
+                            .to_string()
+                    })
+                    .filter(|value| !value.is_empty())
+            };
+            let camera = match (field(::exif::Tag::Make), field(::exif::Tag::Model)) {
+                (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+                (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+                (make, model) => make.or(model),
+            };
+            (field(::exif::Tag::DateTimeOriginal), camera)
+        }
+        Err(_) => (None, None),
+    };
+
+    Ok(ImageInfo {
+        width: size.width as u32,
+        height: size.height as u32,
+        format: format.to_string(),
+        taken_at,
+        camera,
+        variants: vec![],
+    })
+}
+
+/// Resize an image to each of `VARIANT_WIDTHS` narrower than it, keeping its aspect ratio.
+/// Jpegs stay jpegs, and everything else is encoded as png to keep its transparency
+#[cfg(not(target_arch = "wasm32"))]
+pub fn resize(path: &Path, info: &ImageInfo) -> Result<Vec<Resized>, MediaError> {
+    use image::imageops::FilterType;
+    use image::ImageOutputFormat;
+
+    let widths = VARIANT_WIDTHS
+        .into_iter()
+        .filter(|width| *width < info.width)
+        .collect::<Vec<_>>();
+    if widths.is_empty() {
+        return Ok(vec![]);
+    }
+    let original = image::open(path)?;
+    let format = match info.format.as_str() {
+        "jpeg" => ImageOutputFormat::Jpeg(80),
+        _ => ImageOutputFormat::Png,
+    };
+
+    let mut resized = Vec::new();
+    for width in widths {
+        let image = original.resize(width, u32::MAX, FilterType::Lanczos3);
+        let mut data = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut data, format.clone())?;
+        resized.push(Resized {
+            width: image.width(),
+            height: image.height(),
+            data: data.into_inner(),
+        });
+    }
+    Ok(resized)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn exif(path: &Path) -> Result<::exif::Exif, ::exif::Error> {
+    let file = std::fs::File::open(path)?;
+    ::exif::Reader::new().read_from_container(&mut std::io::BufReader::new(file))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn audio(path: &Path) -> Result<AudioInfo, MediaError> {
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    let file = std::fs::File::open(path)?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?
+        .format;
+    let track = format.default_track().ok_or(MediaError::NoTrack)?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+    let sample_rate = params.sample_rate.ok_or(MediaError::NoTrack)?;
+    let channels = params.channels.map(|c| c.count() as u16).unwrap_or(1);
+
+    // Not every container records its length up front, e.g. mp3s without a Xing header.
+    // Then we have to count the frames in every packet
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            let mut frames = 0;
+            loop {
+                match format.next_packet() {
+                    Ok(packet) if packet.track_id() == track_id => frames += packet.dur,
+                    Ok(_) => continue,
+                    Err(symphonia::core::errors::Error::IoError(err))
+                        if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        break
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            frames
+        }
+    };
+
+    Ok(AudioInfo {
+        duration_ms: frames * 1000 / sample_rate as u64,
+        sample_rate,
+        channels,
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, thiserror::Error)]
+pub enum MediaError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not read image: {0}")]
+    Image(#[from] imagesize::ImageError),
+    #[error("could not resize image: {0}")]
+    Resize(#[from] image::ImageError),
+    #[error("could not read audio: {0}")]
+    Audio(#[from] symphonia::core::errors::Error),
+    #[error("audio file has no playable track")]
+    NoTrack,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_durations_are_extracted() {
+        // A second and a half of 8kHz 16-bit stereo silence
+        let (sample_rate, channels, frames) = (8000u32, 2u16, 12000u32);
+        let data_len = frames * channels as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+        wav.extend_from_slice(&(channels * 2).to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.resize(wav.len() + data_len as usize, 0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.wav");
+        std::fs::write(&path, wav).unwrap();
+        let media = extract(&path).unwrap();
+
+        let audio = AudioInfo {
+            duration_ms: 1500,
+            sample_rate,
+            channels,
+        };
+        assert_eq!(audio.display_duration(), "0:01");
+        assert_eq!(media, Some(Media::Audio(audio)));
+    }
+
+    #[test]
+    fn image_dimensions_are_extracted() {
+        // A 1x1 transparent gif
+        let gif = [
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c,
+            0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00,
+            0x3b,
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.gif");
+        std::fs::write(&path, gif).unwrap();
+        let media = extract(&path).unwrap();
+
+        assert_eq!(
+            media,
+            Some(Media::Image(ImageInfo {
+                width: 1,
+                height: 1,
+                format: "gif".to_string(),
+                taken_at: None,
+                camera: None,
+                variants: vec![],
+            }))
+        );
+        assert_eq!(extract(Path::new("notes.md")).unwrap(), None);
+    }
+
+    #[test]
+    fn images_are_resized_to_narrower_widths() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.png");
+        image::RgbImage::new(800, 400).save(&path).unwrap();
+        let Some(Media::Image(info)) = extract(&path).unwrap() else {
+            panic!("expected an image");
+        };
+        let resized = resize(&path, &info).unwrap();
+
+        let sizes = resized
+            .iter()
+            .map(|resized| (resized.width, resized.height))
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, vec![(320, 160), (640, 320)]);
+        assert!(image::load_from_memory(&resized[0].data).is_ok());
+
+        let info = ImageInfo {
+            variants: vec![Variant {
+                width: 320,
+                height: 160,
+                cid: Cid::default(),
+            }],
+            ..info
+        };
+        assert_eq!(info.fitting(300), info.variants.first());
+        assert_eq!(info.fitting(320), info.variants.first());
+        assert_eq!(info.fitting(400), None);
+    }
+}
@@ -0,0 +1,142 @@
+use serde_json::Value;
+
+use super::manifest::MANIFEST_FORMAT;
+use super::schema::SchemaDocument;
+
+/// A migration takes a manifest in one format and returns it in the next
+type Migration = fn(Value) -> Result<Value, MigrationError>;
+
+/// The chain of migrations, in order. The migration at index `i`
+/// upgrades a manifest from format `i + 1` to format `i + 2`, so
+/// there should always be exactly `MANIFEST_FORMAT - 1` of these.
+///
+/// Only changes older readers would get wrong need a new format. Fields they can
+/// safely ignore -- derived metadata, media info, variants, content types, and the
+/// search index and feed links -- are added with defaults instead, so older builds
+/// keep reading newer manifests
+const MIGRATIONS: [Migration; (MANIFEST_FORMAT - 1) as usize] =
+    [v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6];
+
+/// Read the format of a serialized manifest
+/// Manifests that predate format tracking are considered format 1
+pub fn format_of(value: &Value) -> Result<u32, MigrationError> {
+    match value.get("format") {
+        None => Ok(1),
+        Some(format) => format
+            .as_u64()
+            .and_then(|f| u32::try_from(f).ok())
+            .filter(|f| *f > 0)
+            .ok_or(MigrationError::InvalidFormat(format.clone())),
+    }
+}
+
+/// Migrate a serialized manifest up to the current format
+/// Manifests that are already current are returned untouched
+pub fn migrate(mut value: Value) -> Result<Value, MigrationError> {
+    if !value.is_object() {
+        return Err(MigrationError::NotAnObject);
+    }
+    let format = format_of(&value)?;
+    if format > MANIFEST_FORMAT {
+        return Err(MigrationError::UnsupportedFormat(format));
+    }
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(format as usize - 1) {
+        let from = from as u32 + 1;
+        tracing::info!("migrating manifest from format {} to {}", from, from + 1);
+        value = migration(value)?;
+    }
+    Ok(value)
+}
+
+/// v1 -> v2: the manifest records its own format
+fn v1_to_v2(mut value: Value) -> Result<Value, MigrationError> {
+    let map = value.as_object_mut().ok_or(MigrationError::NotAnObject)?;
+    map.insert("format".to_string(), Value::from(2));
+    Ok(value)
+}
+
+/// v2 -> v3: directories may carry their own metadata
+fn v2_to_v3(mut value: Value) -> Result<Value, MigrationError> {
+    let map = value.as_object_mut().ok_or(MigrationError::NotAnObject)?;
+    map.entry("directories")
+        .or_insert_with(|| Value::Object(serde_json::Map::new()));
+    map.insert("format".to_string(), Value::from(3));
+    Ok(value)
+}
+
+/// v3 -> v4: the manifest carries the schemas its metadata is validated against.
+/// Older manifests were tagged against what are now the builtin schemas
+fn v3_to_v4(mut value: Value) -> Result<Value, MigrationError> {
+    let map = value.as_object_mut().ok_or(MigrationError::NotAnObject)?;
+    if !map.contains_key("schemas") {
+        let schemas =
+            serde_json::to_value(SchemaDocument::default()).expect("builtin schemas serialize");
+        map.insert("schemas".to_string(), schemas);
+    }
+    map.insert("format".to_string(), Value::from(4));
+    Ok(value)
+}
+
+/// v4 -> v5: objects may be encrypted for a set of recipients, which older readers
+/// would serve as if they were plaintext. Every older object is public
+fn v4_to_v5(mut value: Value) -> Result<Value, MigrationError> {
+    let map = value.as_object_mut().ok_or(MigrationError::NotAnObject)?;
+    map.insert("format".to_string(), Value::from(5));
+    Ok(value)
+}
+
+/// v5 -> v6: objects may be drafts, or scheduled to be published later, which older
+/// readers would show right away. Every older object was published
+fn v5_to_v6(mut value: Value) -> Result<Value, MigrationError> {
+    let map = value.as_object_mut().ok_or(MigrationError::NotAnObject)?;
+    map.insert("format".to_string(), Value::from(6));
+    Ok(value)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MigrationError {
+    #[error("manifest is not a json object")]
+    NotAnObject,
+    #[error("invalid manifest format: {0}")]
+    InvalidFormat(Value),
+    #[error("manifest format {0} is newer than this build supports -- please upgrade")]
+    UnsupportedFormat(u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::types::Manifest;
+
+    #[test]
+    fn v1_manifest_migrates_to_current() {
+        // A v1 manifest is a current one without a format, directories, or schemas
+        let mut v1 = serde_json::to_value(Manifest::default()).unwrap();
+        for key in ["format", "directories", "schemas"] {
+            v1.as_object_mut().unwrap().remove(key);
+        }
+        assert_eq!(format_of(&v1).unwrap(), 1);
+        let migrated = migrate(v1).unwrap();
+        assert_eq!(format_of(&migrated).unwrap(), MANIFEST_FORMAT);
+        let manifest = Manifest::from_value(migrated).unwrap();
+        assert_eq!(manifest, Manifest::default());
+    }
+
+    #[test]
+    fn current_manifest_round_trips() {
+        let manifest = Manifest::default();
+        let data = serde_json::to_vec(&manifest).unwrap();
+        assert_eq!(Manifest::from_slice(&data).unwrap(), manifest);
+    }
+
+    #[test]
+    fn future_format_is_rejected() {
+        let future = json!({ "format": MANIFEST_FORMAT + 1 });
+        assert!(matches!(
+            migrate(future),
+            Err(MigrationError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -1,10 +1,22 @@
+pub mod content_type;
+mod directory;
+pub mod encryption;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod feed;
+pub mod front_matter;
 mod manifest;
+pub mod markdown;
+pub mod media;
+mod migrations;
 mod object;
 pub mod schema;
+pub mod search;
 
-pub use manifest::Manifest;
-pub use object::Object;
-pub use schema::{Audio, Visual, Writing};
+pub use manifest::{BlockStore, Entry, Manifest, ManifestError, ManifestRoot, MANIFEST_FORMAT};
+pub use object::{Derived, Object, Publication};
 
 #[cfg(not(target_arch = "wasm32"))]
-pub use schema::{Schema, SchemaError};
+pub use schema::SchemaError;
+
+#[cfg(target_arch = "wasm32")]
+pub use schema::{Audio, Collection, Visual, Writing};
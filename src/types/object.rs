@@ -3,18 +3,87 @@ use cid::Cid;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use super::encryption::Encryption;
+use super::media::Media;
+
 // TODO: this needs to be more idiomatically IPLD (should utilize links and IPLD types)
 /// A single Object with DorFS metadata
 /// - created_at: the time the file was added to the DorFS
 /// - updated_at: the time the file was last updated
 /// - cid: the cid of the file (this should be an IPLD link)
 /// - metadata: This can be any piece of Json metadata you want
+/// - derived: set if the metadata was derived from the object itself, rather than tagged
+/// - media: technical metadata about images and audio, extracted when staged
+/// - encryption: set if the content at cid is encrypted, describing who can decrypt it
+/// - content_type: the MIME type of the object's content, sniffed when staged
+/// - publication: whether the object is a draft, published, or scheduled to be published
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct Object {
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
     cid: Cid,
     metadata: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    derived: Option<Derived>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    media: Option<Media>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encryption: Option<Encryption>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Publication::is_published")]
+    publication: Publication,
+}
+
+/// Where an Object's metadata was derived from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "from", rename_all = "snake_case")]
+pub enum Derived {
+    /// Parsed from the front matter of a markdown object.
+    /// If `strip` is set, the front matter is hidden when the body is rendered
+    FrontMatter { strip: bool },
+}
+
+/// When an Object should be shown to readers
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Publication {
+    /// Kept out of the published manifest
+    Draft,
+    /// Shown as soon as it's pushed
+    #[default]
+    Published,
+    /// Pushed ahead of time, and shown from `at` on
+    Scheduled { at: DateTime<Utc> },
+}
+
+impl Publication {
+    pub fn is_published(&self) -> bool {
+        matches!(self, Self::Published)
+    }
+
+    pub fn is_draft(&self) -> bool {
+        matches!(self, Self::Draft)
+    }
+
+    /// Whether readers should see the object at the given time
+    pub fn is_published_at(&self, now: &DateTime<Utc>) -> bool {
+        match self {
+            Self::Draft => false,
+            Self::Published => true,
+            Self::Scheduled { at } => at <= now,
+        }
+    }
+}
+
+impl std::fmt::Display for Publication {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Draft => write!(f, "draft"),
+            Self::Published => write!(f, "published"),
+            Self::Scheduled { at } => write!(f, "scheduled for {}", at.to_rfc3339()),
+        }
+    }
 }
 
 impl Object {
@@ -24,6 +93,11 @@ impl Object {
             updated_at: Utc::now(),
             cid,
             metadata: Value::Null,
+            derived: None,
+            media: None,
+            encryption: None,
+            content_type: None,
+            publication: Publication::default(),
         }
     }
 
@@ -51,8 +125,93 @@ impl Object {
         &self.metadata
     }
 
+    #[allow(dead_code)]
+    pub fn derived(&self) -> Option<&Derived> {
+        self.derived.as_ref()
+    }
+
+    pub fn media(&self) -> Option<&Media> {
+        self.media.as_ref()
+    }
+
+    /// Set technical metadata extracted from the object's content
+    pub fn set_media(&mut self, media: Option<Media>) {
+        self.media = media;
+    }
+
+    pub fn encryption(&self) -> Option<&Encryption> {
+        self.encryption.as_ref()
+    }
+
+    /// Set how the content at cid is encrypted, if it is
+    pub fn set_encryption(&mut self, encryption: Option<Encryption>) {
+        self.encryption = encryption;
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Set the MIME type of the object's content
+    pub fn set_content_type(&mut self, content_type: Option<String>) {
+        self.content_type = content_type;
+    }
+
+    pub fn publication(&self) -> &Publication {
+        &self.publication
+    }
+
+    /// Set when the object should be shown to readers
+    pub fn set_publication(&mut self, publication: Publication) {
+        self.publication = publication;
+    }
+
+    /// Whether the metadata was tagged by hand, and so shouldn't be re-derived
+    pub fn is_tagged(&self) -> bool {
+        !self.metadata.is_null() && self.derived.is_none()
+    }
+
+    /// Set metadata tagged by hand
     pub fn set_metdata(&mut self, value: Value) {
         self.metadata = value;
+        self.derived = None;
+        self.updated_at = Utc::now();
+    }
+
+    /// Set metadata derived from the object itself
+    pub fn set_derived_metadata(&mut self, value: Value, derived: Derived) {
+        self.metadata = value;
+        self.derived = Some(derived);
         self.updated_at = Utc::now();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn scheduled_objects_are_published_on_their_date() {
+        let now = Utc::now();
+        let scheduled = Publication::Scheduled {
+            at: now + Duration::days(1),
+        };
+        assert!(!scheduled.is_published_at(&now));
+        assert!(scheduled.is_published_at(&(now + Duration::days(2))));
+        assert!(!Publication::Draft.is_published_at(&now));
+        assert!(Publication::Published.is_published_at(&now));
+
+        // Published objects serialize just as they did before they could be scheduled
+        let mut object = Object::new(Cid::default());
+        assert!(serde_json::to_value(&object)
+            .unwrap()
+            .get("publication")
+            .is_none());
+        object.set_publication(scheduled);
+        let value = serde_json::to_value(&object).unwrap();
+        assert_eq!(value["publication"]["state"], "scheduled");
+        assert_eq!(serde_json::from_value::<Object>(value).unwrap(), object);
+    }
+}
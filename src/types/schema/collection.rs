@@ -0,0 +1,26 @@
+use schema_derive::{Schema, SchemaEnum};
+
+/// How the contents of a collection should be ordered
+#[derive(Clone, Copy, Default, PartialEq, Eq, SchemaEnum)]
+pub enum CollectionOrder {
+    /// Alphabetically by name
+    #[default]
+    Name,
+    /// Most recently created first
+    Newest,
+    /// Least recently created first
+    Oldest,
+}
+
+/// A directory of related objects, like a series of posts or an album
+#[derive(Schema)]
+#[schema(name = "collection", directories)]
+pub struct Collection {
+    /// The title of the collection
+    pub title: String,
+    /// A short description of the collection
+    pub description: String,
+    /// How to order the collection. One of (name, newest, oldest). Defaults to name
+    #[schema(default)]
+    pub order: CollectionOrder,
+}
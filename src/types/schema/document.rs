@@ -0,0 +1,413 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::validation::{closest, pointer, ValidationError};
+use super::SchemaError;
+
+/// The schemas every new space starts out with
+const BUILTIN_SCHEMAS: &str = include_str!("schemas.json");
+
+/// A document describing every schema objects and directories may be tagged with,
+/// keyed by schema name. This lives in the Manifest, so new schemas can be
+/// introduced by editing the document rather than cutting a release.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SchemaDocument(BTreeMap<String, SchemaDefinition>);
+
+impl Default for SchemaDocument {
+    fn default() -> Self {
+        serde_json::from_str(BUILTIN_SCHEMAS).expect("valid builtin schemas")
+    }
+}
+
+#[allow(dead_code)]
+impl SchemaDocument {
+    pub fn get(&self, name: &str) -> Option<&SchemaDefinition> {
+        self.0.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.0.keys()
+    }
+
+    /// Validate a piece of tagged metadata against the schema named by its `type`
+    /// # Returns the normalized metadata
+    pub fn validate(&self, value: &Value) -> Result<Value, SchemaError> {
+        let r#type = value["type"].as_str().ok_or(SchemaError::MissingType)?;
+        let definition = self.get(r#type).ok_or(SchemaError::UnknownSchema(
+            r#type.to_string(),
+            closest(r#type, self.names().map(String::as_str)).map(String::from),
+        ))?;
+        definition.validate(r#type, value.clone())
+    }
+}
+
+/// A single schema
+/// - description: what the schema is for
+/// - extensions: the file extensions objects tagged with this schema may have
+/// - directories: whether the schema tags directories rather than objects
+/// - fields: the fields tagged metadata is made of, in display order
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SchemaDefinition {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default)]
+    directories: bool,
+    fields: Vec<Field>,
+}
+
+#[allow(dead_code)]
+impl SchemaDefinition {
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    pub fn tags_directories(&self) -> bool {
+        self.directories
+    }
+
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Whether an object with the given extension may be tagged with this schema
+    pub fn accepts_extension(&self, extension: &str) -> bool {
+        self.extensions
+            .iter()
+            .any(|valid| valid.eq_ignore_ascii_case(extension))
+    }
+
+    /// Validate metadata against this schema, filling in defaults,
+    /// normalizing enum values, and writing the schema name as its `type`.
+    /// Every problem with the metadata is reported, not just the first
+    pub fn validate(&self, name: &str, value: Value) -> Result<Value, SchemaError> {
+        let mut map = match value {
+            Value::Object(map) => map,
+            value => {
+                let error =
+                    ValidationError::new(String::new(), "an object".to_string(), Some(&value));
+                return Err(SchemaError::Invalid(vec![error]));
+            }
+        };
+        let mut errors = Vec::new();
+        if let Some(r#type) = map.remove("type") {
+            if r#type.as_str() != Some(name) {
+                errors.push(ValidationError::new(
+                    pointer("type"),
+                    format!("{:?}", name),
+                    Some(&r#type),
+                ));
+            }
+        }
+
+        let mut validated = Map::new();
+        for field in self.fields.iter() {
+            match map.remove(&field.name) {
+                Some(value) if !value.is_null() => match field.validate(value) {
+                    Ok(value) => {
+                        validated.insert(field.name.clone(), value);
+                    }
+                    Err(error) => errors.push(error),
+                },
+                _ => match &field.default {
+                    Some(default) => {
+                        validated.insert(field.name.clone(), default.clone());
+                    }
+                    None if field.required => {
+                        errors.push(ValidationError::new(
+                            pointer(&field.name),
+                            field.expected(),
+                            None,
+                        ));
+                    }
+                    None => {}
+                },
+            }
+        }
+        for (key, value) in map.iter() {
+            let fields = self.fields.iter().map(|field| field.name.as_str());
+            errors.push(
+                ValidationError::new(pointer(key), "no such field".to_string(), Some(value))
+                    .with_suggestion(closest(key, fields).map(|field| format!("{:?}", field))),
+            );
+        }
+
+        if !errors.is_empty() {
+            return Err(SchemaError::Invalid(errors));
+        }
+        validated.insert("type".to_string(), Value::String(name.to_string()));
+        Ok(Value::Object(validated))
+    }
+}
+
+/// A single field within a schema
+/// - name: the key the field is stored under
+/// - type: the Json type of the field
+/// - description: a short helpful blurb
+/// - required: whether the field must be set. Fields with a default never need to be
+/// - enum: if set, the only values the field may take
+/// - default: the value used when the field isn't set
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    name: String,
+    #[serde(rename = "type")]
+    r#type: FieldType,
+    #[serde(default)]
+    description: String,
+    #[serde(default = "required_by_default")]
+    required: bool,
+    #[serde(default, rename = "enum", skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default: Option<Value>,
+}
+
+fn required_by_default() -> bool {
+    true
+}
+
+#[allow(dead_code)]
+impl Field {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn r#type(&self) -> FieldType {
+        self.r#type
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn required(&self) -> bool {
+        self.required && self.default.is_none()
+    }
+
+    pub fn values(&self) -> Option<&[Value]> {
+        self.values.as_deref()
+    }
+
+    pub fn default(&self) -> Option<&Value> {
+        self.default.as_ref()
+    }
+
+    /// Describe the values this field accepts
+    pub fn expected(&self) -> String {
+        match &self.values {
+            Some(values) => {
+                let values = values.iter().map(Value::to_string).collect::<Vec<_>>();
+                format!("one of {}", values.join(", "))
+            }
+            None => self.r#type.to_string(),
+        }
+    }
+
+    /// Check a value has the right type, and if this is an enum,
+    /// return the variant it names
+    pub fn validate(&self, value: Value) -> Result<Value, ValidationError> {
+        let error =
+            |value: &Value| ValidationError::new(pointer(&self.name), self.expected(), Some(value));
+        if !self.r#type.matches(&value) {
+            return Err(error(&value));
+        }
+        let values = match &self.values {
+            Some(values) => values,
+            None => return Ok(value),
+        };
+        if let Some(variant) = values.iter().find(|variant| same_variant(variant, &value)) {
+            return Ok(variant.clone());
+        }
+        let suggestion = value.as_str().and_then(|value| {
+            closest(&normalize(value), values.iter().filter_map(Value::as_str))
+                .map(|variant| format!("{:?}", variant))
+        });
+        Err(error(&value).with_suggestion(suggestion))
+    }
+}
+
+/// The Json types a field may have
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+impl FieldType {
+    /// Parse a value of this type from a plain string, like a line of input or a Csv cell
+    pub fn parse(&self, s: &str) -> Option<Value> {
+        match self {
+            Self::String => Some(Value::String(s.to_string())),
+            Self::Integer => s.parse::<i64>().ok().map(Value::from),
+            Self::Number => s.parse::<f64>().ok().map(Value::from),
+            Self::Boolean => match s.to_lowercase().as_str() {
+                "y" | "yes" | "true" => Some(Value::Bool(true)),
+                "n" | "no" | "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+        }
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Boolean => value.is_boolean(),
+        }
+    }
+}
+
+impl Display for FieldType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::String => "a string",
+                Self::Number => "a number",
+                Self::Integer => "an integer",
+                Self::Boolean => "a boolean",
+            }
+        )
+    }
+}
+
+/// String variants are matched loosely, so `mic_test` names `Mic Test`
+fn same_variant(variant: &Value, value: &Value) -> bool {
+    match (variant.as_str(), value.as_str()) {
+        (Some(variant), Some(value)) => normalize(variant) == normalize(value),
+        _ => variant == value,
+    }
+}
+
+fn normalize(s: &str) -> String {
+    s.trim().to_lowercase().replace('_', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::types::schema::{Audio, Collection, Schema, Visual, Writing};
+
+    #[test]
+    fn builtin_schemas_normalize_metadata() {
+        let schemas = SchemaDocument::default();
+        let writing = schemas
+            .validate(&json!({
+                "type": "writing",
+                "title": "Hello World",
+                "description": "A lil hello!",
+                "genre": "blog"
+            }))
+            .unwrap();
+        assert_eq!(writing["genre"], "Blog");
+        let audio = schemas
+            .validate(&json!({ "type": "audio", "title": "Freak", "project": "mic_test" }))
+            .unwrap();
+        assert_eq!(audio["project"], "Mic Test");
+        let collection = schemas
+            .validate(&json!({ "type": "collection", "title": "Poems", "description": "" }))
+            .unwrap();
+        assert_eq!(collection["order"], "name");
+    }
+
+    #[test]
+    fn typed_schemas_agree_with_builtin_schemas() {
+        fn check<S: Schema>(schemas: &SchemaDocument) {
+            let definition = schemas.get(S::NAME).unwrap();
+            let fields = S::fields()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            let defined = definition
+                .fields()
+                .iter()
+                .map(Field::name)
+                .collect::<Vec<_>>();
+            assert_eq!(fields, defined, "{} fields", S::NAME);
+            assert_eq!(
+                S::valid_extensions(),
+                definition.extensions(),
+                "{} extensions",
+                S::NAME
+            );
+            assert_eq!(S::tags_directories(), definition.tags_directories());
+        }
+        let schemas = SchemaDocument::default();
+        check::<Writing>(&schemas);
+        check::<Audio>(&schemas);
+        check::<Visual>(&schemas);
+        check::<Collection>(&schemas);
+    }
+
+    /// Validate metadata, returning the pointers of every error
+    fn errors(schemas: &SchemaDocument, value: Value) -> Vec<ValidationError> {
+        match schemas.validate(&value) {
+            Err(SchemaError::Invalid(errors)) => errors,
+            result => panic!("expected validation errors, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn every_invalid_field_is_reported() {
+        let schemas = SchemaDocument::default();
+        let errors = errors(
+            &schemas,
+            json!({ "type": "writing", "title": 3, "genre": "poetyr", "autor": "me" }),
+        );
+        let pointers = errors.iter().map(|e| e.pointer()).collect::<Vec<_>>();
+        assert_eq!(pointers, ["/title", "/description", "/genre", "/autor"]);
+        assert_eq!(errors[0].expected(), "a string");
+        assert_eq!(errors[0].found(), "3");
+        assert_eq!(errors[1].found(), "nothing");
+        assert_eq!(errors[2].suggestion(), Some("\"Poetry\""));
+        assert_eq!(
+            errors[2].to_string(),
+            "/genre: expected one of \"Poetry\", \"Fiction\", \"Blog\", \"Essay\", \
+             found \"poetyr\" (did you mean \"Poetry\"?)"
+        );
+        assert_eq!(errors[3].suggestion(), None);
+        assert!(matches!(
+            schemas.validate(&json!({ "type": "witing" })),
+            Err(SchemaError::UnknownSchema(_, Some(suggestion))) if suggestion == "writing"
+        ));
+    }
+
+    #[test]
+    fn schemas_can_be_added_without_code() {
+        let schemas: SchemaDocument = serde_json::from_value(json!({
+            "podcast": {
+                "extensions": ["mp3"],
+                "fields": [
+                    { "name": "title", "type": "string" },
+                    { "name": "episode", "type": "integer" },
+                    { "name": "explicit", "type": "boolean", "required": false }
+                ]
+            }
+        }))
+        .unwrap();
+        let podcast = schemas.get("podcast").unwrap();
+        assert!(podcast.accepts_extension("MP3"));
+        assert!(podcast
+            .validate("podcast", json!({ "title": "Pilot", "episode": 1 }))
+            .is_ok());
+        assert!(matches!(
+            podcast.validate("podcast", json!({ "title": "Pilot", "episode": "one" })),
+            Err(SchemaError::Invalid(errors)) if errors[0].expected() == "an integer"
+        ));
+    }
+}
@@ -1,10 +1,16 @@
 use std::convert::TryFrom;
 
 mod audio;
+mod collection;
+mod document;
+mod validation;
 mod visual;
 mod writing;
 
 pub use audio::Audio;
+pub use collection::{Collection, CollectionOrder};
+pub use document::{SchemaDefinition, SchemaDocument};
+pub use validation::ValidationError;
 pub use visual::Visual;
 pub use writing::Writing;
 
@@ -30,14 +36,11 @@ impl TryFrom<Value> for Schemas {
     }
 }
 
-// NOTE: this whole notion of a schema is poorly concieved
-//  For example, how do you migrate schemas if they get updated?
-//  How Do you reduce the amount of boilerplate needed to implement one?
-//  How would you get to the eventual goal of defining these an ANSII or something?
-//  Lots of unanswered question, but I'm just experimenting here, and for now it should be alright :/
-// TODO: These could generally benefit from macros
+// NOTE: what metadata is valid is decided by the SchemaDocument in the Manifest.
+//  These types are just typed views over metadata we know how to render specially,
+//  and should agree with the builtin schemas. Implement this with `#[derive(Schema)]`
 #[allow(dead_code)]
-pub trait Schema: Into<Value> + TryFrom<Value> {
+pub trait Schema: Into<Value> + TryFrom<Value, Error = SchemaError> {
     const NAME: &'static str;
 
     fn name() -> &'static str {
@@ -47,6 +50,11 @@ pub trait Schema: Into<Value> + TryFrom<Value> {
     /// Returns a list of valid extensions for this schema.
     fn valid_extensions() -> Vec<&'static str>;
 
+    /// Whether this schema describes directories rather than single objects
+    fn tags_directories() -> bool {
+        false
+    }
+
     /// Return the relevant fields for this schema.
     /// Each field is a static str,str tuple where the first
     /// describes the field name, and the second a short helpful blurb
@@ -72,8 +80,7 @@ pub trait Schema: Into<Value> + TryFrom<Value> {
         if r#type != Self::NAME {
             return Err(SchemaError::MismatchedType(r#type.to_string()));
         }
-        // TODO: wow really just losing error info here huh
-        Self::try_from(value).map_err(|_| SchemaError::FailedConversion)
+        Self::try_from(value)
     }
 }
 
@@ -85,8 +92,56 @@ pub enum SchemaError {
     MismatchedType(String),
     #[error("missing field in map: {0}")]
     MissingField(String),
-    #[error("failed conversion from value")]
-    FailedConversion,
     #[error("invalid field in map: field -> {0} | value -> {1}")]
     InvalidField(String, String),
+    #[error("unknown schema: {0}{}", suggest(.1))]
+    UnknownSchema(String, Option<String>),
+    #[error("{}", display_errors(.0))]
+    Invalid(Vec<ValidationError>),
+}
+
+fn suggest(suggestion: &Option<String>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(" (did you mean {}?)", suggestion),
+        None => String::new(),
+    }
+}
+
+fn display_errors(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(ValidationError::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn derived_schemas_round_trip() {
+        let value = json!({ "title": "Poems", "description": "Some poems", "order": "Newest" });
+        let collection = Collection::try_from(value).unwrap();
+        assert!(collection.order == CollectionOrder::Newest);
+        assert_eq!(
+            collection.into_schema_value(),
+            json!({
+                "type": "collection",
+                "title": "Poems",
+                "description": "Some poems",
+                "order": "newest"
+            })
+        );
+        // Orders are optional, but must be valid if they're set
+        let value = json!({ "title": "Poems", "description": "" });
+        assert!(Collection::try_from(value).unwrap().order == CollectionOrder::Name);
+        let value = json!({ "title": "Poems", "description": "", "order": "random" });
+        assert!(matches!(
+            Collection::try_from(value),
+            Err(SchemaError::InvalidField(field, _)) if field == "order"
+        ));
+    }
 }
@@ -0,0 +1,127 @@
+use std::fmt::Display;
+
+use serde_json::Value;
+
+/// A single problem found while validating metadata against a schema
+/// - pointer: a Json pointer to the offending value, e.g. `/genre`
+/// - expected: what the schema expected to find there
+/// - found: what was actually there
+/// - suggestion: a close match for what was found, if there is one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pointer: String,
+    expected: String,
+    found: String,
+    suggestion: Option<String>,
+}
+
+#[allow(dead_code)]
+impl ValidationError {
+    pub fn new(pointer: String, expected: String, found: Option<&Value>) -> Self {
+        Self {
+            pointer,
+            expected,
+            found: match found {
+                Some(value) => value.to_string(),
+                None => "nothing".to_string(),
+            },
+            suggestion: None,
+        }
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    pub fn expected(&self) -> &str {
+        &self.expected
+    }
+
+    pub fn found(&self) -> &str {
+        &self.found
+    }
+
+    pub fn suggestion(&self) -> Option<&str> {
+        self.suggestion.as_deref()
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The empty pointer refers to the whole document
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(
+            f,
+            "{}: expected {}, found {}",
+            pointer, self.expected, self.found
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean {}?)", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Build a Json pointer to a key within an object, escaping it per RFC 6901
+pub fn pointer(key: &str) -> String {
+    format!("/{}", key.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Find the candidate closest to a value, if any are close enough to be a likely typo
+pub fn closest<'a>(value: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let value = value.to_lowercase();
+    candidates
+        .into_iter()
+        .map(|candidate| (candidate, distance(&value, &candidate.to_lowercase())))
+        .filter(|(candidate, distance)| *distance <= (candidate.chars().count() / 3).max(1))
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+}
+
+/// Edit distance between two strings, counting swapped adjacent characters as one edit
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_matches_are_suggested() {
+        let genres = ["Poetry", "Fiction", "Blog", "Essay"];
+        assert_eq!(closest("poetyr", genres), Some("Poetry"));
+        assert_eq!(closest("blgo", genres), Some("Blog"));
+        assert_eq!(closest("limerick", genres), None);
+        assert_eq!(pointer("a/b~c"), "/a~1b~0c");
+    }
+}
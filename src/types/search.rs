@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use cid::Cid;
+use serde::{Deserialize, Serialize};
+
+/// BM25 term frequency saturation
+const K1: f32 = 1.2;
+/// BM25 document length normalization
+const B: f32 = 0.75;
+/// Titles and descriptions count for more than bodies
+const TITLE_WEIGHT: u32 = 3;
+const DESCRIPTION_WEIGHT: u32 = 2;
+/// Terms that only share a prefix with the last word of a query count for less,
+/// so results show up while someone is still typing
+const PREFIX_WEIGHT: f32 = 0.5;
+/// Words too common to be worth indexing
+const STOP_WORDS: [&str; 24] = [
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "of", "on", "or", "so", "that", "the", "to", "was", "with",
+];
+
+/// The (document, weighted frequency) pairs a term appears in
+type Postings = Vec<(u32, u32)>;
+
+/// A static inverted index over our content, published alongside the Manifest
+/// so it can be searched without a server
+/// - documents: the objects that were indexed
+/// - terms: each term, and the (document, weighted frequency) pairs it appears in
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SearchIndex {
+    documents: Vec<IndexedDocument>,
+    terms: BTreeMap<String, Postings>,
+}
+
+/// An object within the SearchIndex
+/// - path: where the object lives in the Manifest
+/// - cid: the object's cid, for linking to it
+/// - title: the title to list it under
+/// - length: the weighted number of terms indexed for it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IndexedDocument {
+    pub path: PathBuf,
+    pub cid: Cid,
+    pub title: String,
+    length: u32,
+}
+
+/// The text of an object to add to the index
+pub struct Document<'a> {
+    pub path: PathBuf,
+    pub cid: Cid,
+    pub title: &'a str,
+    pub description: Option<&'a str>,
+    pub body: Option<&'a str>,
+}
+
+impl SearchIndex {
+    #[allow(dead_code)]
+    pub fn from_slice(data: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(data)
+    }
+
+    pub fn to_vec(&self) -> Result<Vec<u8>, serde_json::Error> {
+        serde_json::to_vec(self)
+    }
+
+    #[allow(dead_code)]
+    pub fn documents(&self) -> &[IndexedDocument] {
+        &self.documents
+    }
+
+    /// Add a document to the index
+    pub fn insert(&mut self, document: Document<'_>) {
+        let id = self.documents.len() as u32;
+        let mut frequencies = BTreeMap::<String, u32>::new();
+        let fields = [
+            (Some(document.title), TITLE_WEIGHT),
+            (document.description, DESCRIPTION_WEIGHT),
+            (document.body, 1),
+        ];
+        for (text, weight) in fields {
+            for term in tokenize(text.unwrap_or_default()) {
+                *frequencies.entry(term).or_default() += weight;
+            }
+        }
+        let length = frequencies.values().sum();
+        for (term, frequency) in frequencies {
+            self.terms.entry(term).or_default().push((id, frequency));
+        }
+        self.documents.push(IndexedDocument {
+            path: document.path,
+            cid: document.cid,
+            title: document.title.to_string(),
+            length,
+        });
+    }
+
+    /// Rank every document matching any term in the query, best match first
+    #[allow(dead_code)]
+    pub fn search(&self, query: &str) -> Vec<(&IndexedDocument, f32)> {
+        // Repeated terms only count once. The last term keeps its place, since it's
+        //  matched as a prefix
+        let mut query_terms = tokenize(query);
+        let last_term = query_terms.pop();
+        let mut seen = BTreeSet::new();
+        query_terms.retain(|term| Some(term) != last_term.as_ref() && seen.insert(term.clone()));
+        query_terms.extend(last_term);
+        if query_terms.is_empty() || self.documents.is_empty() {
+            return vec![];
+        }
+
+        let count = self.documents.len() as f32;
+        let average_length = self
+            .documents
+            .iter()
+            .map(|document| document.length as f32)
+            .sum::<f32>()
+            / count;
+        let mut scores = vec![0f32; self.documents.len()];
+        let last = query_terms.len() - 1;
+        for (i, query_term) in query_terms.iter().enumerate() {
+            let matches: Box<dyn Iterator<Item = (&String, &Postings)>> = if i == last {
+                Box::new(
+                    self.terms
+                        .range(query_term.clone()..)
+                        .take_while(|(term, _)| term.starts_with(query_term.as_str())),
+                )
+            } else {
+                Box::new(self.terms.get_key_value(query_term).into_iter())
+            };
+            for (term, postings) in matches {
+                let weight = if term == query_term {
+                    1.0
+                } else {
+                    PREFIX_WEIGHT
+                };
+                let n = postings.len() as f32;
+                let idf = ((count - n + 0.5) / (n + 0.5) + 1.0).ln();
+                for (id, frequency) in postings {
+                    let length = self.documents[*id as usize].length as f32;
+                    let frequency = *frequency as f32;
+                    let norm = K1 * (1.0 - B + B * length / average_length);
+                    scores[*id as usize] +=
+                        weight * idf * frequency * (K1 + 1.0) / (frequency + norm);
+                }
+            }
+        }
+
+        let mut results = self
+            .documents
+            .iter()
+            .zip(scores)
+            .filter(|(_, score)| *score > 0.0)
+            .collect::<Vec<_>>();
+        results.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.total_cmp(a_score).then_with(|| a.path.cmp(&b.path))
+        });
+        results
+    }
+}
+
+/// Split text into lowercase terms, dropping stop words and single characters
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(|word| word.to_lowercase())
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::default();
+        let documents = [
+            (
+                "writing/hello.md",
+                "Hello World",
+                "A lil hello!",
+                "Welcome to the site.",
+            ),
+            (
+                "writing/turtles.md",
+                "On Turtles",
+                "Some thoughts",
+                "Turtles are great. I like turtles.",
+            ),
+            ("visual/turtles.jpg", "Petting the Turtles", "New York", ""),
+        ];
+        for (path, title, description, body) in documents {
+            index.insert(Document {
+                path: PathBuf::from(path),
+                cid: Cid::default(),
+                title,
+                description: Some(description),
+                body: Some(body),
+            });
+        }
+        index
+    }
+
+    fn paths(results: Vec<(&IndexedDocument, f32)>) -> Vec<String> {
+        results
+            .into_iter()
+            .map(|(document, _)| document.path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn results_are_ranked_by_relevance() {
+        let index = index();
+        assert_eq!(
+            paths(index.search("Turtles")),
+            vec!["writing/turtles.md", "visual/turtles.jpg"]
+        );
+        assert_eq!(paths(index.search("hello")), vec!["writing/hello.md"]);
+        // Stop words and unknown terms match nothing
+        assert!(index.search("the").is_empty());
+        assert!(index.search("giraffes").is_empty());
+    }
+
+    #[test]
+    fn repeated_query_terms_only_count_once() {
+        let index = index();
+        let scores = |query| {
+            index
+                .search(query)
+                .into_iter()
+                .map(|(document, score)| (document.path.clone(), score))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scores("turtles hello turtles"), scores("hello turtles"));
+        assert_eq!(
+            scores("turtles hello turtles hello"),
+            scores("turtles hello")
+        );
+    }
+
+    #[test]
+    fn the_last_word_of_a_query_matches_prefixes() {
+        let index = index();
+        assert_eq!(paths(index.search("new yo")), vec!["visual/turtles.jpg"]);
+        assert_eq!(paths(index.search("welc")), vec!["writing/hello.md"]);
+        // But earlier words must match exactly
+        assert_eq!(
+            paths(index.search("welc turtles")),
+            vec!["writing/turtles.md", "visual/turtles.jpg"]
+        );
+
+        let data = index.to_vec().unwrap();
+        assert_eq!(SearchIndex::from_slice(&data).unwrap(), index);
+    }
+}
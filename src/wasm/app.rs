@@ -1,3 +1,4 @@
+use super::config::AppConfig;
 use super::pages::InternalRouter;
 
 pub struct App;
@@ -5,6 +6,10 @@ pub struct App;
 impl App {
     pub fn run() {
         console_error_panic_hook::set_once();
-        leptos::mount_to_body(InternalRouter);
+        // Pages read the configuration as they render, so it has to be loaded first
+        leptos::spawn_local(async {
+            AppConfig::load().await;
+            leptos::mount_to_body(InternalRouter);
+        });
     }
 }
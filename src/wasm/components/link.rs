@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use cid::Cid;
 use leptos::*;
 use leptos_use::use_event_listener;
@@ -35,8 +37,11 @@ pub fn InternalLink(query: String, msg: String) -> impl IntoView {
 
 impl IntoView for ObjectLink {
     fn into_view(self) -> View {
+        // The path lets the object's page read just its part of a sharded manifest
+        let path = self.path.to_string_lossy().to_string();
+        let path: String = url::form_urlencoded::byte_serialize(path.as_bytes()).collect();
         view! {
-            <InternalLink query=format!("?route=object&query={}", self.cid.to_string()) msg=self.title/>
+            <InternalLink query=format!("?route=object&query={}&path={}", self.cid.to_string(), path) msg=self.title/>
         }
     }
 }
@@ -44,5 +49,6 @@ impl IntoView for ObjectLink {
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct ObjectLink {
     pub cid: Cid,
+    pub path: PathBuf,
     pub title: String,
 }
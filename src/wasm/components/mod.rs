@@ -2,8 +2,10 @@ mod error_message_box;
 mod link;
 mod socials;
 mod static_md;
+mod thumbnail;
 
 pub use error_message_box::ErrorMessageBox;
 pub use link::{InternalLink, ObjectLink};
 pub use socials::Socials;
 pub use static_md::StaticMd;
+pub use thumbnail::Thumbnail;
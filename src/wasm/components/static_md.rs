@@ -15,7 +15,7 @@ pub fn StaticMd(name: &'static str) -> impl IntoView {
                 .text()
                 .await
                 .expect("text");
-            let content = markdown_to_html(content);
+            let content = markdown_to_html(&content);
             content
         },
     );
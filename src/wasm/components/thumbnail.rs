@@ -0,0 +1,34 @@
+use cid::Cid;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::wasm::utils::verified_url;
+
+/// A small preview of an image, for listings. It's read and verified like any other
+/// content, then shown from an object url.
+/// Renders nothing if the image has no thumbnail
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Thumbnail {
+    pub cid: Option<Cid>,
+    pub alt: String,
+}
+
+impl IntoView for Thumbnail {
+    fn into_view(self) -> View {
+        let Some(cid) = self.cid else {
+            return view! { <span></span> }.into_view();
+        };
+        let alt = self.alt;
+        // Thumbnails may have been re-encoded, so the browser is left to work out their type
+        let src = create_local_resource(
+            move || cid,
+            |cid| async move { verified_url(&cid, None).await.ok() },
+        );
+        (move || {
+            src.get().flatten().map(|src| {
+                view! { <img src=src alt=alt.clone() class="h-16 w-auto"/> }
+            })
+        })
+        .into_view()
+    }
+}
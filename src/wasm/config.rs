@@ -0,0 +1,175 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::env::{APP_CHAIN_ID, APP_CONTRACT_ADDRESS, APP_IPFS_GATEWAY_URL, APP_NAME, APP_RPC_URL};
+use super::utils::base_url;
+
+/// The file the configuration is read from at runtime, served next to index.html
+const CONFIG_FILE: &str = "config.json";
+
+static APP_CONFIG: OnceLock<AppConfig> = OnceLock::new();
+
+/// Everything the web app needs to find a site
+/// - name: what to call the site
+/// - contract_address: the RootCid contract pointing at the site's manifest
+/// - chain_id: the chain the contract is deployed on
+/// - rpc_url: an RPC for that chain
+/// - ipfs_gateway_url: the gateway to read the site's content from
+/// - fallback_ipfs_gateway_urls: gateways to fall back on, in order, when the first one fails
+/// - allow_query_overrides: whether url parameters may override any of the above
+/// - overridden: whether url parameters did override any of the above, for this page
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AppConfig {
+    pub name: String,
+    pub contract_address: String,
+    pub chain_id: u32,
+    pub rpc_url: String,
+    pub ipfs_gateway_url: String,
+    #[serde(default)]
+    pub fallback_ipfs_gateway_urls: Vec<String>,
+    #[serde(default)]
+    pub allow_query_overrides: bool,
+    #[serde(skip)]
+    pub overridden: bool,
+}
+
+/// Any part of the configuration, to lay over the rest
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct ConfigOverrides {
+    name: Option<String>,
+    contract_address: Option<String>,
+    chain_id: Option<u32>,
+    rpc_url: Option<String>,
+    ipfs_gateway_url: Option<String>,
+    fallback_ipfs_gateway_urls: Option<Vec<String>>,
+    /// Only ever read from the config file. Urls can't let themselves in
+    allow_query_overrides: Option<bool>,
+}
+
+impl ConfigOverrides {
+    /// Read overrides from url parameters named after the fields of the configuration,
+    /// e.g. `?contract_address=0x...&chain_id=1`. Lists are separated by commas.
+    /// Anything else, including whether overrides are allowed, is left alone
+    fn from_query(query: &str) -> Self {
+        let mut overrides = Self::default();
+        let query = query.strip_prefix('?').unwrap_or(query);
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            let value = value.to_string();
+            match key.as_ref() {
+                "name" => overrides.name = Some(value),
+                "contract_address" => overrides.contract_address = Some(value),
+                "chain_id" => overrides.chain_id = u32::from_str(&value).ok(),
+                "rpc_url" => overrides.rpc_url = Some(value),
+                "ipfs_gateway_url" => overrides.ipfs_gateway_url = Some(value),
+                "fallback_ipfs_gateway_urls" => {
+                    overrides.fallback_ipfs_gateway_urls =
+                        Some(value.split(',').map(String::from).collect())
+                }
+                _ => {}
+            }
+        }
+        overrides
+    }
+
+    fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.contract_address.is_none()
+            && self.chain_id.is_none()
+            && self.rpc_url.is_none()
+            && self.ipfs_gateway_url.is_none()
+            && self.fallback_ipfs_gateway_urls.is_none()
+    }
+}
+
+impl AppConfig {
+    /// The configuration compiled into the app by build.rs
+    fn compiled() -> Self {
+        Self {
+            name: APP_NAME.to_string(),
+            contract_address: APP_CONTRACT_ADDRESS.to_string(),
+            chain_id: u32::from_str(APP_CHAIN_ID).unwrap_or_default(),
+            rpc_url: APP_RPC_URL.to_string(),
+            ipfs_gateway_url: APP_IPFS_GATEWAY_URL.to_string(),
+            fallback_ipfs_gateway_urls: Vec::new(),
+            allow_query_overrides: false,
+            overridden: false,
+        }
+    }
+
+    fn apply(&mut self, overrides: ConfigOverrides) {
+        if let Some(name) = overrides.name {
+            self.name = name;
+        }
+        if let Some(contract_address) = overrides.contract_address {
+            self.contract_address = contract_address;
+        }
+        if let Some(chain_id) = overrides.chain_id {
+            self.chain_id = chain_id;
+        }
+        if let Some(rpc_url) = overrides.rpc_url {
+            self.rpc_url = rpc_url;
+        }
+        if let Some(ipfs_gateway_url) = overrides.ipfs_gateway_url {
+            self.ipfs_gateway_url = ipfs_gateway_url;
+        }
+        if let Some(fallback_ipfs_gateway_urls) = overrides.fallback_ipfs_gateway_urls {
+            self.fallback_ipfs_gateway_urls = fallback_ipfs_gateway_urls;
+        }
+        if let Some(allow_query_overrides) = overrides.allow_query_overrides {
+            self.allow_query_overrides = allow_query_overrides;
+        }
+    }
+
+    /// Load the configuration for this page. The compiled defaults are overridden by
+    /// the config file served next to index.html, if there is one, and then by any
+    /// overrides passed in the url. This should be called once, before the app is mounted
+    pub async fn load() -> &'static AppConfig {
+        let mut config = Self::compiled();
+        if let Some(overrides) = fetch_config_file().await {
+            config.apply(overrides);
+        }
+        // Anyone can link to the app with parameters pointing it at their own contract or
+        //  gateway, so they're only honored where the site allows it, or in development
+        if config.allow_query_overrides || cfg!(debug_assertions) {
+            let overrides = query_overrides();
+            config.overridden = !overrides.is_empty();
+            config.apply(overrides);
+        }
+        APP_CONFIG.get_or_init(|| config)
+    }
+}
+
+/// The configuration in use. Falls back to the compiled defaults if it hasn't been loaded
+pub fn app_config() -> &'static AppConfig {
+    APP_CONFIG.get_or_init(AppConfig::compiled)
+}
+
+/// Read the config file served next to index.html, wherever that is. Sites without one
+/// use the defaults
+async fn fetch_config_file() -> Option<ConfigOverrides> {
+    let url = base_url()?.join(CONFIG_FILE).ok()?;
+    let response = reqwest::get(url).await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let text = response.text().await.ok()?;
+    match serde_json::from_str::<ConfigOverrides>(&text) {
+        Ok(overrides) => Some(overrides),
+        Err(e) => {
+            leptos::logging::warn!("ignoring invalid {}: {}", CONFIG_FILE, e);
+            None
+        }
+    }
+}
+
+/// Overrides passed in this page's url. They aren't kept, so they only last until the
+/// next page is loaded
+fn query_overrides() -> ConfigOverrides {
+    let query = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .unwrap_or_default();
+    ConfigOverrides::from_query(&query)
+}
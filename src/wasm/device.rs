@@ -1,13 +1,15 @@
+use std::path::Path;
 use std::str::FromStr;
 
 use cid::Cid;
 use ethers::types::Address;
 
 pub use crate::eth::{EthClient, EthClientError, EthRemote, RootCid, RootCidError};
-use crate::types::Manifest;
+use crate::types::search::SearchIndex;
+use crate::types::{BlockStore, Manifest, ManifestError, ManifestRoot};
 
-use crate::wasm::env::{APP_CHAIN_ID, APP_CONTRACT_ADDRESS, APP_RPC_URL};
-use crate::wasm::utils::gateway_url;
+use crate::ipfs::{IpfsError, IpfsGateway};
+use crate::wasm::config::AppConfig;
 
 /// One stop shop for reading Store data from IPFS and Ethereum
 pub struct WasmDevice {
@@ -16,36 +18,75 @@ pub struct WasmDevice {
     /// EthClient for reading and updating a root cid. The contract address should be
     /// callable from this client
     eth: EthClient,
+    /// IpfsGateways to read content from, in order
+    ipfs_gateway: IpfsGateway,
 }
 
 /// One stop shop for coordinating interactions with a given remote configuration
 impl WasmDevice {
-    pub fn new() -> Result<Self, WasmDeviceError> {
-        let contract_address = Address::from_str(APP_CONTRACT_ADDRESS).map_err(|_e| {
-            WasmDeviceError::InvalidContractAddress(APP_CONTRACT_ADDRESS.to_string())
+    pub fn new(config: &AppConfig) -> Result<Self, WasmDeviceError> {
+        let contract_address = Address::from_str(&config.contract_address).map_err(|_e| {
+            WasmDeviceError::InvalidContractAddress(config.contract_address.clone())
         })?;
-        let chain_id = u32::from_str(APP_CHAIN_ID)?;
         let eth_remote = EthRemote {
-            rpc_url: APP_RPC_URL.parse()?,
-            chain_id,
+            rpc_url: config.rpc_url.parse()?,
+            chain_id: config.chain_id,
         };
         let eth = EthClient::try_from(eth_remote)?;
+        let fallbacks = config
+            .fallback_ipfs_gateway_urls
+            .iter()
+            .map(|url| url.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+        let ipfs_gateway =
+            IpfsGateway::new(config.ipfs_gateway_url.parse()?).with_fallbacks(fallbacks);
         Ok(Self {
             contract_address,
             eth,
+            ipfs_gateway,
         })
     }
 
     /* Dor Store Helpers */
 
     /// Read a Block by its Cid as a Manifest from Ipfs
+    /// Manifests in older formats are migrated to the current format, and
+    /// sharded manifests have all their shards loaded
     /// # Args
     /// - cid: The cid of the Manifest object
-    /// - remote: whether to read against the remote of local IPFS client
     pub async fn read_manifest(&self, cid: &Cid) -> Result<Manifest, WasmDeviceError> {
         let manifest_data = self.read_ipfs_gateway_data(cid).await?;
-        let manifest = serde_json::from_slice(&manifest_data)?;
-        Ok(manifest)
+        match ManifestRoot::from_slice(&manifest_data)? {
+            ManifestRoot::Flat(manifest) => Ok(manifest),
+            ManifestRoot::Sharded(sharded) => sharded.load(self).await,
+        }
+    }
+
+    /// Read only the part of a Manifest within a directory. For sharded manifests, only
+    /// the shards along the way to the directory and under it are fetched. Flat manifests
+    /// are a single block, so they're read whole
+    /// # Args
+    /// - cid: The cid of the Manifest object
+    /// - dir: The directory to read. An empty path is the root directory
+    /// # Returns None if there's no such directory
+    pub async fn read_manifest_within(
+        &self,
+        cid: &Cid,
+        dir: &Path,
+    ) -> Result<Option<Manifest>, WasmDeviceError> {
+        let manifest_data = self.read_ipfs_gateway_data(cid).await?;
+        match ManifestRoot::from_slice(&manifest_data)? {
+            ManifestRoot::Flat(manifest) => Ok(Some(manifest)),
+            ManifestRoot::Sharded(sharded) => sharded.load_within(self, dir).await,
+        }
+    }
+
+    /// Read the SearchIndex published alongside a Manifest
+    /// # Args
+    /// - cid: The cid of the SearchIndex file
+    pub async fn read_search_index(&self, cid: &Cid) -> Result<SearchIndex, WasmDeviceError> {
+        let index_data = self.read_ipfs_gateway_data(cid).await?;
+        Ok(SearchIndex::from_slice(&index_data)?)
     }
 
     /* Eth Helpers */
@@ -64,15 +105,21 @@ impl WasmDevice {
 
     /* Ipfs Helpers */
 
-    /// Read a Cid from the configured Ipfs Gateway
+    /// Read a Cid from the configured Ipfs Gateways, verifying what they serve against it.
+    /// Gateways are tried in order until one serves the content intact
     /// # Args
     /// - cid: the cid to read
-    /// - path: Optional path parameter if the Cid points to a unix-fs directory
     pub async fn read_ipfs_gateway_data(&self, cid: &Cid) -> Result<Vec<u8>, WasmDeviceError> {
-        let url = gateway_url(cid);
-        let resp = reqwest::get(url).await?;
-        let bytes = resp.bytes().await?;
-        Ok(bytes.to_vec())
+        Ok(self.ipfs_gateway.get(cid).await?)
+    }
+}
+
+/// Manifest shards are read straight from the gateway
+impl BlockStore for WasmDevice {
+    type Error = WasmDeviceError;
+
+    async fn get_block(&self, cid: &Cid) -> Result<Vec<u8>, WasmDeviceError> {
+        self.read_ipfs_gateway_data(cid).await
     }
 }
 
@@ -86,12 +133,16 @@ pub enum WasmDeviceError {
     RootCid(#[from] RootCidError),
     #[error("serde error: {0}")]
     Serde(#[from] serde_json::Error),
+    #[error("manifest error: {0}")]
+    Manifest(#[from] ManifestError),
     #[error("int error: {0}")]
     Int(#[from] std::num::ParseIntError),
     #[error("reqwest error: {0}")]
     Reqwest(#[from] reqwest::Error),
     #[error("url error: {0}")]
     Url(#[from] url::ParseError),
+    #[error("ipfs error: {0}")]
+    Ipfs(#[from] IpfsError),
     #[error("invalid contract address: {0}")]
     InvalidContractAddress(String),
 }
@@ -6,5 +6,5 @@ pub const APP_VERSION: &str = "2.0.0";
 pub const APP_NAME: &str = "Krondor.Org";
 pub const APP_CONTRACT_ADDRESS: &str = "0x8740bb2ded8C1aaABbBcbB6f7087B9cEE944B31D";
 pub const APP_CHAIN_ID: &str = "11155111";
-pub const APP_RPC_URL: &str = "https://sepolia.infura.io/v3/c2a72953873d451f81839acff0f498cd";
+pub const APP_RPC_URL: &str = "https://ethereum-sepolia-rpc.publicnode.com";
 pub const APP_IPFS_GATEWAY_URL: &str = "https://ipfs.krondor.org";
@@ -1,5 +1,6 @@
 mod app;
 mod components;
+mod config;
 mod device;
 mod env;
 mod pages;
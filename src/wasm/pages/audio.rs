@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use async_trait::async_trait;
 use chrono::naive::NaiveDate;
 use cid::Cid;
@@ -5,6 +7,7 @@ use leptos::*;
 use leptos_struct_table::*;
 use serde::{Deserialize, Serialize};
 
+use crate::types::media::Media;
 use crate::types::{Audio, Object};
 use crate::wasm::components::ObjectLink;
 
@@ -40,7 +43,7 @@ impl IntoView for AudioPage {
                         Err(_) => false,
                     }
                 })
-                .map(|(_path, object)| object.into())
+                .map(|item| item.into())
                 .collect::<Vec<AudioRow>>();
             audio.sort_by(|a, b| b.date.cmp(&a.date));
             audio
@@ -71,11 +74,14 @@ pub struct AudioRow {
     #[table(head_class = "p-2", cell_class = "p-2")]
     project: String,
     #[table(head_class = "p-2", cell_class = "p-2")]
+    duration: String,
+    #[table(head_class = "p-2", cell_class = "p-2")]
     date: NaiveDate,
 }
 
-impl From<&Object> for AudioRow {
-    fn from(object: &Object) -> Self {
+impl From<(&PathBuf, &Object)> for AudioRow {
+    fn from(item: (&PathBuf, &Object)) -> Self {
+        let (path, object) = item;
         let id = object.cid().clone();
         let metadata = object.metadata();
         // Note: it's gaurnateed that this will succeed at this point
@@ -84,9 +90,14 @@ impl From<&Object> for AudioRow {
             id,
             title: ObjectLink {
                 cid: object.cid().clone(),
+                path: path.clone(),
                 title: audio.title,
             },
             project: audio.project.to_string(),
+            duration: match object.media() {
+                Some(Media::Audio(info)) => info.display_duration(),
+                _ => String::new(),
+            },
             date: object.created_at().date_naive(),
         }
     }
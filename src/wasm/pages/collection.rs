@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Collection, Entry, Object};
+use crate::wasm::components::{InternalLink, ObjectLink};
+
+use super::{Page, PageContext};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CollectionPage(PageContext);
+
+impl Page for CollectionPage {
+    fn ctx(&self) -> &PageContext {
+        &self.0
+    }
+    fn from_ctx(ctx: PageContext) -> Box<dyn Page> {
+        Box::new(Self(ctx))
+    }
+    fn into_view_ref(&self) -> View {
+        self.clone().into_view()
+    }
+}
+
+impl IntoView for CollectionPage {
+    fn into_view(self) -> View {
+        // No query lists the root of the manifest
+        let dir = PathBuf::from(self.ctx().query().clone().unwrap_or_default());
+        let manifest = self.ctx().manifest();
+        if !dir.as_os_str().is_empty() && !manifest.is_directory(&dir) {
+            return view! {
+                <div>
+                    <p>
+                        "Oh no! There's nothing in this collection!"
+                    </p>
+                </div>
+            }
+            .into_view();
+        }
+
+        let collection = manifest
+            .directory(&dir)
+            .and_then(|directory| Collection::try_from(directory.metadata().clone()).ok());
+        let (title, description) = match collection {
+            Some(collection) => (collection.title, collection.description),
+            None if dir.as_os_str().is_empty() => ("Everything".to_string(), String::new()),
+            None => (dir.display().to_string(), String::new()),
+        };
+        let entries = manifest.ls(&dir).into_iter().map(entry_view).collect_view();
+
+        view! {
+            <div>
+                <h1 class="text-3xl font-bold italic bg-gray-800 p-2">
+                    {title}
+                    <div class="text-sm font-normal text-gray-200">
+                        <p>{description}</p>
+                    </div>
+                </h1>
+                <ul class="p-2">
+                    {entries}
+                </ul>
+            </div>
+        }
+        .into_view()
+    }
+}
+
+fn entry_view(entry: Entry<'_>) -> View {
+    let name = entry
+        .path()
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match entry {
+        Entry::Object { path, object } => {
+            let link = ObjectLink {
+                cid: *object.cid(),
+                path: path.clone(),
+                title: object_title(object).unwrap_or(name),
+            };
+            view! { <li>{link}</li> }.into_view()
+        }
+        Entry::Directory {
+            path, directory, ..
+        } => {
+            let title = directory
+                .and_then(|directory| Collection::try_from(directory.metadata().clone()).ok())
+                .map(|collection| collection.title)
+                .unwrap_or(format!("{}/", name));
+            let path = path.to_string_lossy().to_string();
+            let query: String = url::form_urlencoded::byte_serialize(path.as_bytes()).collect();
+            view! {
+                <li>
+                    <InternalLink query=format!("?route=collection&query={}", query) msg=title/>
+                </li>
+            }
+            .into_view()
+        }
+    }
+}
+
+/// Read the title of an object, whichever schema it was tagged with
+fn object_title(object: &Object) -> Option<String> {
+    object.metadata()["title"].as_str().map(String::from)
+}
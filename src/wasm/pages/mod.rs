@@ -1,3 +1,6 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
 use cid::Cid;
 use leptos::*;
 use leptos_router::Router;
@@ -6,24 +9,28 @@ use serde::{Deserialize, Serialize};
 
 use crate::types::Manifest;
 use crate::wasm::components::{ErrorMessageBox, InternalLink};
+use crate::wasm::config::app_config;
 use crate::wasm::device::{WasmDevice, WasmDeviceError};
-use crate::wasm::env::APP_NAME;
 
 // This router is an attempt to make SPAs easy
 // Register and use pages here
 
 mod about;
 mod audio;
+mod collection;
 mod index;
 mod object;
+mod search;
 mod status;
 mod visual;
 mod writing;
 
 use about::AboutPage;
 use audio::AudioPage;
+use collection::CollectionPage;
 use index::IndexPage;
 use object::ObjectPage;
+use search::SearchPage;
 use status::StatusPage;
 use visual::VisualPage;
 use writing::WritingPage;
@@ -65,6 +72,8 @@ impl IntoView for PageContext {
                 "writing" => WritingPage::from_ctx(self),
                 "audio" => AudioPage::from_ctx(self),
                 "visual" => VisualPage::from_ctx(self),
+                "collection" => CollectionPage::from_ctx(self),
+                "search" => SearchPage::from_ctx(self),
                 "status" => StatusPage::from_ctx(self),
                 _ => IndexPage::from_ctx(self),
             },
@@ -89,8 +98,13 @@ pub fn InternalRouter() -> impl IntoView {
         <Router>
                 <input type="checkbox" id="drawer-toggle" name="drawer-toggle"/>
                 <label for="drawer-toggle" id="drawer-toggle-label"></label>
-                <header><InternalLink query="".to_string()  msg={APP_NAME.to_string()}/></header>
+                <header><InternalLink query="".to_string()  msg={app_config().name.clone()}/></header>
                 <main>
+                    {app_config().overridden.then(|| view! {
+                        <p class="bg-yellow-300 text-black font-bold p-2">
+                            "This page was opened with a configuration from its url, so it may not be showing this site's own content"
+                        </p>
+                    })}
                     <PageRoute/>
                 </main>
                 <nav id="drawer">
@@ -100,6 +114,8 @@ pub fn InternalRouter() -> impl IntoView {
                         <li><InternalLink query="?route=writing".to_string()  msg="Writing".to_string()/></li>
                         <li><InternalLink query="?route=audio".to_string()  msg="Audio".to_string()/></li>
                         <li><InternalLink query="?route=visual".to_string()  msg="Visual".to_string()/></li>
+                        <li><InternalLink query="?route=collection".to_string()  msg="Browse".to_string()/></li>
+                        <li><InternalLink query="?route=search".to_string()  msg="Search".to_string()/></li>
                         <li><InternalLink query="?route=status".to_string()  msg="Status".to_string()/></li>
                     </ul>
                 </nav>
@@ -112,13 +128,14 @@ pub fn InternalRouter() -> impl IntoView {
 fn PageRoute() -> impl IntoView {
     let (route, _) = create_query_signal::<String>("route");
     let (query, _) = create_query_signal::<String>("query");
+    let (path, _) = create_query_signal::<String>("path");
 
     let ctx = create_resource(
         || (),
         move |_| async move {
             // TODO: move device init out of here, but works for now
-            // let device = WasmDevice::new().map_err(PageError::WasmDevice)?;
-            let device = match WasmDevice::new().map_err(PageError::WasmDevice) {
+            // let device = WasmDevice::new(app_config()).map_err(PageError::WasmDevice)?;
+            let device = match WasmDevice::new(app_config()).map_err(PageError::WasmDevice) {
                 Ok(device) => device,
                 Err(e) => {
                     return PageContextResource {
@@ -145,11 +162,24 @@ fn PageRoute() -> impl IntoView {
                     error_message: Some(PageError::NoRootCid.to_string()),
                 };
             }
-            let manifest = match device
-                .read_manifest(&root_cid)
-                .await
-                .map_err(PageError::ManifestRead)
-            {
+            // Pages about a single directory or object only need the part of the manifest
+            // within it, which sharded manifests can read without fetching every shard
+            let within = match route.as_deref() {
+                Some("collection") => Some(PathBuf::from(query.clone().unwrap_or_default())),
+                Some("object") => path.get().map(|path| {
+                    let path = PathBuf::from(path);
+                    path.parent().map(Path::to_path_buf).unwrap_or_default()
+                }),
+                _ => None,
+            };
+            let manifest = match within {
+                Some(dir) => device
+                    .read_manifest_within(&root_cid, &dir)
+                    .await
+                    .map(Option::unwrap_or_default),
+                None => device.read_manifest(&root_cid).await,
+            };
+            let mut manifest = match manifest.map_err(PageError::ManifestRead) {
                 Ok(manifest) => manifest,
                 Err(e) => {
                     return PageContextResource {
@@ -159,6 +189,10 @@ fn PageRoute() -> impl IntoView {
                 }
             };
 
+            // Scheduled objects are pushed ahead of time, and only shown from their date on
+            let now = Utc::now();
+            manifest.retain_objects(|object| object.publication().is_published_at(&now));
+
             let ctx = PageContext {
                 root_cid,
                 chain_id,
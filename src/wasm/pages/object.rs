@@ -4,12 +4,20 @@ use std::str::FromStr;
 use cid::Cid;
 use leptos::*;
 use leptos_use::use_event_listener;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+use crate::types::content_type::Viewer;
+use crate::types::encryption::{self, Encryption, SecretKey};
+use crate::types::front_matter;
+use crate::types::markdown;
+use crate::types::media::Media;
 use crate::types::schema::Schemas;
-use crate::types::{Manifest, Object};
-use crate::wasm::utils::{gateway_url, markdown_to_html, object_url};
+use crate::types::{Derived, Manifest, Object};
+use crate::wasm::utils::{
+    blob_url, gateway_url, markdown_to_html, object_url, read_object_data, store_encryption_key,
+    stored_encryption_key, verified_url,
+};
 
 use super::{Page, PageContext};
 
@@ -111,11 +119,36 @@ pub async fn render_object_view(
     path: &PathBuf,
     object: &Object,
 ) -> impl IntoView {
-    let metadata = object.metadata();
     let date = object.updated_at().date_naive().to_string();
-    let schema = Schemas::try_from(metadata.clone())
-        .map_err(|_| {
-            // If this is not an object we know how to render, just
+    // Encrypted objects can only be rendered once the reader gives us their key
+    if let Some(encryption) = object.encryption() {
+        let title = object.metadata()["title"]
+            .as_str()
+            .map(String::from)
+            .unwrap_or(path.display().to_string());
+        return render_encrypted_view(path, object, encryption, title, date).into_view();
+    }
+    let metadata = match manifest.schemas().validate(object.metadata()) {
+        Ok(metadata) => metadata,
+        // Untagged objects we know how to show get a page of their own
+        Err(_) if Viewer::for_object(object, path) != Viewer::Download => {
+            let title = path.display().to_string();
+            let content = object_content_view(manifest, path, object).await;
+            return view! {
+                <div>
+                    <h1 class="text-3xl font-bold italic bg-gray-800 p-2">
+                        {title}
+                        <div class="text-sm font-normal text-gray-200">
+                            <p>Last updated: {date}</p>
+                        </div>
+                    </h1>
+                    {content}
+                </div>
+            }
+            .into_view();
+        }
+        Err(_) => {
+            // If this object doesn't match any of our schemas, just
             // forward them to the IPFS gateway
             let url = object_url(object);
             web_sys::window()
@@ -124,11 +157,11 @@ pub async fn render_object_view(
                 .set_href(&url)
                 .expect("href");
             return view! { <div> </div> }.into_view();
-        })
-        .expect("valid schema");
+        }
+    };
 
-    let content = match schema {
-        Schemas::Writing(writing) => {
+    let content = match Schemas::try_from(metadata.clone()) {
+        Ok(Schemas::Writing(writing)) => {
             let html = object_markdown_to_html(manifest, path).await;
             view! {
                 <div>
@@ -143,9 +176,31 @@ pub async fn render_object_view(
                 </div>
             }
         }
-        Schemas::Visual(visual) => {
-            let url = object_url(object);
-            let html = format!(r#"<img src="{url}"/>"#, url = url);
+        Ok(Schemas::Visual(visual)) => {
+            let (cid, content_type, size, details) = match object.media() {
+                Some(Media::Image(info)) => {
+                    // Only read the smallest variant that fills the page. This stands in for
+                    //  srcset, which would have the browser load variants unverified. Variants
+                    //  may have been re-encoded, so the browser is left to work out their type
+                    let (cid, content_type) = match info.fitting(page_width()) {
+                        Some(variant) => (variant.cid, None),
+                        None => (*object.cid(), object.content_type()),
+                    };
+                    let size = format!(r#" width="{}" height="{}""#, info.width, info.height);
+                    let mut details =
+                        vec![format!("{}×{} {}", info.width, info.height, info.format)];
+                    details.extend(info.camera.clone());
+                    details.extend(info.taken_at.clone());
+                    (cid, content_type, size, Some(details.join(", ")))
+                }
+                _ => (*object.cid(), object.content_type(), String::new(), None),
+            };
+            let url = match verified_url(&cid, content_type).await {
+                Ok(url) => url,
+                Err(err) => return view! { <p class="p-2 text-red-500">{err}</p> }.into_view(),
+            };
+            // Size the image up front, so the page doesn't jump around while it loads
+            let html = format!(r#"<img src="{}"{}/>"#, url, size);
             view! {
                 <div>
                     <h1 class="text-3xl font-bold italic bg-gray-800 p-2">
@@ -153,17 +208,27 @@ pub async fn render_object_view(
                         <div class="text-sm font-normal text-gray-200">
                             <p>Last updated: {date}</p>
                             <p>{visual.location}, {visual.medium}</p>
+                            {details.map(|details| view! { <p>{details}</p> })}
                         </div>
                     </h1>
                     <div class="prose max-w-none p-10" inner_html=html/>
                 </div>
             }
         }
-        Schemas::Audio(audio_obj) => {
-            let url = object_url(object);
+        Ok(Schemas::Audio(audio_obj)) => {
+            let url = match verified_url(object.cid(), object.content_type()).await {
+                Ok(url) => url,
+                Err(err) => return view! { <p class="p-2 text-red-500">{err}</p> }.into_view(),
+            };
             let audio_ref: NodeRef<html::Audio> = create_node_ref::<html::Audio>();
             let button_ref: NodeRef<html::Button> = create_node_ref::<html::Button>();
             let slider_ref: NodeRef<html::Input> = create_node_ref::<html::Input>();
+            let audio_info = match object.media() {
+                Some(Media::Audio(info)) => Some(info.clone()),
+                _ => None,
+            };
+            let duration = audio_info.as_ref().map(|info| info.display_duration());
+            let max = audio_info.map(|info| info.duration_secs());
 
             audio_ref.on_load(move |_| {
                 button_ref.on_load(move |_| {
@@ -187,7 +252,10 @@ pub async fn render_object_view(
                     slider.set_min("0");
                     slider.set_value("0");
                     slider.set_step("any");
-                    slider.set_max(&audio.duration().to_string());
+                    // Prefer the duration we extracted when staging, since the
+                    // audio's own isn't known until its metadata has loaded
+                    let max = max.unwrap_or_else(|| audio.duration());
+                    slider.set_max(&max.to_string());
                     let _ = use_event_listener(slider_ref, leptos::ev::input, move |_| {
                         let slider = slider_ref.get().expect("slider");
                         let audio = audio_ref.get().expect("audio");
@@ -208,6 +276,7 @@ pub async fn render_object_view(
                         <div class="text-sm font-normal text-gray-200">
                             <p>Last updated: {date}</p>
                             <p>{audio_obj.project.to_string()}</p>
+                            {duration.map(|duration| view! { <p>{duration}</p> })}
                         </div>
                     </h1>
                     <div class="flex flex-col items-center justify-center space-y-4">
@@ -231,6 +300,44 @@ pub async fn render_object_view(
                 </div>
             }
         }
+        // Otherwise, render the fields of whichever schema it was tagged with
+        Err(_) => {
+            let content = object_content_view(manifest, path, object).await;
+            let title = metadata["title"]
+                .as_str()
+                .map(String::from)
+                .unwrap_or(path.display().to_string());
+            let schema = metadata["type"]
+                .as_str()
+                .and_then(|name| manifest.schemas().get(name))
+                .expect("validated schema");
+            let fields = schema
+                .fields()
+                .iter()
+                .filter(|field| field.name() != "title")
+                .filter_map(|field| {
+                    let value = match &metadata[field.name()] {
+                        Value::Null => return None,
+                        Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    };
+                    let label = field.name().to_string();
+                    Some(view! { <p>{label}: {value}</p> })
+                })
+                .collect_view();
+            view! {
+                <div>
+                    <h1 class="text-3xl font-bold italic bg-gray-800 p-2">
+                        {title}
+                        <div class="text-sm font-normal text-gray-200">
+                            <p>Last updated: {date}</p>
+                            {fields}
+                        </div>
+                    </h1>
+                    {content}
+                </div>
+            }
+        }
     };
     view! {
         <div>
@@ -240,56 +347,179 @@ pub async fn render_object_view(
     .into_view()
 }
 
-/// Replace all links to assets within the filesystem with links to the IPFS gateway
-/// This is a hack to get around the fact that we don't have a way to resolve links
-/// within our Manifest yet, since we decide we HATE unix-fs
-async fn object_markdown_to_html(manifest: &Manifest, object_path: &PathBuf) -> String {
-    let objects = manifest.objects();
-    let object = objects.get(object_path).unwrap();
-    let url = object_url(object);
-    let object_content = reqwest::get(url)
-        .await
-        .expect("object_content")
-        .text()
-        .await
-        .expect("object_content");
-    let base_path = object_path.parent().expect("base_path");
+/// Render an encrypted object behind a form for the reader's secret key.
+/// The key is kept in local storage, and objects are decrypted in the browser
+fn render_encrypted_view(
+    path: &PathBuf,
+    object: &Object,
+    encryption: &Encryption,
+    title: String,
+    date: String,
+) -> impl IntoView {
+    let cid = *object.cid();
+    let encryption = encryption.clone();
+    let viewer = Viewer::for_object(object, path);
+    let content_type = object.content_type().map(str::to_string);
+    let strip = matches!(object.derived(), Some(Derived::FrontMatter { strip: true }));
+    let key = create_rw_signal(stored_encryption_key());
+    let key_input: NodeRef<html::Input> = create_node_ref::<html::Input>();
+    let key_error = create_rw_signal(None::<String>);
 
-    // First find all occurences of ./ and retrieve the object url from the manifest
-    // Then replace the ./ with a link to the Ipfs gateway
-    let re = Regex::new(r#"src="./([^"]+)"#).unwrap();
-    let mut result = object_content.clone();
+    let on_submit = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let value = key_input.get().expect("key input").value();
+        match value.parse::<SecretKey>() {
+            Ok(secret) => {
+                store_encryption_key(&secret);
+                key_error.set(None);
+                key.set(Some(secret));
+            }
+            Err(err) => key_error.set(Some(err.to_string())),
+        }
+    };
 
-    for caps in re.captures_iter(&object_content) {
-        if let Some(cap) = caps.get(1) {
-            let path = PathBuf::from(cap.as_str());
-            let path = base_path.join(path);
-            let normalized_path = normalize_path(&path);
-            if let Some(object) = objects.get(&normalized_path) {
-                let url = object_url(object);
+    // Resources need to compare their sources, which we'd rather not do with keys themselves
+    let decrypted = create_local_resource(
+        move || key.get().map(|secret| secret.public_key().to_string()),
+        move |public_key| {
+            let encryption = encryption.clone();
+            async move {
+                public_key?;
+                let secret = key.get_untracked()?;
+                let sealed = read_object_data(&cid).await.ok()?;
+                Some(
+                    encryption::decrypt(&sealed, &encryption, &secret)
+                        .map_err(|err| err.to_string()),
+                )
+            }
+        },
+    );
 
-                let old = format!(r#"src="./{}""#, cap.as_str());
-                let new = format!(r#"src="{}""#, url);
-                result = result.replace(&old, &new);
+    let content = move || match decrypted.get().flatten() {
+        None => view! {
+            <form class="p-2 space-y-2" on:submit=on_submit>
+                <p>"This is private. Enter your key to read it."</p>
+                <input
+                    type="password"
+                    class="w-full p-2 bg-gray-800 rounded"
+                    placeholder="Secret key"
+                    node_ref=key_input
+                />
+                <button
+                    class="bg-gray-500 hover:bg-gray-600 font-bold py-2 px-4 rounded"
+                    type="submit"
+                >
+                    "Decrypt"
+                </button>
+                {move || key_error.get().map(|err| view! { <p class="text-red-500">{err}</p> })}
+            </form>
+        }
+        .into_view(),
+        Some(Err(err)) => view! { <p class="p-2 text-red-500">{err}</p> }.into_view(),
+        Some(Ok(data)) => match viewer {
+            Viewer::Markdown => {
+                let text = String::from_utf8_lossy(&data).to_string();
+                let text = if strip {
+                    front_matter::strip(&text).to_string()
+                } else {
+                    text
+                };
+                let html = markdown_to_html(&text);
+                view! { <div class="prose max-w-none p-2 md" inner_html=html/> }.into_view()
             }
+            Viewer::Text => {
+                let text = String::from_utf8_lossy(&data).to_string();
+                view! { <pre class="whitespace-pre-wrap p-2">{text}</pre> }.into_view()
+            }
+            viewer => media_view(
+                viewer,
+                blob_url(&data, content_type.as_deref()).unwrap_or_default(),
+            ),
+        },
+    };
+
+    view! {
+        <div>
+            <h1 class="text-3xl font-bold italic bg-gray-800 p-2">
+                {title}
+                <div class="text-sm font-normal text-gray-200">
+                    <p>Last updated: {date}</p>
+                </div>
+            </h1>
+            {content}
+        </div>
+    }
+}
+
+/// Show an object's content inline, however its content type calls for
+async fn object_content_view(manifest: &Manifest, path: &PathBuf, object: &Object) -> View {
+    match Viewer::for_object(object, path) {
+        Viewer::Markdown => {
+            let html = object_markdown_to_html(manifest, path).await;
+            view! { <div class="prose max-w-none p-2 md" inner_html=html/> }.into_view()
         }
+        Viewer::Text => {
+            let text = match read_object_data(object.cid()).await {
+                Ok(data) => String::from_utf8_lossy(&data).to_string(),
+                Err(err) => err,
+            };
+            view! { <pre class="whitespace-pre-wrap p-2">{text}</pre> }.into_view()
+        }
+        viewer => match verified_url(object.cid(), object.content_type()).await {
+            Ok(url) => media_view(viewer, url),
+            Err(err) => view! { <p class="p-2 text-red-500">{err}</p> }.into_view(),
+        },
     }
+}
 
-    let html = markdown_to_html(result);
-    html
+/// How many device pixels wide images are shown. They never take up more than 1280px
+fn page_width() -> u32 {
+    let window = web_sys::window().expect("window");
+    let width = window
+        .inner_width()
+        .ok()
+        .and_then(|width| width.as_f64())
+        .unwrap_or(1280.0);
+    (width.min(1280.0) * window.device_pixel_ratio()) as u32
 }
 
-fn normalize_path(path: &PathBuf) -> PathBuf {
-    let mut normalized_path = PathBuf::new();
-    for component in path.components() {
-        match component {
-            std::path::Component::ParentDir => {
-                normalized_path.pop();
-            }
-            _ => {
-                normalized_path.push(component);
-            }
+/// Show content the browser can load from a url by itself
+fn media_view(viewer: Viewer, url: String) -> View {
+    match viewer {
+        Viewer::Image => view! { <img class="p-10" src=url/> }.into_view(),
+        Viewer::Audio => view! { <audio class="w-full p-2" controls src=url/> }.into_view(),
+        Viewer::Video => view! { <video class="w-full p-2" controls src=url/> }.into_view(),
+        Viewer::Pdf => view! { <iframe class="w-full h-screen p-2" src=url/> }.into_view(),
+        _ => view! {
+            <div class="p-2">
+                <a href=url class="hover:text-gray-400 underline">"Open"</a>
+            </div>
         }
+        .into_view(),
     }
-    normalized_path
+}
+
+/// Replace all links to assets within the filesystem with links to the IPFS gateway
+/// This is a hack to get around the fact that we don't have a way to resolve links
+/// within our Manifest yet, since we decide we HATE unix-fs
+async fn object_markdown_to_html(manifest: &Manifest, object_path: &PathBuf) -> String {
+    let objects = manifest.objects();
+    let object = objects.get(object_path).unwrap();
+    let object_content = match read_object_data(object.cid()).await {
+        Ok(data) => String::from_utf8_lossy(&data).to_string(),
+        Err(err) => return format!("<p>{}</p>", err),
+    };
+    // Hide front matter we've already pulled into the object's metadata, if asked to
+    let object_content = match object.derived() {
+        Some(Derived::FrontMatter { strip: true }) => {
+            front_matter::strip(&object_content).to_string()
+        }
+        _ => object_content,
+    };
+    // Link images and media the object refers to relatively to their copies on the gateway
+    let content =
+        markdown::link_relative_sources(&object_content, object_path, objects, |_, object| {
+            object_url(object)
+        });
+    markdown_to_html(&content)
 }
@@ -0,0 +1,119 @@
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+use crate::types::search::SearchIndex;
+use crate::types::Manifest;
+use crate::wasm::components::{ErrorMessageBox, ObjectLink};
+use crate::wasm::config::app_config;
+use crate::wasm::device::WasmDevice;
+
+use super::{Page, PageContext};
+
+/// How many results to list at most
+const MAX_RESULTS: usize = 25;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SearchPage(PageContext);
+
+impl Page for SearchPage {
+    fn ctx(&self) -> &PageContext {
+        &self.0
+    }
+    fn from_ctx(ctx: PageContext) -> Box<dyn Page> {
+        Box::new(Self(ctx))
+    }
+    fn into_view_ref(&self) -> View {
+        self.clone().into_view()
+    }
+}
+
+impl IntoView for SearchPage {
+    fn into_view(self) -> View {
+        let search_cid = match self.ctx().manifest().search() {
+            Some(cid) => *cid,
+            None => {
+                return view! {
+                    <div>
+                        <p>
+                            "Oh no! This site hasn't published a search index yet!"
+                        </p>
+                    </div>
+                }
+                .into_view()
+            }
+        };
+        let query = create_rw_signal(self.ctx().query().clone().unwrap_or_default());
+        let manifest = self.ctx().manifest().clone();
+
+        // The index is only fetched from the gateway once, then searched as you type
+        let index = create_resource(
+            || (),
+            move |_| async move {
+                let device = WasmDevice::new(app_config()).map_err(|e| e.to_string())?;
+                device
+                    .read_search_index(&search_cid)
+                    .await
+                    .map_err(|e| e.to_string())
+            },
+        );
+
+        let results = move || match index.get() {
+            None => view! { <p>"Loading..."</p> }.into_view(),
+            Some(Err(msg)) => view! { <ErrorMessageBox msg=msg/> }.into_view(),
+            Some(Ok(index)) => results_view(&index, &manifest, &query.get()),
+        };
+
+        view! {
+            <div>
+                <h1 class="text-3xl font-bold italic bg-gray-800 p-2">
+                    Search
+                    <div class="text-sm font-normal text-gray-200">
+                        <input
+                            type="search"
+                            class="w-full mt-2 p-2 bg-gray-700 rounded"
+                            placeholder="Search titles, descriptions, and writing"
+                            prop:value=query
+                            on:input=move |ev| query.set(event_target_value(&ev))
+                        />
+                    </div>
+                </h1>
+                <div class="p-2">
+                    {results}
+                </div>
+            </div>
+        }
+        .into_view()
+    }
+}
+
+/// List the best matches for a query. The index covers scheduled objects too,
+/// so only results still in the (published) manifest are listed
+fn results_view(index: &SearchIndex, manifest: &Manifest, query: &str) -> View {
+    if query.trim().is_empty() {
+        return view! { <span></span> }.into_view();
+    }
+    let mut results = index.search(query);
+    results.retain(|(document, _score)| manifest.objects().contains_key(&document.path));
+    if results.is_empty() {
+        return view! { <p>"Nothing matched your search"</p> }.into_view();
+    }
+    let results = results
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(document, _score)| {
+            let link = ObjectLink {
+                cid: document.cid,
+                path: document.path.clone(),
+                title: document.title.clone(),
+            };
+            let path = document.path.display().to_string();
+            view! {
+                <li>
+                    {link}
+                    <span class="text-sm text-gray-400">" " {path}</span>
+                </li>
+            }
+        })
+        .collect_view();
+    view! { <ul>{results}</ul> }.into_view()
+}
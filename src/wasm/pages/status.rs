@@ -82,6 +82,24 @@ impl IntoView for StatusPage {
                             }
                         </span>
                     </p>
+                    <p>
+                        <strong class="font-bold">Feeds:</strong>
+                        {
+                            self.ctx()
+                                .manifest()
+                                .feeds()
+                                .iter()
+                                .map(|(name, cid)| {
+                                    view! {
+                                        <a href=gateway_url(cid) target="_blank" class="link">
+                                            "   "
+                                            {name.clone()}
+                                        </a>
+                                    }
+                                })
+                                .collect_view()
+                        }
+                    </p>
                 </div>
                 <StatusRowTable items=items/>
             </div>
@@ -108,6 +126,7 @@ impl From<(&PathBuf, &Object)> for StatusRow {
             id,
             path: ObjectLink {
                 cid: object.cid().clone(),
+                path: path.clone(),
                 title: format!("{}", path.display()),
             },
             date: object.created_at().date_naive(),
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use async_trait::async_trait;
 use chrono::naive::NaiveDate;
 use cid::Cid;
@@ -5,8 +7,9 @@ use leptos::*;
 use leptos_struct_table::*;
 use serde::{Deserialize, Serialize};
 
+use crate::types::media::Media;
 use crate::types::{Object, Visual};
-use crate::wasm::components::ObjectLink;
+use crate::wasm::components::{ObjectLink, Thumbnail};
 
 use super::{Page, PageContext};
 
@@ -40,7 +43,7 @@ impl IntoView for VisualPage {
                         Err(_) => false,
                     }
                 })
-                .map(|(_path, object)| object.into())
+                .map(|item| item.into())
                 .collect::<Vec<VisualRow>>();
             visual.sort_by(|a, b| b.date.cmp(&a.date));
             visual
@@ -67,23 +70,43 @@ pub struct VisualRow {
     #[table(key, skip)]
     id: Cid,
     #[table(head_class = "p-2", cell_class = "p-2")]
+    thumbnail: Thumbnail,
+    #[table(head_class = "p-2", cell_class = "p-2")]
     title: ObjectLink,
     #[table(head_class = "p-2", cell_class = "p-2")]
+    dimensions: String,
+    #[table(head_class = "p-2", cell_class = "p-2")]
     date: NaiveDate,
 }
 
-impl From<&Object> for VisualRow {
-    fn from(object: &Object) -> Self {
+impl From<(&PathBuf, &Object)> for VisualRow {
+    fn from(item: (&PathBuf, &Object)) -> Self {
+        let (path, object) = item;
         let id = object.cid().clone();
         let metadata = object.metadata();
         // Note: it's gaurnateed that this will succeed at this point
         let audio = Visual::try_from(metadata.clone()).expect("valid writing schema");
+        let info = match object.media() {
+            Some(Media::Image(info)) => Some(info),
+            _ => None,
+        };
         Self {
             id,
+            // Listings only ever load thumbnails, never the originals
+            thumbnail: Thumbnail {
+                cid: info
+                    .and_then(|info| info.thumbnail())
+                    .map(|thumbnail| thumbnail.cid),
+                alt: audio.title.clone(),
+            },
             title: ObjectLink {
                 cid: object.cid().clone(),
+                path: path.clone(),
                 title: audio.title,
             },
+            dimensions: info
+                .map(|info| format!("{}×{}", info.width, info.height))
+                .unwrap_or_default(),
             date: object.created_at().date_naive(),
         }
     }
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use async_trait::async_trait;
 use chrono::naive::NaiveDate;
 use cid::Cid;
@@ -39,7 +41,7 @@ impl IntoView for WritingPage {
                         Err(_) => false,
                     }
                 })
-                .map(|(_path, object)| object.into())
+                .map(|item| item.into())
                 .collect::<Vec<WritingRow>>();
             writing.sort_by(|a, b| b.date.cmp(&a.date));
             writing
@@ -74,8 +76,9 @@ pub struct WritingRow {
     date: NaiveDate,
 }
 
-impl From<&Object> for WritingRow {
-    fn from(object: &Object) -> Self {
+impl From<(&PathBuf, &Object)> for WritingRow {
+    fn from(item: (&PathBuf, &Object)) -> Self {
+        let (path, object) = item;
         let id = object.cid().clone();
         let metadata = object.metadata();
         // Note: it's gaurnateed that this will succeed at this point
@@ -84,6 +87,7 @@ impl From<&Object> for WritingRow {
             id,
             title: ObjectLink {
                 cid: object.cid().clone(),
+                path: path.clone(),
                 title: writing.title,
             },
             genre: writing.genre.to_string(),
@@ -1,33 +1,81 @@
 use cid::Cid;
-use pulldown_cmark::{html, Options, Parser};
+use url::Url;
 
+use crate::types::encryption::SecretKey;
+pub use crate::types::markdown::markdown_to_html;
 use crate::types::Object;
 
-use super::env::APP_IPFS_GATEWAY_URL;
+use super::config::app_config;
+use super::device::WasmDevice;
+
+/// Where a reader's secret key is kept between visits
+const ENCRYPTION_KEY_STORAGE_KEY: &str = "encryption_key";
 
 pub fn origin_url() -> String {
     web_sys::window().expect("window").origin()
 }
 
+/// The url relative paths resolve against -- wherever index.html is served from, which may be
+/// a subpath, or `/ipfs/<cid>/` on a gateway
+pub fn base_url() -> Option<Url> {
+    let base = web_sys::window()?.document()?.base_uri().ok()??;
+    Url::parse(&base).ok()
+}
+
 pub fn gateway_url(cid: &Cid) -> String {
-    format!("{}/ipfs/{}", APP_IPFS_GATEWAY_URL, cid.to_string())
+    format!("{}/ipfs/{}", app_config().ipfs_gateway_url, cid.to_string())
 }
 
 pub fn object_url(object: &Object) -> String {
     gateway_url(object.cid())
 }
 
-pub fn markdown_to_html(content: String) -> String {
-    let mut options = Options::empty();
-    options.insert(Options::ENABLE_STRIKETHROUGH);
-    options.insert(Options::ENABLE_TASKLISTS);
-    options.insert(Options::ENABLE_TABLES);
-    options.insert(Options::ENABLE_FOOTNOTES);
-    options.insert(Options::ENABLE_SMART_PUNCTUATION);
-    options.insert(Options::ENABLE_TASKLISTS);
+/// Read an object's content through the configured gateways, verified against its cid.
+/// Media the browser loads by url can't be verified, so prefer this, or `verified_url`,
+/// for anything we fetch
+pub async fn read_object_data(cid: &Cid) -> Result<Vec<u8>, String> {
+    let device = WasmDevice::new(app_config()).map_err(|e| e.to_string())?;
+    device
+        .read_ipfs_gateway_data(cid)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Read an object's content verified against its cid, and serve it from an object url.
+/// Media elements would otherwise load whatever the gateway gives them
+pub async fn verified_url(cid: &Cid, content_type: Option<&str>) -> Result<String, String> {
+    let data = read_object_data(cid).await?;
+    blob_url(&data, content_type).ok_or(format!("could not serve {}", cid))
+}
+
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// The secret key this reader decrypts private objects with, if they've given us one
+pub fn stored_encryption_key() -> Option<SecretKey> {
+    local_storage()?
+        .get_item(ENCRYPTION_KEY_STORAGE_KEY)
+        .ok()??
+        .parse()
+        .ok()
+}
+
+/// Remember a reader's secret key in local storage. It never leaves the browser
+pub fn store_encryption_key(key: &SecretKey) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(ENCRYPTION_KEY_STORAGE_KEY, &key.to_string());
+    }
+}
 
-    let parser = Parser::new_ext(&content, options);
-    let mut html = String::new();
-    html::push_html(&mut html, parser);
-    html
+/// Serve bytes we've decrypted in the browser from an object url, so media elements can load them.
+/// Browsers won't play some media without knowing its content type, so pass it when we know it
+pub fn blob_url(data: &[u8], content_type: Option<&str>) -> Option<String> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let mut options = web_sys::BlobPropertyBag::new();
+    if let Some(content_type) = content_type {
+        options.type_(content_type);
+    }
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).ok()?;
+    web_sys::Url::create_object_url_with_blob(&blob).ok()
 }